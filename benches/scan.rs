@@ -0,0 +1,53 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use sqlr::db::Db;
+
+// people spans several leaf pages under an interior one
+const FIXTURE: &str = "test_index.db";
+const TABLE: &str = "people";
+
+fn row_count() -> u64 {
+    let mut db = Db::from_file(FIXTURE).unwrap();
+    let mut scanner = db.scanner_for(TABLE).unwrap();
+    let mut count = 0;
+    while scanner.next_record().unwrap().is_some() {
+        count += 1;
+    }
+    count
+}
+
+fn scan(c: &mut Criterion) {
+    let bytes = std::fs::read(FIXTURE).unwrap();
+    let mut group = c.benchmark_group("scan");
+    // reported as rows per second
+    group.throughput(Throughput::Elements(row_count()));
+
+    // every page is loaded and parsed again
+    group.bench_function("next_record cold", |b| {
+        b.iter_batched(
+            || Db::from_bytes(bytes.clone()).unwrap(),
+            |mut db| {
+                let mut scanner = db.scanner_for(TABLE).unwrap();
+                while scanner.next_record().unwrap().is_some() {}
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    // every page is served from the pager's cache
+    let mut db = Db::from_bytes(bytes.clone()).unwrap();
+    group.bench_function("next_record cached", |b| {
+        b.iter(|| {
+            let mut scanner = db.scanner_for(TABLE).unwrap();
+            while scanner.next_record().unwrap().is_some() {}
+        })
+    });
+
+    group.bench_function("count(*)", |b| {
+        b.iter(|| db.execute("SELECT COUNT(*) FROM people").unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, scan);
+criterion_main!(benches);
@@ -1,22 +1,121 @@
-use crate::{record::record_header::RecordHeader, value::Value};
+use std::rc::Rc;
 
-#[derive(Debug)]
+use crate::{
+    record::{record_field_type::RecordFieldType, record_header::RecordHeader},
+    value::Value,
+};
+
+#[derive(Debug, Clone)]
 pub struct Cursor {
     pub header: RecordHeader,
     pub payload: Vec<u8>,
+    // always 0 for records read from an index b-tree, e.g. WITHOUT ROWID tables
+    pub row_id: i64,
+    // index of the INTEGER PRIMARY KEY column, stored as null in the record
+    pub rowid_alias: Option<usize>,
+    // position in the record of each column when it differs from the declaration order
+    pub column_order: Option<Rc<[usize]>>,
+    // the value of each column missing from the record, see Table::defaults
+    pub defaults: Option<Rc<[Value<'static>]>>,
+    // a record of another table joined to this one, its columns are numbered from the given
+    // one on
+    pub joined: Option<(usize, Box<Cursor>)>,
 }
 
 impl Cursor {
-    pub fn new(header: RecordHeader, payload: Vec<u8>) -> Self {
-        Self { header, payload }
+    pub fn new(header: RecordHeader, payload: Vec<u8>, row_id: i64) -> Self {
+        Self {
+            header,
+            payload,
+            row_id,
+            rowid_alias: None,
+            column_order: None,
+            defaults: None,
+            joined: None,
+        }
+    }
+
+    pub fn with_rowid_alias(mut self, rowid_alias: Option<usize>) -> Self {
+        self.rowid_alias = rowid_alias;
+        self
+    }
+
+    pub fn with_column_order(mut self, column_order: Option<Rc<[usize]>>) -> Self {
+        self.column_order = column_order;
+        self
+    }
+
+    pub fn with_defaults(mut self, defaults: Option<Rc<[Value<'static>]>>) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    // columns from the given one on are read from the joined record
+    pub fn with_joined(mut self, first_column: usize, joined: Cursor) -> Self {
+        self.joined = Some((first_column, Box::new(joined)));
+        self
+    }
+
+    // records written before an ALTER TABLE ADD COLUMN lack the new columns, but a record can't
+    // have more fields than its table has columns
+    pub fn check_field_count(&self) -> anyhow::Result<()> {
+        match &self.defaults {
+            Some(defaults) if self.header.len() > defaults.len() => anyhow::bail!(
+                "record {} has {} fields but its table has {} columns",
+                self.row_id,
+                self.header.len(),
+                defaults.len()
+            ),
+            _ => Ok(()),
+        }
     }
 
-    pub fn field(&self, n: usize) -> Option<Value> {
-        let record_field = self.header.fields.get(n)?;
+    pub fn field(&self, n: usize) -> anyhow::Result<Option<Value<'_>>> {
+        if let Some((first_column, joined)) = &self.joined {
+            if n >= *first_column {
+                return joined.field(n - first_column);
+            }
+        }
+        let i = match &self.column_order {
+            Some(column_order) => match column_order.get(n) {
+                Some(&i) => i,
+                None => return Ok(None),
+            },
+            None => n,
+        };
+        let Some(record_field) = self.header.fields.get(i) else {
+            return Ok(self.defaults.as_ref().and_then(|d| d.get(n)).cloned());
+        };
+        if self.rowid_alias == Some(n) && record_field.field_type == RecordFieldType::Null {
+            return Ok(Some(Value::Int(self.row_id)));
+        }
         record_field
             .field_type
             .value(&self.payload, record_field.offset)
     }
+
+    // unlike field, text and blobs are copied so the value outlives the cursor
+    pub fn field_owned(&self, n: usize) -> anyhow::Result<Option<Value<'static>>> {
+        Ok(self.field(n)?.map(Value::into_owned))
+    }
+
+    // every column of the record in declaration order, including the defaults of the columns
+    // missing from it
+    pub fn fields(&self) -> anyhow::Result<Vec<Value<'_>>> {
+        let count = match (&self.joined, &self.defaults, &self.column_order) {
+            (Some((first_column, _)), _, _) => *first_column,
+            (None, Some(defaults), _) => defaults.len(),
+            (None, None, Some(column_order)) => column_order.len(),
+            (None, None, None) => self.header.len(),
+        };
+        let mut fields = (0..count)
+            .map(|n| self.field(n).map(|v| v.unwrap_or(Value::Null)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if let Some((_, joined)) = &self.joined {
+            fields.extend(joined.fields()?);
+        }
+        Ok(fields)
+    }
 }
 
 #[cfg(test)]
@@ -36,7 +135,7 @@ mod test {
     use std::{borrow::Cow, io::Read};
 
     #[test]
-    fn cursor_field_tests() -> () {
+    fn cursor_field_tests() {
         let mut file = std::fs::File::open("test.db").unwrap();
         let mut header_buffer = [0; HEADER_SIZE];
         file.read_exact(&mut header_buffer).unwrap();
@@ -44,13 +143,94 @@ mod test {
         let mut pager = FilePager::new(file, db_header.page_size as usize);
         let page_nr = 1;
         let page = pager.read_page(page_nr).unwrap();
-        let cell = page.cells.get(0).unwrap();
-        let (header, payload) = match cell {
-            Cell::TableLeaf(c) => (RecordHeader::parse(&c.payload).unwrap(), c.payload.clone()),
-            Cell::TableInterior(c) => panic!("not a leaf: {:?}", c),
+        let cell = page.cells.first().unwrap();
+        let (header, payload, row_id) = match cell {
+            Cell::TableLeaf(c) => (
+                RecordHeader::parse(&c.payload).unwrap(),
+                c.payload.clone(),
+                c.row_id,
+            ),
+            c => panic!("not a table leaf: {:?}", c),
         };
-        let cursor = Cursor::new(header, payload);
-        assert_eq!(Some(Value::String(Cow::from("table"))), cursor.field(0));
-        assert_eq!(Some(Value::String(Cow::from("tbl1"))), cursor.field(1));
+        let cursor = Cursor::new(header, payload, row_id);
+        assert_eq!(
+            Some(Value::String(Cow::from("table"))),
+            cursor.field(0).unwrap()
+        );
+        assert_eq!(
+            Some(Value::String(Cow::from("tbl1"))),
+            cursor.field(1).unwrap()
+        );
+        assert_eq!(None, cursor.field(5).unwrap());
+    }
+
+    #[test]
+    fn cursor_field_owned_tests() {
+        // record with the string "a", then the blob [1, 2]
+        let payload = vec![3, 15, 16, 97, 1, 2];
+        let header = RecordHeader::parse(&payload).unwrap();
+        let cursor = Cursor::new(header, payload, 0);
+        let text = cursor.field_owned(0).unwrap();
+        let blob = cursor.field_owned(1).unwrap();
+        assert_eq!(None, cursor.field_owned(2).unwrap());
+        drop(cursor);
+        assert!(matches!(text, Some(Value::String(Cow::Owned(s))) if s == "a"));
+        assert!(matches!(blob, Some(Value::Blob(Cow::Owned(b))) if b == [1, 2]));
+    }
+
+    #[test]
+    fn cursor_rowid_alias_tests() {
+        // record with a null, then the string "a"
+        let payload = vec![3, 0, 15, 97];
+        let header = RecordHeader::parse(&payload).unwrap();
+        let cursor = Cursor::new(header.clone(), payload.clone(), 42);
+        assert_eq!(Some(Value::Null), cursor.field(0).unwrap());
+        let cursor = Cursor::new(header, payload, 42).with_rowid_alias(Some(0));
+        assert_eq!(Some(Value::Int(42)), cursor.field(0).unwrap());
+        assert_eq!(
+            Some(Value::String(Cow::from("a"))),
+            cursor.field(1).unwrap()
+        );
+    }
+
+    #[test]
+    fn cursor_column_order_tests() {
+        // record with the string "a", then 7
+        let payload = vec![3, 15, 1, 97, 7];
+        let header = RecordHeader::parse(&payload).unwrap();
+        let cursor = Cursor::new(header, payload, 0).with_column_order(Some(Rc::from([1, 0])));
+        assert_eq!(Some(Value::Int(7)), cursor.field(0).unwrap());
+        assert_eq!(
+            Some(Value::String(Cow::from("a"))),
+            cursor.field(1).unwrap()
+        );
+        assert_eq!(None, cursor.field(2).unwrap());
+    }
+
+    #[test]
+    fn cursor_defaults_tests() {
+        // record with the string "a", written before two columns were added
+        let payload = vec![2, 15, 97];
+        let header = RecordHeader::parse(&payload).unwrap();
+        let cursor = Cursor::new(header.clone(), payload.clone(), 1);
+        assert_eq!(None, cursor.field(1).unwrap());
+        assert_eq!(
+            vec![Value::String(Cow::from("a"))],
+            cursor.fields().unwrap()
+        );
+        let defaults = Rc::from([Value::Null, Value::Int(7), Value::Null]);
+        let cursor = Cursor::new(header, payload, 1).with_defaults(Some(defaults));
+        assert!(cursor.check_field_count().is_ok());
+        assert_eq!(Some(Value::Int(7)), cursor.field(1).unwrap());
+        assert_eq!(None, cursor.field(3).unwrap());
+        assert_eq!(
+            vec![Value::String(Cow::from("a")), Value::Int(7), Value::Null],
+            cursor.fields().unwrap()
+        );
+
+        let payload = vec![3, 15, 1, 97, 7];
+        let header = RecordHeader::parse(&payload).unwrap();
+        let cursor = Cursor::new(header, payload, 1).with_defaults(Some(Rc::from([Value::Null])));
+        assert!(cursor.check_field_count().is_err());
     }
 }
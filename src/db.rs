@@ -1,93 +1,2477 @@
-use std::{io::Read, path::Path};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    io::{Read, Seek},
+    path::Path,
+    rc::Rc,
+};
 
 use anyhow::Context;
 
 use crate::{
-    paging::{self, page_header, pager::FilePager},
+    cursor::Cursor,
+    error::SqlrError,
+    paging::{
+        self,
+        cell::Cell,
+        page::Page,
+        page_header,
+        page_stats::PageStats,
+        page_type::PageType,
+        pager::{FilePager, Pager},
+        pager_stats::PagerStats,
+        wal::Wal,
+    },
+    query::{
+        executor::{Plan, RowStream},
+        parser,
+        result_set::ResultSet,
+    },
+    record::{
+        index_record::IndexRecord, record_field_type::RecordFieldType, record_header::RecordHeader,
+    },
     scanner::Scanner,
+    schema::{
+        index::Index,
+        schema_object::SchemaObject,
+        sqlite_master::{self, Schema},
+        table::Table,
+        view_def::ViewDef,
+    },
     utils,
+    value::{Affinity, Value},
 };
 
+// https://www.sqlite.org/fileformat.html#the_database_header
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct DbHeader {
     pub page_size: u32,
+    // bytes at the end of every page which are left to extensions, usually 0
+    pub reserved_space: u8,
+    // incremented on every commit
+    pub change_counter: u32,
+    // the "in-header database size", in pages
+    pub database_size: u32,
+    // 0 when there are no free pages
+    pub first_freelist_trunk: u32,
+    pub freelist_count: u32,
+    // incremented every time the schema changes
+    pub schema_cookie: u32,
+    // 1 to 4, or 0 when there is no schema yet, DESC is only honored in indexes from format 4
+    pub schema_format: u32,
+    // non-zero in auto-vacuum and incremental-vacuum databases, which have pointer map pages
+    pub largest_root_page: u32,
+    // 1: utf-8, 2: utf-16le, 3: utf-16be
+    pub text_encoding: u32,
+    // set by applications through pragma user_version
+    pub user_version: i32,
+    // set by applications through pragma application_id to identify their file format
+    pub application_id: i32,
+    // value of the change counter when database_size was last written
+    pub version_valid_for: u32,
+    // 1: rollback journal, 2: wal
+    pub write_version: u8,
+    pub read_version: u8,
 }
 
 impl DbHeader {
     const HEADER_PREFIX: &'static [u8] = b"SQLite format 3\0";
+    const MAX_SCHEMA_FORMAT: u32 = 4;
     const HEADER_PAGE_SIZE_OFFSET: usize = 16;
+    const HEADER_WRITE_VERSION_OFFSET: usize = 18;
+    const HEADER_READ_VERSION_OFFSET: usize = 19;
+    const HEADER_RESERVED_SPACE_OFFSET: usize = 20;
+    const HEADER_CHANGE_COUNTER_OFFSET: usize = 24;
+    const HEADER_DATABASE_SIZE_OFFSET: usize = 28;
+    const HEADER_FIRST_FREELIST_TRUNK_OFFSET: usize = 32;
+    const HEADER_FREELIST_COUNT_OFFSET: usize = 36;
+    const HEADER_SCHEMA_COOKIE_OFFSET: usize = 40;
+    const HEADER_SCHEMA_FORMAT_OFFSET: usize = 44;
+    const HEADER_LARGEST_ROOT_PAGE_OFFSET: usize = 52;
+    const HEADER_TEXT_ENCODING_OFFSET: usize = 56;
+    const HEADER_USER_VERSION_OFFSET: usize = 60;
+    const HEADER_APPLICATION_ID_OFFSET: usize = 68;
+    const HEADER_VERSION_VALID_FOR_OFFSET: usize = 92;
 
-    pub fn parse(buffer: &[u8]) -> anyhow::Result<DbHeader> {
-        if !buffer.starts_with(Self::HEADER_PREFIX) {
-            let len = buffer.len().min(Self::HEADER_PREFIX.len());
-            let prefix = String::from_utf8_lossy(&buffer[..len]);
-            Err(anyhow::anyhow!("invalid header prefix: {prefix}"))
+    pub fn parse(buffer: &[u8]) -> crate::error::Result<DbHeader> {
+        // a short buffer is only reported as truncated when what's there looks like sqlite
+        let len = buffer.len().min(Self::HEADER_PREFIX.len());
+        if !Self::HEADER_PREFIX.starts_with(&buffer[..len]) {
+            Err(SqlrError::BadMagic {
+                expected: Self::HEADER_PREFIX,
+                actual: buffer[..len].to_vec(),
+            })
+        } else if buffer.len() < paging::page::HEADER_SIZE {
+            Err(SqlrError::TruncatedHeader {
+                expected: paging::page::HEADER_SIZE,
+                actual: buffer.len(),
+            })
         } else {
             let page_size_raw = utils::read_be_word_at(buffer, Self::HEADER_PAGE_SIZE_OFFSET).1;
             let page_size = match page_size_raw {
                 1 => Ok(page_header::PAGE_MAX_SIZE),
                 n if n.is_power_of_two() => Ok(n as u32),
-                _ => Err(anyhow::anyhow!(
-                    "page size is not a power of 2: {}",
-                    page_size_raw
-                )),
+                _ => Err(SqlrError::InvalidPageSize(page_size_raw)),
             };
-            page_size.map(|page_size| DbHeader { page_size })
+            let page_size = page_size?;
+            // the layout of records and indexes in a newer format is unknown
+            let schema_format =
+                utils::read_be_double_word_at(buffer, Self::HEADER_SCHEMA_FORMAT_OFFSET).1;
+            if schema_format > Self::MAX_SCHEMA_FORMAT {
+                return Err(SqlrError::UnsupportedSchemaFormat(schema_format));
+            }
+            Ok(DbHeader {
+                page_size,
+                reserved_space: buffer[Self::HEADER_RESERVED_SPACE_OFFSET],
+                change_counter: utils::read_be_double_word_at(
+                    buffer,
+                    Self::HEADER_CHANGE_COUNTER_OFFSET,
+                )
+                .1,
+                database_size: utils::read_be_double_word_at(
+                    buffer,
+                    Self::HEADER_DATABASE_SIZE_OFFSET,
+                )
+                .1,
+                first_freelist_trunk: utils::read_be_double_word_at(
+                    buffer,
+                    Self::HEADER_FIRST_FREELIST_TRUNK_OFFSET,
+                )
+                .1,
+                freelist_count: utils::read_be_double_word_at(
+                    buffer,
+                    Self::HEADER_FREELIST_COUNT_OFFSET,
+                )
+                .1,
+                schema_cookie: utils::read_be_double_word_at(
+                    buffer,
+                    Self::HEADER_SCHEMA_COOKIE_OFFSET,
+                )
+                .1,
+                schema_format,
+                largest_root_page: utils::read_be_double_word_at(
+                    buffer,
+                    Self::HEADER_LARGEST_ROOT_PAGE_OFFSET,
+                )
+                .1,
+                text_encoding: utils::read_be_double_word_at(
+                    buffer,
+                    Self::HEADER_TEXT_ENCODING_OFFSET,
+                )
+                .1,
+                user_version: utils::read_be_double_word_at(
+                    buffer,
+                    Self::HEADER_USER_VERSION_OFFSET,
+                )
+                .1 as i32,
+                application_id: utils::read_be_double_word_at(
+                    buffer,
+                    Self::HEADER_APPLICATION_ID_OFFSET,
+                )
+                .1 as i32,
+                version_valid_for: utils::read_be_double_word_at(
+                    buffer,
+                    Self::HEADER_VERSION_VALID_FOR_OFFSET,
+                )
+                .1,
+                write_version: buffer
+                    .get(Self::HEADER_WRITE_VERSION_OFFSET)
+                    .copied()
+                    .unwrap_or(0),
+                read_version: buffer
+                    .get(Self::HEADER_READ_VERSION_OFFSET)
+                    .copied()
+                    .unwrap_or(0),
+            })
+        }
+    }
+
+    // stands in for a header which can't be read, the rest is what a new database would have and
+    // the database size is left for the file size to give
+    fn assumed(page_size: u32) -> DbHeader {
+        DbHeader {
+            page_size,
+            reserved_space: 0,
+            change_counter: 0,
+            database_size: 0,
+            first_freelist_trunk: 0,
+            freelist_count: 0,
+            schema_cookie: 0,
+            schema_format: Self::MAX_SCHEMA_FORMAT,
+            largest_root_page: 0,
+            text_encoding: 1,
+            user_version: 0,
+            application_id: 0,
+            version_valid_for: 0,
+            write_version: 1,
+            read_version: 1,
+        }
+    }
+
+    // legacy writers don't maintain the database size, it can only be trusted when it was written
+    // along with the latest change
+    pub fn database_size_is_valid(&self) -> bool {
+        self.database_size != 0 && self.change_counter == self.version_valid_for
+    }
+
+    // the part of every page b-tree content can use
+    pub fn usable_size(&self) -> usize {
+        (self.page_size as usize).saturating_sub(self.reserved_space as usize)
+    }
+
+    // https://www.sqlite.org/fileformat.html#the_lock_byte_page
+    // the page holding the bytes from offset 2^30 on, which sqlite uses for locking and never
+    // stores anything in, only files over 1 GiB have one
+    pub fn lock_byte_page(&self) -> usize {
+        (1 << 30) / self.page_size as usize + 1
+    }
+
+    // https://www.sqlite.org/fileformat.html#pointer_map_or_ptrmap_pages
+    // the first one is page 2, each one is followed by the pages it has an entry for, one landing
+    // on the lock-byte page is moved to the page after it
+    pub fn is_ptrmap_page(&self, page_num: usize) -> bool {
+        let stride = self.usable_size() / 5 + 1;
+        let is_slot = |page_num: usize| page_num >= 2 && (page_num - 2).is_multiple_of(stride);
+        let lock_byte_page = self.lock_byte_page();
+        self.largest_root_page != 0
+            && page_num != lock_byte_page
+            && (is_slot(page_num) || (page_num == lock_byte_page + 1 && is_slot(lock_byte_page)))
+    }
+
+    // older formats ignore DESC, every index is stored in ascending order
+    pub fn honors_descending_indexes(&self) -> bool {
+        self.schema_format >= 4
+    }
+
+    pub fn is_wal(&self) -> bool {
+        self.write_version == 2 || self.read_version == 2
+    }
+
+    pub fn text_encoding_name(&self) -> &'static str {
+        match self.text_encoding {
+            1 => "utf8",
+            2 => "utf16le",
+            3 => "utf16be",
+            _ => "unknown",
         }
     }
 }
 
 #[derive(Debug)]
-pub struct Db {
-    pub header: DbHeader,
-    pager: FilePager,
+pub struct Db<P: Pager = FilePager> {
+    // read once when opening, the pager's page size is taken from it
+    header: DbHeader,
+    pager: P,
 }
 
 impl Db {
+    // a database in wal mode is read along with its -wal file when there is one, as that's where
+    // the latest committed pages live until they are checkpointed
     pub fn from_file(filename: impl AsRef<Path>) -> anyhow::Result<Db> {
-        let mut file = std::fs::File::open(filename.as_ref()).context("open db file")?;
+        let file = std::fs::File::open(filename.as_ref()).context("open db file")?;
+        let db = Self::from_reader(file)?;
+        if !db.header.is_wal() {
+            return Ok(db);
+        }
+
+        match open_wal(filename)? {
+            Some(wal) => db.with_wal(wal),
+            None => Ok(db),
+        }
+    }
+
+    // a way into files with a damaged header, pages are read with the given size and nothing else
+    // is taken from the header
+    pub fn from_file_with_page_size(
+        filename: impl AsRef<Path>,
+        page_size: usize,
+    ) -> anyhow::Result<Db> {
+        if !page_size.is_power_of_two() || !(512..=65536).contains(&page_size) {
+            anyhow::bail!(
+                "invalid page size {}: expected a power of 2 between 512 and 65536",
+                page_size
+            );
+        }
+        let file = std::fs::File::open(filename.as_ref()).context("open db file")?;
+        Ok(Db {
+            header: DbHeader::assumed(page_size as u32),
+            pager: FilePager::new(file, page_size),
+        })
+    }
+}
+
+impl Db<FilePager<std::io::Cursor<Vec<u8>>>> {
+    // for databases which are already in memory
+    pub fn from_bytes(bytes: Vec<u8>) -> anyhow::Result<Self> {
+        Self::from_reader(std::io::Cursor::new(bytes))
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl Db<paging::mmap_pager::MmapPager> {
+    // see MmapPager for what must hold while the file is mapped
+    pub fn from_file_mmap(filename: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mmap = paging::mmap_pager::map(filename.as_ref())?;
+        let header = DbHeader::parse(&mmap).context("parse db header")?;
+        // the mapping only covers the main file, the pages committed to the wal would be missed
+        if header.is_wal()
+            && open_wal(filename.as_ref())?.is_some_and(|wal| wal.database_size.is_some())
+        {
+            anyhow::bail!(
+                "can't map a database whose wal holds committed pages, open it as a file"
+            );
+        }
+        let pager = paging::mmap_pager::MmapPager::new(mmap, header.page_size as usize)
+            .with_reserved_space(header.reserved_space as usize);
+
+        Ok(Db { header, pager })
+    }
+
+    // like the file backed db, cells claiming larger payloads make their page fail to load
+    pub fn with_max_payload_size(self, max_payload_size: usize) -> Self {
+        Db {
+            header: self.header,
+            pager: self.pager.with_max_payload_size(max_payload_size),
+        }
+    }
 
-        let mut header_buffer = [0; paging::page::HEADER_SIZE];
-        file.read_exact(&mut header_buffer)
+    // like the file backed db, a last page cut short is padded with zeros
+    pub fn with_short_page_padding(self, pad_short_page: bool) -> Self {
+        Db {
+            header: self.header,
+            pager: self.pager.with_short_page_padding(pad_short_page),
+        }
+    }
+}
+
+impl<I: Read + Seek> Db<FilePager<I>> {
+    pub fn from_reader(mut input: I) -> anyhow::Result<Self> {
+        // a short read is left for the header parsing to report
+        let mut header_buffer = Vec::with_capacity(paging::page::HEADER_SIZE);
+        input
+            .by_ref()
+            .take(paging::page::HEADER_SIZE as u64)
+            .read_to_end(&mut header_buffer)
             .context("read db header")?;
 
         let header = DbHeader::parse(&header_buffer).context("parse db header")?;
 
-        let pager = FilePager::new(file, header.page_size as usize);
+        let pager = FilePager::new(input, header.page_size as usize)
+            .with_reserved_space(header.reserved_space as usize);
+
+        Ok(Db { header, pager })
+    }
+
+    // cells claiming larger payloads make their page fail to load, which protects against files
+    // crafted to make a reader allocate more than it can afford
+    pub fn with_max_payload_size(self, max_payload_size: usize) -> Self {
+        Db {
+            header: self.header,
+            pager: self.pager.with_max_payload_size(max_payload_size),
+        }
+    }
+
+    // a last page cut short by a truncated copy is padded with zeros so that a scan can go on
+    // over the cells which made it, instead of failing to load it
+    pub fn with_short_page_padding(self, pad_short_page: bool) -> Self {
+        Db {
+            header: self.header,
+            pager: self.pager.with_short_page_padding(pad_short_page),
+        }
+    }
+
+    // page 1 may have been rewritten in the wal, the header is read again from there
+    pub fn with_wal(self, wal: Wal<I>) -> anyhow::Result<Self> {
+        if wal.database_size.is_none() {
+            return Ok(self);
+        }
+        if wal.page_size != self.header.page_size as usize {
+            anyhow::bail!(
+                "wal page size {} doesn't match the database page size {}",
+                wal.page_size,
+                self.header.page_size
+            );
+        }
+
+        let mut pager = self.pager.with_wal(wal);
+        let page = pager.load_raw_page(1).context("read db header")?;
+        let header = DbHeader::parse(&page).context("parse db header")?;
 
         Ok(Db { header, pager })
     }
+}
+
+impl<P: Pager> Db<P> {
+    const SEQUENCE_TABLE: &'static str = "sqlite_sequence";
 
-    pub fn scanner(&mut self, page: usize) -> Scanner {
+    pub fn header(&self) -> &DbHeader {
+        &self.header
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.header.page_size as usize
+    }
+
+    pub fn scanner(&mut self, page: usize) -> Scanner<'_> {
         Scanner::new(&mut self.pager, page)
     }
+
+    pub fn schema_scanner(&mut self) -> Scanner<'_> {
+        self.scanner(sqlite_master::SCHEMA_PAGE)
+    }
+
+    // a single b-tree page, for inspecting the file's layout
+    pub fn page(&mut self, page_num: usize) -> anyhow::Result<Rc<Page>> {
+        let page_count = self.page_count()?;
+        if page_num == 0 || page_num > page_count {
+            anyhow::bail!("page {} is out of range 1..={}", page_num, page_count);
+        }
+        if page_num == self.header.lock_byte_page() {
+            anyhow::bail!("page {} is the lock-byte page", page_num);
+        }
+        self.pager
+            .read_page(page_num)
+            .with_context(|| format!("read page {}", page_num))
+    }
+
+    pub fn pager_stats(&self) -> PagerStats {
+        self.pager.stats()
+    }
+
+    pub fn schema(&mut self) -> anyhow::Result<Schema> {
+        let mut scanner = self.schema_scanner();
+        let mut objects = Vec::new();
+
+        while let Some(record) = scanner.next_record()? {
+            objects.push(SchemaObject::from_schema_record(&record)?);
+        }
+
+        Ok(Schema { objects })
+    }
+
+    pub fn tables(&mut self) -> anyhow::Result<Vec<Table>> {
+        let mut scanner = self.schema_scanner();
+        let mut tables = Vec::new();
+
+        while let Some(record) = scanner.next_record()? {
+            if let Some(table) = Table::from_schema_record(&record)? {
+                tables.push(table);
+            }
+        }
+
+        Ok(tables)
+    }
+
+    pub fn indexes(&mut self) -> anyhow::Result<Vec<Index>> {
+        let honors_descending = self.header.honors_descending_indexes();
+        let mut scanner = self.schema_scanner();
+        let mut indexes = Vec::new();
+
+        while let Some(record) = scanner.next_record()? {
+            if let Some(mut index) = Index::from_schema_record(&record)? {
+                if !honors_descending {
+                    index.descending.fill(false);
+                }
+                indexes.push(index);
+            }
+        }
+
+        Ok(indexes)
+    }
+
+    pub fn views(&mut self) -> anyhow::Result<Vec<ViewDef>> {
+        Ok(self
+            .schema()?
+            .objects
+            .iter()
+            .filter_map(ViewDef::from_schema_object)
+            .collect())
+    }
+
+    pub fn table(&mut self, name: &str) -> anyhow::Result<Table> {
+        if sqlite_master::is_schema_table(name) {
+            return Ok(sqlite_master::schema_table());
+        }
+        self.tables()?
+            .into_iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| anyhow::anyhow!("no such table: {}", name))
+    }
+
+    // catches partially copied files before a scan fails deep in the pager
+    pub fn check_size(&mut self) -> anyhow::Result<()> {
+        let file_size = self.pager.input_size()?;
+        let page_size = self.header.page_size as u64;
+
+        if self.header.database_size_is_valid() {
+            let expected = self.header.database_size as u64 * page_size;
+            if file_size < expected {
+                anyhow::bail!(
+                    "database is truncated: expected {} bytes ({} pages), got {}",
+                    expected,
+                    self.header.database_size,
+                    file_size
+                );
+            }
+            if file_size > expected {
+                anyhow::bail!(
+                    "database has {} trailing bytes past its {} pages",
+                    file_size - expected,
+                    self.header.database_size
+                );
+            }
+        } else if file_size % page_size != 0 {
+            // the header can't be trusted, the file size is all there is to go by
+            anyhow::bail!(
+                "database size {} is not a multiple of the page size {}",
+                file_size,
+                page_size
+            );
+        }
+
+        Ok(())
+    }
+
+    // mirrors a basic PRAGMA integrity_check, every problem found is reported instead of stopping
+    // at the first one
+    pub fn integrity_check(&mut self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let page_count = match self.page_count() {
+            Ok(page_count) => page_count,
+            Err(e) => {
+                problems.push(format!("cannot determine the page count: {:#}", e));
+                return problems;
+            }
+        };
+
+        let mut roots = vec![("sqlite_master".to_string(), sqlite_master::SCHEMA_PAGE)];
+        match self.schema_roots() {
+            Ok(schema_roots) => roots.extend(schema_roots),
+            Err(e) => problems.push(format!("cannot read the schema: {:#}", e)),
+        }
+
+        let mut used = HashSet::new();
+        for (name, root_page) in roots {
+            self.check_btree(&name, root_page, page_count, &mut used, &mut problems);
+        }
+        self.check_freelist(page_count, &mut used, &mut problems);
+
+        let lock_byte_page = self.header.lock_byte_page();
+        for page_num in 1..=page_count {
+            if !used.contains(&page_num)
+                && page_num != lock_byte_page
+                && !self.header.is_ptrmap_page(page_num)
+            {
+                problems.push(format!("page {} is never used", page_num));
+            }
+        }
+
+        problems
+    }
+
+    // where the bytes of the file go, the page count comes from the file size which must agree
+    // with the header, the lock-byte page isn't counted
+    pub fn page_stats(&mut self) -> anyhow::Result<PageStats> {
+        self.check_size()?;
+        let page_count = self.page_count()?;
+
+        let mut free = HashSet::new();
+        let mut problems = Vec::new();
+        self.check_freelist(page_count, &mut free, &mut problems);
+        if let Some(problem) = problems.into_iter().next() {
+            anyhow::bail!(problem);
+        }
+
+        let lock_byte_page = self.header.lock_byte_page();
+        let mut stats = PageStats::default();
+        for page_num in (1..=page_count).filter(|&n| n != lock_byte_page) {
+            if free.contains(&page_num) {
+                stats.freelist += 1;
+                continue;
+            }
+            if self.header.is_ptrmap_page(page_num) {
+                stats.ptrmap += 1;
+                continue;
+            }
+            let buffer = self
+                .pager
+                .load_raw_page(page_num)
+                .with_context(|| format!("failed to read page {}", page_num))?;
+            let offset = if page_num == 1 {
+                paging::page::HEADER_SIZE
+            } else {
+                0
+            };
+            match PageType::parse(&buffer[offset..]) {
+                Ok(PageType::TableLeaf) => stats.table_leaf += 1,
+                Ok(PageType::TableInterior) => stats.table_interior += 1,
+                Ok(PageType::IndexLeaf) => stats.index_leaf += 1,
+                Ok(PageType::IndexInterior) => stats.index_interior += 1,
+                // overflow pages start with the number of the next one, whose first byte can't
+                // be taken for a page type in files of fewer than 2^25 pages
+                Err(_) => stats.overflow += 1,
+            }
+        }
+
+        Ok(stats)
+    }
+
+    // pages covered by the header when it can be trusted, by the file size otherwise
+    pub fn page_count(&mut self) -> anyhow::Result<usize> {
+        if self.header.database_size_is_valid() {
+            Ok(self.header.database_size as usize)
+        } else {
+            Ok((self.pager.input_size()? / self.header.page_size as u64) as usize)
+        }
+    }
+
+    // best-effort recovery of deleted rows whose pages weren't reused yet: the table leaves found
+    // among the pages no b-tree refers to, free pages included, are parsed and every cell looking
+    // like a row of the table which isn't in it anymore is returned, anything else is skipped
+    pub fn recover_deleted(&mut self, table_name: &str) -> anyhow::Result<Vec<Cursor>> {
+        let table = self.table(table_name)?;
+        if table.without_rowid {
+            anyhow::bail!("can't recover rows of WITHOUT ROWID table {}", table.name);
+        }
+        let page_count = self.page_count()?;
+
+        let mut roots = vec![("sqlite_master".to_string(), sqlite_master::SCHEMA_PAGE)];
+        roots.extend(self.schema_roots()?);
+        let mut used = HashSet::new();
+        for (name, root_page) in roots {
+            // problems in the live b-trees don't matter here
+            self.check_btree(&name, root_page, page_count, &mut used, &mut Vec::new());
+        }
+
+        // freed leaves of these tables can't be told apart from the requested table's by their
+        // records alone
+        let lookalikes = self
+            .tables()?
+            .into_iter()
+            .filter(|t| {
+                t.name != table.name && !t.without_rowid && t.columns.len() == table.columns.len()
+            })
+            .collect::<Vec<_>>();
+
+        let defaults = Rc::<[Value<'static>]>::from(table.defaults());
+        let mut recovered = Vec::new();
+        for page_num in 1..=page_count {
+            if used.contains(&page_num)
+                || page_num == self.header.lock_byte_page()
+                || self.header.is_ptrmap_page(page_num)
+            {
+                continue;
+            }
+            // freelist trunk pages and overflow pages don't parse as b-tree pages
+            let Ok(buffer) = self.pager.load_raw_page(page_num) else {
+                continue;
+            };
+            let Ok(page) = Page::parse_checked(&buffer, page_num, self.header.usable_size()) else {
+                continue;
+            };
+            let mut candidates = Vec::new();
+            for cell in page.cells {
+                let Cell::TableLeaf(leaf) = cell else {
+                    continue;
+                };
+                let Ok(header) = RecordHeader::parse(&leaf.payload) else {
+                    continue;
+                };
+                let cursor = Cursor::new(header, leaf.payload, leaf.row_id)
+                    .with_rowid_alias(table.rowid_alias())
+                    .with_defaults(Some(defaults.clone()));
+                if !cursor.header.is_empty()
+                    && cursor.check_field_count().is_ok()
+                    && cursor.fields().is_ok()
+                    && fits_affinities(&table, &cursor)
+                {
+                    candidates.push(cursor);
+                }
+            }
+            // a leaf holding a stale copy of a row still in another table belonged to that table
+            if !self.holds_live_row(&lookalikes, &candidates)? {
+                recovered.extend(candidates);
+            }
+        }
+
+        // freed pages can hold stale copies of rows which are still there, or of each other
+        recovered.sort_by_key(|cursor| cursor.row_id);
+        recovered.dedup_by(|a, b| a.row_id == b.row_id && a.payload == b.payload);
+        let mut deleted = Vec::new();
+        for cursor in recovered {
+            if self.seek_row(&table, cursor.row_id)?.is_none() {
+                deleted.push(cursor);
+            }
+        }
+        Ok(deleted)
+    }
+
+    fn holds_live_row(&mut self, tables: &[Table], cursors: &[Cursor]) -> anyhow::Result<bool> {
+        for table in tables {
+            for cursor in cursors {
+                if let Some(live) = self.seek_row(table, cursor.row_id)? {
+                    if live.payload == cursor.payload {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    // root pages of every b-tree in the schema, tables and indexes alike
+    fn schema_roots(&mut self) -> anyhow::Result<Vec<(String, usize)>> {
+        let mut scanner = self.schema_scanner();
+        let mut roots = Vec::new();
+        while let Some(record) = scanner.next_record()? {
+            let name = record.field(1)?.map(|v| v.to_string()).unwrap_or_default();
+            let root_page = record.field(3)?.and_then(|v| v.as_int()).unwrap_or(0);
+            if root_page > 0 {
+                roots.push((name, root_page as usize));
+            }
+        }
+        Ok(roots)
+    }
+
+    fn check_btree(
+        &mut self,
+        name: &str,
+        root_page: usize,
+        page_count: usize,
+        used: &mut HashSet<usize>,
+        problems: &mut Vec<String>,
+    ) {
+        let mut stack = vec![(root_page, None)];
+        while let Some((page_num, parent)) = stack.pop() {
+            let referrer = match parent {
+                Some(parent) => format!("page {}", parent),
+                None => "the schema".to_string(),
+            };
+            if page_num == 0 || page_num > page_count {
+                problems.push(format!(
+                    "{}: page {} referenced by {} is out of range (page count is {})",
+                    name, page_num, referrer, page_count
+                ));
+                continue;
+            }
+            if !used.insert(page_num) {
+                problems.push(format!(
+                    "{}: page {} referenced by {} is already in use",
+                    name, page_num, referrer
+                ));
+                continue;
+            }
+
+            let page = match self.pager.load_page(page_num) {
+                Ok(page) => page,
+                Err(e) => {
+                    problems.push(format!("{}: page {}: {:#}", name, page_num, e));
+                    continue;
+                }
+            };
+            self.check_overflow_chains(name, page_num, &page, page_count, used, problems);
+            let children = page
+                .cells
+                .iter()
+                .filter_map(|cell| match cell {
+                    Cell::TableInterior(interior) => Some(interior.left_child_page),
+                    Cell::IndexInterior(interior) => Some(interior.left_child_page),
+                    Cell::TableLeaf(_) | Cell::IndexLeaf(_) => None,
+                })
+                .chain(page.header.rightmost_pointer());
+            // pushed in reverse so that pages are visited in order
+            let children = children.collect::<Vec<_>>();
+            for child in children.into_iter().rev() {
+                stack.push((child as usize, Some(page_num)));
+            }
+        }
+    }
+
+    // https://www.sqlite.org/fileformat.html#ovflpgs
+    // each overflow page starts with the number of the next one, 0 ending the chain, followed by
+    // as much of the rest of the payload as fits
+    fn check_overflow_chains(
+        &mut self,
+        name: &str,
+        page_num: usize,
+        page: &Page,
+        page_count: usize,
+        used: &mut HashSet<usize>,
+        problems: &mut Vec<String>,
+    ) {
+        // (cell number, bytes left once the part stored in the page is read)
+        let spilling = page
+            .cells
+            .iter()
+            .enumerate()
+            .filter_map(|(i, cell)| {
+                let stored = cell.payload()?.len() as i64;
+                let remaining = cell.payload_size()?.checked_sub(stored)?;
+                (remaining > 0).then_some((i, remaining as usize))
+            })
+            .collect::<Vec<_>>();
+        if spilling.is_empty() {
+            return;
+        }
+        let buffer = match self.pager.load_raw_page(page_num) {
+            Ok(buffer) => buffer,
+            Err(e) => {
+                problems.push(format!("{}: page {}: {:#}", name, page_num, e));
+                return;
+            }
+        };
+
+        let usable_size = self.header.usable_size();
+        let per_page = usable_size.saturating_sub(4).max(1);
+        for (i, remaining) in spilling {
+            let offset = page.header_offset as usize + page.cell_pointers[i] as usize;
+            let first = buffer
+                .get(offset..)
+                .and_then(|cell| Cell::overflow_page(cell, &page.header, usable_size));
+            let Some(first) = first else {
+                problems.push(format!(
+                    "{}: cell {} of page {} has no overflow page",
+                    name, i, page_num
+                ));
+                continue;
+            };
+
+            let mut expected = remaining.div_ceil(per_page);
+            let mut referrer = page_num;
+            let mut overflow_page = first as usize;
+            while overflow_page != 0 {
+                if expected == 0 {
+                    problems.push(format!(
+                        "{}: overflow chain of cell {} of page {} is too long",
+                        name, i, page_num
+                    ));
+                    break;
+                }
+                if overflow_page > page_count {
+                    problems.push(format!(
+                        "{}: overflow page {} referenced by page {} is out of range (page count is {})",
+                        name, overflow_page, referrer, page_count
+                    ));
+                    break;
+                }
+                if !used.insert(overflow_page) {
+                    problems.push(format!(
+                        "{}: overflow page {} referenced by page {} is already in use",
+                        name, overflow_page, referrer
+                    ));
+                    break;
+                }
+                expected -= 1;
+                let next = match self.pager.load_raw_page(overflow_page) {
+                    Ok(buffer) => utils::read_be_double_word_at(&buffer, 0).1 as usize,
+                    Err(e) => {
+                        problems.push(format!(
+                            "{}: overflow page {}: {:#}",
+                            name, overflow_page, e
+                        ));
+                        break;
+                    }
+                };
+                referrer = overflow_page;
+                overflow_page = next;
+            }
+            if overflow_page == 0 && expected > 0 {
+                problems.push(format!(
+                    "{}: overflow chain of cell {} of page {} is missing {} pages",
+                    name, i, page_num, expected
+                ));
+            }
+        }
+    }
+
+    // https://www.sqlite.org/fileformat.html#the_freelist
+    fn check_freelist(
+        &mut self,
+        page_count: usize,
+        used: &mut HashSet<usize>,
+        problems: &mut Vec<String>,
+    ) {
+        let mut free_pages = 0;
+        let mut trunk = self.header.first_freelist_trunk as usize;
+        while trunk != 0 {
+            if trunk > page_count {
+                problems.push(format!(
+                    "freelist: trunk page {} is out of range (page count is {})",
+                    trunk, page_count
+                ));
+                break;
+            }
+            if !used.insert(trunk) {
+                problems.push(format!("freelist: trunk page {} is already in use", trunk));
+                break;
+            }
+            free_pages += 1;
+
+            let buffer = match self.pager.load_raw_page(trunk) {
+                Ok(buffer) => buffer,
+                Err(e) => {
+                    problems.push(format!("freelist: trunk page {}: {:#}", trunk, e));
+                    break;
+                }
+            };
+            let next_trunk = utils::read_be_double_word_at(&buffer, 0).1;
+            let mut leaf_count = utils::read_be_double_word_at(&buffer, 4).1 as usize;
+            let max_leaf_count = buffer.len() / 4 - 2;
+            if leaf_count > max_leaf_count {
+                problems.push(format!(
+                    "freelist: trunk page {} claims {} leaves but can hold at most {}",
+                    trunk, leaf_count, max_leaf_count
+                ));
+                leaf_count = max_leaf_count;
+            }
+
+            for i in 0..leaf_count {
+                let leaf = utils::read_be_double_word_at(&buffer, 8 + 4 * i).1 as usize;
+                free_pages += 1;
+                if leaf == 0 || leaf > page_count {
+                    problems.push(format!(
+                        "freelist: leaf page {} of trunk page {} is out of range (page count is {})",
+                        leaf, trunk, page_count
+                    ));
+                } else if !used.insert(leaf) {
+                    problems.push(format!(
+                        "freelist: leaf page {} of trunk page {} is already in use",
+                        leaf, trunk
+                    ));
+                }
+            }
+
+            trunk = next_trunk as usize;
+        }
+
+        if free_pages != self.header.freelist_count as usize {
+            problems.push(format!(
+                "freelist: the header counts {} pages but {} were found",
+                self.header.freelist_count, free_pages
+            ));
+        }
+    }
+
+    // last row id handed out to each AUTOINCREMENT table, sqlite_sequence only exists once such
+    // a table was created
+    pub fn sequences(&mut self) -> anyhow::Result<HashMap<String, i64>> {
+        let mut sequences = HashMap::new();
+        if !self
+            .tables()?
+            .iter()
+            .any(|t| t.name.eq_ignore_ascii_case(Self::SEQUENCE_TABLE))
+        {
+            return Ok(sequences);
+        }
+
+        let mut scanner = self.scanner_for(Self::SEQUENCE_TABLE)?;
+        while let Some(record) = scanner.next_record()? {
+            let name = record
+                .field(0)
+                .context("invalid name field")?
+                .and_then(|v| v.as_str().map(|s| s.to_string()))
+                .context("missing name field")?;
+            let seq = record
+                .field(1)
+                .context("invalid seq field")?
+                .and_then(|v| v.as_int())
+                .context("missing seq field")?;
+            sequences.insert(name, seq);
+        }
+
+        Ok(sequences)
+    }
+
+    // row ids of the entries whose leading column equals the value, in index order, only the
+    // subtrees which can hold such entries are visited
+    pub fn index_lookup(&mut self, index: &Index, value: &Value) -> anyhow::Result<Vec<i64>> {
+        self.index_lookup_prefix(index, std::slice::from_ref(value))
+    }
+
+    // row ids of the entries whose leading columns equal the given values, in index order
+    pub fn index_lookup_prefix(
+        &mut self,
+        index: &Index,
+        prefix: &[Value],
+    ) -> anyhow::Result<Vec<i64>> {
+        if prefix.len() > index.columns.len() {
+            anyhow::bail!(
+                "index {} has {} columns, not {}",
+                index.name,
+                index.columns.len(),
+                prefix.len()
+            );
+        }
+        let mut row_ids = Vec::new();
+        let mut visited = HashSet::new();
+        self.collect_index_matches(index, index.root_page, prefix, &mut row_ids, &mut visited)?;
+        Ok(row_ids)
+    }
+
+    // returns true once an entry sorting after the value was found, i.e. when there is nothing
+    // left to collect
+    fn collect_index_matches(
+        &mut self,
+        index: &Index,
+        page_num: usize,
+        prefix: &[Value],
+        row_ids: &mut Vec<i64>,
+        visited: &mut HashSet<usize>,
+    ) -> anyhow::Result<bool> {
+        if !visited.insert(page_num) {
+            anyhow::bail!("page {} visited twice", page_num);
+        }
+        let page = self.pager.read_page(page_num)?;
+        // the entries before the first match and their subtrees sort before the prefix
+        let first = page
+            .index_lower_bound(prefix, index.columns.len(), &index.descending)
+            .with_context(|| format!("index page {}", page_num))?;
+        for cell in &page.cells[first..] {
+            let (left_child_page, payload) = match cell {
+                Cell::IndexInterior(interior) => {
+                    (Some(interior.left_child_page), &interior.payload)
+                }
+                Cell::IndexLeaf(leaf) => (None, &leaf.payload),
+                Cell::TableInterior(_) | Cell::TableLeaf(_) => {
+                    anyhow::bail!("page {} is not an index page", page_num)
+                }
+            };
+            let record = IndexRecord::parse(payload, index.columns.len())
+                .with_context(|| format!("index page {}", page_num))?;
+            let ordering = record.cmp_key(prefix, &index.descending)?;
+            if let Some(child) = left_child_page {
+                if self.collect_index_matches(index, child as usize, prefix, row_ids, visited)? {
+                    return Ok(true);
+                }
+            }
+            if ordering == Ordering::Greater {
+                return Ok(true);
+            }
+            let row_id = record
+                .row_id()
+                .with_context(|| format!("index page {}", page_num))?;
+            row_ids.push(row_id);
+        }
+        match page.header.rightmost_pointer() {
+            Some(child) => {
+                self.collect_index_matches(index, child as usize, prefix, row_ids, visited)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // descends the table b-tree to the leaf which would hold the row id
+    pub fn seek_row(&mut self, table: &Table, row_id: i64) -> anyhow::Result<Option<Cursor>> {
+        let mut page_num = table.root_page;
+        // a corrupted tree could point back to one of its pages and never end
+        let mut visited = HashSet::new();
+        loop {
+            if !visited.insert(page_num) {
+                anyhow::bail!("page {} visited twice", page_num);
+            }
+            let page = self.pager.read_page(page_num)?;
+            if page.header.is_index() {
+                anyhow::bail!("page {} is not a table page", page_num);
+            }
+            if let Some(child) = page.header.rightmost_pointer() {
+                page_num = page
+                    .cells
+                    .iter()
+                    .find_map(|cell| match cell {
+                        Cell::TableInterior(interior) if interior.key >= row_id => {
+                            Some(interior.left_child_page)
+                        }
+                        _ => None,
+                    })
+                    .unwrap_or(child) as usize;
+                continue;
+            }
+
+            let leaf = page.cells.iter().find_map(|cell| match cell {
+                Cell::TableLeaf(leaf) if leaf.row_id == row_id => Some(leaf),
+                _ => None,
+            });
+            return match leaf {
+                Some(leaf) => {
+                    let header = RecordHeader::parse(&leaf.payload)?;
+                    let cursor = Cursor::new(header, leaf.payload.clone(), leaf.row_id)
+                        .with_rowid_alias(table.rowid_alias())
+                        .with_defaults(Some(Rc::from(table.defaults())));
+                    cursor.check_field_count()?;
+                    Ok(Some(cursor))
+                }
+                None => Ok(None),
+            };
+        }
+    }
+
+    // collects every row of a select, see query to process rows as they are read
+    pub fn execute(&mut self, sql: &str) -> anyhow::Result<ResultSet> {
+        let rows = self.query(sql)?;
+        let columns = rows.columns().to_vec();
+
+        Ok(ResultSet {
+            columns,
+            rows: rows.collect::<anyhow::Result<_>>()?,
+        })
+    }
+
+    // rows are read from the table as the stream is advanced, even with a WHERE, LIMIT or OFFSET,
+    // and an equality filter on an indexed column only reads the matching rows
+    // with an ORDER BY or aggregates, every matching row is read on the first call to next
+    pub fn query(&mut self, sql: &str) -> anyhow::Result<RowStream<'_, P>> {
+        let select =
+            parser::parse_select(sql).with_context(|| SqlrError::UnsupportedSql(sql.to_owned()))?;
+        Plan::new(self, &select)?.into_rows(self)
+    }
+
+    // like SELECT COUNT(*) without decoding a single record, rows are counted from the cells of
+    // the table's pages
+    pub fn table_row_count(&mut self, table_name: &str) -> anyhow::Result<usize> {
+        let table = self.table(table_name)?;
+        self.count_rows(table.root_page)
+            .with_context(|| format!("count the rows of {}", table.name))
+    }
+
+    // the entries of the b-tree rooted at the given page: the cells of its leaves, along with
+    // the cells of its interior pages for an index b-tree, e.g. a WITHOUT ROWID table
+    pub fn count_rows(&mut self, root_page: usize) -> anyhow::Result<usize> {
+        let mut count = 0;
+        let mut stack = vec![root_page];
+        // a corrupted tree could point back to one of its pages and never end
+        let mut visited = HashSet::new();
+        while let Some(page_num) = stack.pop() {
+            if !visited.insert(page_num) {
+                anyhow::bail!("page {} is referenced more than once", page_num);
+            }
+            let page = self.pager.read_page(page_num)?;
+            for cell in &page.cells {
+                match cell {
+                    Cell::TableLeaf(_) | Cell::IndexLeaf(_) => count += 1,
+                    Cell::TableInterior(interior) => stack.push(interior.left_child_page as usize),
+                    Cell::IndexInterior(interior) => {
+                        count += 1;
+                        stack.push(interior.left_child_page as usize);
+                    }
+                }
+            }
+            stack.extend(page.header.rightmost_pointer().map(|p| p as usize));
+        }
+        Ok(count)
+    }
+
+    pub fn scanner_for(&mut self, table_name: &str) -> anyhow::Result<Scanner<'_>> {
+        let table = self.table(table_name)?;
+        Ok(self
+            .scanner(table.root_page)
+            .with_rowid_alias(table.rowid_alias())
+            .with_column_order(table.column_order())
+            .with_defaults(Some(table.defaults())))
+    }
+}
+
+// the -wal file next to the database, None when there isn't one
+fn open_wal(filename: impl AsRef<Path>) -> anyhow::Result<Option<Wal<std::fs::File>>> {
+    let mut wal_filename = filename.as_ref().as_os_str().to_owned();
+    wal_filename.push("-wal");
+    match std::fs::File::open(&wal_filename) {
+        Ok(wal_file) => Wal::parse(wal_file).context("parse wal file").map(Some),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).context("open wal file"),
+    }
+}
+
+// whether sqlite could have written the record to the table: the row id alias is stored as null
+// and no value is of a kind its column's affinity would have converted, integral reals being
+// stored as integers whatever the affinity
+fn fits_affinities(table: &Table, cursor: &Cursor) -> bool {
+    let alias = table.rowid_alias();
+    cursor
+        .header
+        .fields
+        .iter()
+        .zip(&table.columns)
+        .enumerate()
+        .all(|(i, (field, column))| {
+            if Some(i) == alias {
+                return field.field_type == RecordFieldType::Null;
+            }
+            let Ok(Some(value)) = field.field_type.value(&cursor.payload, field.offset) else {
+                return false;
+            };
+            match (column.affinity, &value) {
+                (Affinity::Integer | Affinity::Numeric, Value::Float(f)) => {
+                    f.fract() != 0.0 || f.abs() >= i64::MAX as f64
+                }
+                (Affinity::Real, Value::Int(_)) => true,
+                (affinity, _) => {
+                    std::mem::discriminant(&value)
+                        == std::mem::discriminant(&value.clone().affinity_coerce(affinity))
+                }
+            }
+        })
 }
 
 #[cfg(test)]
 mod test {
-    use std::collections::HashMap;
+    use std::{borrow::Cow, collections::HashMap};
+
+    use crate::{paging::page_writer, schema::schema_object::ObjectKind};
 
     use super::*;
 
     #[test]
-    fn parse_dbheader_tests() -> () {
-        assert!(DbHeader::parse(&[1, 2, 3]).is_err());
-        assert!(DbHeader::parse(&[DbHeader::HEADER_PREFIX, &[3]].concat()).is_err());
-        let res_max = DbHeader::parse(&[DbHeader::HEADER_PREFIX, &[1]].concat());
+    fn parse_dbheader_tests() {
+        let header = |page_size: u16| {
+            let mut buffer = [DbHeader::HEADER_PREFIX, &page_size.to_be_bytes()].concat();
+            buffer.resize(paging::page::HEADER_SIZE, 0);
+            buffer
+        };
+        assert_eq!(
+            Err(SqlrError::BadMagic {
+                expected: DbHeader::HEADER_PREFIX,
+                actual: vec![1, 2, 3]
+            }),
+            DbHeader::parse(&[1, 2, 3])
+        );
+        assert!(DbHeader::parse(&header(3)).is_err());
+        let res_max = DbHeader::parse(&header(1));
         assert!(res_max.is_ok());
-        assert_eq!(DbHeader { page_size: 65536 }, res_max.unwrap());
-        let res_pow = DbHeader::parse(&[DbHeader::HEADER_PREFIX, &[8]].concat());
+        assert_eq!(65536, res_max.unwrap().page_size);
+        let res_pow = DbHeader::parse(&header(8));
         assert!(res_pow.is_ok());
-        assert_eq!(DbHeader { page_size: 8 }, res_pow.unwrap());
+        assert_eq!(8, res_pow.unwrap().page_size);
+        assert_eq!(
+            Err(SqlrError::TruncatedHeader {
+                expected: 100,
+                actual: 17
+            }),
+            DbHeader::parse(&header(8)[..17])
+        );
+        assert_eq!(
+            Err(SqlrError::TruncatedHeader {
+                expected: 100,
+                actual: 6
+            }),
+            DbHeader::parse(b"SQLite")
+        );
     }
 
     #[test]
-    fn from_file_tests() -> () {
+    fn schema_format_tests() {
+        let mut buffer = std::fs::read("test.db").unwrap();
+        assert_eq!(4, DbHeader::parse(&buffer).unwrap().schema_format);
+        for format in 0..=4u32 {
+            buffer[44..48].copy_from_slice(&format.to_be_bytes());
+            let header = DbHeader::parse(&buffer).unwrap();
+            assert_eq!(format, header.schema_format);
+            assert_eq!(format == 4, header.honors_descending_indexes());
+        }
+        buffer[44..48].copy_from_slice(&5u32.to_be_bytes());
+        assert_eq!(
+            Err(SqlrError::UnsupportedSchemaFormat(5)),
+            DbHeader::parse(&buffer)
+        );
+        let err = Db::from_bytes(buffer).unwrap_err();
+        assert!(format!("{:#}", err).contains("unsupported schema format: 5"));
+
+        // DESC is ignored before format 4
+        let mut buffer = std::fs::read("test_index.db").unwrap();
+        let descending = |buffer: Vec<u8>| {
+            let mut db = Db::from_bytes(buffer).unwrap();
+            db.indexes()
+                .unwrap()
+                .into_iter()
+                .any(|index| index.descending.contains(&true))
+        };
+        assert!(descending(buffer.clone()));
+        buffer[44..48].copy_from_slice(&3u32.to_be_bytes());
+        assert!(!descending(buffer));
+    }
+
+    #[test]
+    fn user_version_application_id_tests() {
+        let mut buffer = std::fs::read("test.db").unwrap();
+        buffer[60..64].copy_from_slice(&(-3i32).to_be_bytes());
+        buffer[68..72].copy_from_slice(&0x0f055112i32.to_be_bytes());
+        let header = DbHeader::parse(&buffer).unwrap();
+        assert_eq!(-3, header.user_version);
+        assert_eq!(0x0f055112, header.application_id);
+    }
+
+    #[test]
+    fn from_bytes_error_tests() {
+        let err = Db::from_bytes(vec![0; 100]).err().unwrap();
+        assert_eq!(
+            Some(&SqlrError::BadMagic {
+                expected: DbHeader::HEADER_PREFIX,
+                actual: vec![0; 16]
+            }),
+            err.downcast_ref::<SqlrError>()
+        );
+
+        let err = Db::from_bytes(vec![]).err().unwrap();
+        assert_eq!(
+            Some(&SqlrError::TruncatedHeader {
+                expected: 100,
+                actual: 0
+            }),
+            err.downcast_ref::<SqlrError>()
+        );
+
+        let bytes = std::fs::read("test.db").unwrap();
+        let err = Db::from_bytes(bytes[..60].to_vec()).err().unwrap();
+        assert_eq!(
+            Some(&SqlrError::TruncatedHeader {
+                expected: 100,
+                actual: 60
+            }),
+            err.downcast_ref::<SqlrError>()
+        );
+    }
+
+    #[test]
+    fn text_encoding_name_tests() {
+        let mut header = Db::from_file("test.db").unwrap().header;
+        assert_eq!("utf8", header.text_encoding_name());
+        header.text_encoding = 3;
+        assert_eq!("utf16be", header.text_encoding_name());
+        header.text_encoding = 0;
+        assert_eq!("unknown", header.text_encoding_name());
+    }
+
+    #[test]
+    fn from_file_tests() {
         let res = Db::from_file("test.db");
         assert!(res.is_ok());
         let db = res.unwrap();
-        assert_eq!(DbHeader { page_size: 4096 }, db.header);
+        assert_eq!(
+            DbHeader {
+                page_size: 4096,
+                reserved_space: 0,
+                change_counter: 3,
+                database_size: 2,
+                first_freelist_trunk: 0,
+                freelist_count: 0,
+                schema_cookie: 1,
+                schema_format: 4,
+                largest_root_page: 0,
+                text_encoding: 1,
+                user_version: 0,
+                application_id: 0,
+                version_valid_for: 3,
+                write_version: 1,
+                read_version: 1,
+            },
+            *db.header()
+        );
+        assert_eq!(4096, db.page_size());
         assert_eq!(4096, db.pager.page_size);
         assert_eq!(HashMap::new(), db.pager.pages);
     }
+
+    #[test]
+    fn from_file_with_page_size_tests() {
+        // the magic string is misspelled but the pages are fine
+        let err = Db::from_file("test_corrupted_header.db").unwrap_err();
+        assert!(format!("{:#}", err).contains("not a sqlite database"));
+
+        let mut db = Db::from_file_with_page_size("test_corrupted_header.db", 4096).unwrap();
+        assert_eq!(4096, db.page_size());
+        assert_eq!(2, db.page_count().unwrap());
+        let tables = db.tables().unwrap();
+        assert_eq!(
+            vec!["tbl1"],
+            tables.iter().map(|t| &t.name).collect::<Vec<_>>()
+        );
+        let result = db.execute("SELECT * FROM tbl1").unwrap();
+        assert_eq!(2, result.rows.len());
+
+        for page_size in [0, 256, 1000, 131072] {
+            let err = Db::from_file_with_page_size("test.db", page_size).unwrap_err();
+            assert!(err.to_string().contains("invalid page size"));
+        }
+        assert!(Db::from_file_with_page_size("test.db", 65536).is_ok());
+    }
+
+    #[test]
+    fn from_file_64k_page_size_tests() {
+        // the page size is stored as 1
+        let bytes = std::fs::read("test_64k.db").unwrap();
+        assert_eq!([0, 1], bytes[16..18]);
+
+        let mut db = Db::from_file("test_64k.db").unwrap();
+        assert_eq!(65536, db.header().page_size);
+        assert_eq!(65536, db.page_size());
+        assert_eq!(65536, db.pager.page_size);
+        assert!(db.check_size().is_ok());
+        assert_eq!(
+            vec!["t", "e"],
+            db.tables()
+                .unwrap()
+                .into_iter()
+                .map(|t| t.name)
+                .collect::<Vec<_>>()
+        );
+        let mut scanner = db.scanner_for("t").unwrap();
+        let mut values = Vec::new();
+        while let Some(record) = scanner.next_record().unwrap() {
+            values.push(record.field(1).unwrap().unwrap().to_string());
+        }
+        assert_eq!(vec!["one", "two", "three"], values);
+        assert!(db
+            .scanner_for("e")
+            .unwrap()
+            .next_record()
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn from_file_reserved_space_tests() {
+        let mut db = Db::from_file("test_reserved.db").unwrap();
+        assert_eq!(32, db.header.reserved_space);
+        assert_eq!(4064, db.header.usable_size());
+        assert_eq!(4064, db.pager.usable_size());
+        let mut scanner = db.scanner_for("t").unwrap();
+        let mut values = Vec::new();
+        while let Some(record) = scanner.next_record().unwrap() {
+            values.push(record.field(0).unwrap().unwrap().to_string());
+        }
+        assert_eq!(vec!["one", "two", "three"], values);
+
+        // the table's first cell pointer moved into the reserved bytes of page 2
+        let mut bytes = std::fs::read("test_reserved.db").unwrap();
+        bytes[4096 + 8..4096 + 10].copy_from_slice(&4070u16.to_be_bytes());
+        let mut db = Db::from_bytes(bytes).unwrap();
+        let err = db.scanner_for("t").unwrap().next_record().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<SqlrError>(),
+            Some(SqlrError::InvalidCellPointer { pointer: 4070, .. })
+        ));
+    }
+
+    #[test]
+    fn page_tests() {
+        let mut db = Db::from_file("test_freeblock.db").unwrap();
+        let page = db.page(2).unwrap();
+        assert_eq!(3, page.cells.len());
+        assert_eq!(vec![(4065, 8), (4082, 7)], page.free_blocks);
+        assert_eq!(100, db.page(1).unwrap().header_offset);
+        assert!(db.page(0).is_err());
+        assert!(db.page(3).is_err());
+
+        // freelist pages aren't b-tree pages
+        let mut db = Db::from_file("test_freelist.db").unwrap();
+        let trunk = db.header.first_freelist_trunk as usize;
+        assert!(db.page(trunk).is_err());
+    }
+
+    #[test]
+    fn added_columns_tests() {
+        // columns b, c and d were added after the first two rows were inserted
+        let mut db = Db::from_file("test_alter.db").unwrap();
+        let rows = db.execute("SELECT * FROM t").unwrap().rows;
+        assert_eq!(3, rows.len());
+        assert!(matches!(rows[0][1], Value::Int(7)));
+        assert_eq!(Value::Null, rows[0][2]);
+        assert!(matches!(rows[1][3], Value::Float(f) if f == -1.5));
+        assert!(matches!(rows[2][1], Value::Int(3)));
+        assert_eq!(Some("x"), rows[2][2].as_str());
+        let rows = db.execute("SELECT a FROM t WHERE b = 7").unwrap().rows;
+        assert_eq!(
+            vec![
+                vec![Value::String("one".into())],
+                vec![Value::String("two".into())]
+            ],
+            rows
+        );
+        let table = db.table("t").unwrap();
+        let record = db.seek_row(&table, 1).unwrap().unwrap();
+        assert!(matches!(record.field(1).unwrap(), Some(Value::Int(7))));
+        assert_eq!(None, record.field(4).unwrap());
+
+        // tbl1 records have two fields
+        let mut db = Db::from_file("test.db").unwrap();
+        let mut scanner = db
+            .scanner_for("tbl1")
+            .unwrap()
+            .with_defaults(Some(vec![Value::Null]));
+        let err = scanner.next_record().unwrap_err();
+        assert_eq!(
+            "record in page 2 at file offset 8180: record 1 has 2 fields but its table has 1 columns",
+            format!("{:#}", err)
+        );
+    }
+
+    #[test]
+    fn from_bytes_tests() {
+        assert!(Db::from_bytes(vec![0; 10]).is_err());
+        let bytes = std::fs::read("test.db").unwrap();
+        let mut db = Db::from_bytes(bytes).unwrap();
+        assert_eq!(4096, db.header.page_size);
+        assert_eq!(
+            vec!["tbl1"],
+            db.tables()
+                .unwrap()
+                .iter()
+                .map(|t| t.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        let mut scanner = db.scanner_for("tbl1").unwrap();
+        let record = scanner.next_record().unwrap().unwrap();
+        assert_eq!(Some("hello!"), record.field(0).unwrap().unwrap().as_str());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn from_file_mmap_tests() {
+        assert!(Db::from_file_mmap("missing.db").is_err());
+        let mut db = Db::from_file_mmap("test_index.db").unwrap();
+        assert_eq!(Db::from_file("test_index.db").unwrap().header, db.header);
+        assert!(db.integrity_check().is_empty());
+        let table = db.table("people").unwrap();
+        let record = db.seek_row(&table, 42).unwrap().unwrap();
+        assert_eq!(Some("name041"), record.field(1).unwrap().unwrap().as_str());
+
+        // pages 2 and 3 were only committed to the wal
+        let err = Db::from_file_mmap("test_wal.db").unwrap_err();
+        assert!(err.to_string().contains("wal holds committed pages"));
+    }
+
+    #[test]
+    fn from_file_wal_tests() {
+        fn names<P: Pager>(db: &mut Db<P>) -> Vec<String> {
+            db.tables().unwrap().into_iter().map(|t| t.name).collect()
+        }
+
+        // the main file alone only has what was checkpointed
+        let mut db = Db::from_bytes(std::fs::read("test_wal.db").unwrap()).unwrap();
+        assert!(db.header.is_wal());
+        assert_eq!(2, db.header.database_size);
+        assert_eq!(vec!["t"], names(&mut db));
+
+        let mut db = Db::from_file("test_wal.db").unwrap();
+        assert_eq!(3, db.header.database_size);
+        assert!(db.check_size().is_ok());
+        assert!(db.integrity_check().is_empty());
+        assert_eq!(vec!["t", "u"], names(&mut db));
+        let mut scanner = db.scanner_for("t").unwrap();
+        let mut values = Vec::new();
+        while let Some(record) = scanner.next_record().unwrap() {
+            values.push(record.field(1).unwrap().unwrap().to_string());
+        }
+        assert_eq!(vec!["updated", "main2", "main3", "wal4"], values);
+
+        // a wal whose header checksum doesn't match holds no committed frames and is ignored
+        let mut wal = std::fs::read("test_wal.db-wal").unwrap();
+        wal[10] = 0x20;
+        let db = Db::from_bytes(std::fs::read("test_wal.db").unwrap()).unwrap();
+        let mut db = db
+            .with_wal(Wal::parse(std::io::Cursor::new(wal)).unwrap())
+            .unwrap();
+        assert_eq!(2, db.header.database_size);
+        assert_eq!(vec!["t"], names(&mut db));
+    }
+
+    #[test]
+    fn tables_tests() {
+        let mut db = Db::from_file("test.db").unwrap();
+        let tables = db.tables().unwrap();
+        assert_eq!(1, tables.len());
+        assert_eq!("tbl1", tables[0].name);
+        assert_eq!(2, tables[0].root_page);
+        assert_eq!("CREATE TABLE tbl1(one text, two int)", tables[0].sql);
+        assert_eq!(
+            vec!["one", "two"],
+            tables[0]
+                .columns
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn views_tests() {
+        let mut db = Db::from_file("test_view.db").unwrap();
+        assert_eq!(
+            vec![
+                ViewDef {
+                    name: "adults".to_string(),
+                    sql: "CREATE VIEW adults as select name from people where age >= 18"
+                        .to_string(),
+                },
+                ViewDef {
+                    name: "Names".to_string(),
+                    sql: "CREATE VIEW \"Names\" as select name from people".to_string(),
+                },
+            ],
+            db.views().unwrap()
+        );
+        // views aren't tables
+        assert_eq!(1, db.tables().unwrap().len());
+        assert!(Db::from_file("test.db")
+            .unwrap()
+            .views()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn schema_tests() {
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let schema = db.schema().unwrap();
+        assert_eq!(
+            vec![
+                ObjectKind::Table,
+                ObjectKind::Index,
+                ObjectKind::Index,
+                ObjectKind::Index,
+                ObjectKind::Index
+            ],
+            schema.objects.iter().map(|o| o.kind).collect::<Vec<_>>()
+        );
+
+        let index = schema.find("IDX_PEOPLE_AGE").unwrap();
+        assert_eq!(
+            SchemaObject {
+                kind: ObjectKind::Index,
+                name: "idx_people_age".to_string(),
+                tbl_name: "people".to_string(),
+                root_page: 4,
+                sql: Some("CREATE INDEX idx_people_age ON people(age)".to_string()),
+            },
+            *index
+        );
+        assert_eq!(None, schema.find("sqlite_autoindex_people_1").unwrap().sql);
+        assert!(schema.find("missing").is_none());
+    }
+
+    #[test]
+    fn schema_table_alias_tests() {
+        let mut db = Db::from_file("test.db").unwrap();
+        for name in ["sqlite_master", "SQLITE_SCHEMA"] {
+            let mut scanner = db.scanner_for(name).unwrap();
+            let record = scanner.next_record().unwrap().unwrap();
+            assert_eq!(Some("tbl1"), record.field(1).unwrap().unwrap().as_str());
+        }
+        assert_eq!(1, db.tables().unwrap().len());
+    }
+
+    #[test]
+    fn indexes_tests() {
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let tables = db.tables().unwrap();
+        assert_eq!(
+            vec!["people"],
+            tables.iter().map(|t| t.name.as_str()).collect::<Vec<_>>()
+        );
+
+        // the index backing the UNIQUE constraint on email has no sql and is left out
+        let indexes = db.indexes().unwrap();
+        assert_eq!(
+            vec![
+                ("idx_people_age", 4, vec!["age"], false, false),
+                ("idx people name", 5, vec!["name"], true, false),
+                ("idx_people_adults", 6, vec!["age", "name"], false, true),
+            ],
+            indexes
+                .iter()
+                .map(|i| (
+                    i.name.as_str(),
+                    i.root_page,
+                    i.columns
+                        .iter()
+                        .flatten()
+                        .map(|c| c.as_str())
+                        .collect::<Vec<_>>(),
+                    i.unique,
+                    i.partial
+                ))
+                .collect::<Vec<_>>()
+        );
+        assert!(indexes.iter().all(|i| i.table == "people"));
+
+        assert!(Db::from_file("test.db")
+            .unwrap()
+            .indexes()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn expression_indexes_tests() {
+        // an index on lower(a) is listed next to the one on b, with no column name to seek on
+        let mut db = Db::from_file("test_expr_index.db").unwrap();
+        let indexes = db.indexes().unwrap();
+        assert_eq!(
+            vec![("i_expr", vec![None]), ("i_b", vec![Some("b".to_string())])],
+            indexes
+                .iter()
+                .map(|i| (i.name.as_str(), i.columns.clone()))
+                .collect::<Vec<_>>()
+        );
+        let [a, b] = db.table("t").unwrap().columns.try_into().unwrap();
+        assert!(!indexes[0].can_seek(&a));
+        assert!(indexes[1].can_seek(&b));
+        assert_eq!(
+            vec![2, 4],
+            db.index_lookup(&indexes[1], &Value::Int(2)).unwrap()
+        );
+    }
+
+    #[test]
+    fn index_record_tests() {
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let table = db.table("people").unwrap();
+        let adults = db.indexes().unwrap().remove(2);
+        assert_eq!(
+            vec![Some("age".to_string()), Some("name".to_string())],
+            adults.columns
+        );
+
+        // every entry of the root page points back to the row it was taken from
+        let root = db.page(adults.root_page).unwrap();
+        assert!(!root.cells.is_empty());
+        for cell in &root.cells {
+            let payload = match cell {
+                Cell::IndexInterior(interior) => &interior.payload,
+                Cell::IndexLeaf(leaf) => &leaf.payload,
+                c => panic!("not an index cell: {:?}", c),
+            };
+            let record = IndexRecord::parse(payload, 2).unwrap();
+            let row = db
+                .seek_row(&table, record.row_id().unwrap())
+                .unwrap()
+                .unwrap();
+            let keys = record.keys().unwrap();
+            assert!(keys[0].as_int().unwrap() >= 18);
+            assert_eq!(
+                keys,
+                vec![
+                    row.field(2).unwrap().unwrap(),
+                    row.field(1).unwrap().unwrap()
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn index_lookup_tests() {
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let indexes = db.indexes().unwrap();
+
+        // ages cycle through 0..50, the index spans several pages
+        let by_age = &indexes[0];
+        assert_eq!(
+            (0..10).map(|i| 11 + 50 * i).collect::<Vec<_>>(),
+            db.index_lookup(by_age, &Value::Int(10)).unwrap()
+        );
+        assert_eq!(
+            (0..10).map(|i| 50 + 50 * i).collect::<Vec<_>>(),
+            db.index_lookup(by_age, &Value::Float(49.0)).unwrap()
+        );
+        assert!(db.index_lookup(by_age, &Value::Int(50)).unwrap().is_empty());
+        assert!(db.index_lookup(by_age, &Value::Int(-1)).unwrap().is_empty());
+        assert!(db
+            .index_lookup(by_age, &Value::String("10".into()))
+            .unwrap()
+            .is_empty());
+
+        // entries of a DESC index are sorted in reverse, names being lowercase NOCASE doesn't
+        // change their order
+        let by_name = &indexes[1];
+        assert_eq!(
+            vec![43],
+            db.index_lookup(by_name, &Value::String("name042".into()))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn index_lookup_prefix_tests() {
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let adults = db.indexes().unwrap().remove(2);
+
+        // entries sharing the leading column come in the order of the next one
+        let by_age = (0..10).map(|i| 21 + 50 * i).collect::<Vec<_>>();
+        assert_eq!(
+            by_age,
+            db.index_lookup_prefix(&adults, &[Value::Int(20)]).unwrap()
+        );
+        assert_eq!(by_age, db.index_lookup(&adults, &Value::Int(20)).unwrap());
+        assert_eq!(
+            vec![71],
+            db.index_lookup_prefix(&adults, &[Value::Int(20), Value::String("name070".into())])
+                .unwrap()
+        );
+        assert!(db
+            .index_lookup_prefix(&adults, &[Value::Int(20), Value::String("name072".into())])
+            .unwrap()
+            .is_empty());
+        // the partial index has no minors
+        assert!(db
+            .index_lookup_prefix(&adults, &[Value::Int(10)])
+            .unwrap()
+            .is_empty());
+        // every entry matches an empty prefix
+        assert_eq!(320, db.index_lookup_prefix(&adults, &[]).unwrap().len());
+
+        let too_long = [Value::Int(20), Value::Null, Value::Null];
+        assert!(db.index_lookup_prefix(&adults, &too_long).is_err());
+    }
+
+    #[test]
+    fn empty_db_tests() {
+        // a freshly created db is a single table leaf page without cells, its encoding and schema
+        // format are only set once something is written to it
+        let mut db = Db::from_file("test_empty.db").unwrap();
+        assert_eq!(0, db.header().text_encoding);
+        assert_eq!(0, db.header().schema_format);
+        assert_eq!(1, db.page_count().unwrap());
+        assert!(db.tables().unwrap().is_empty());
+        assert!(db.indexes().unwrap().is_empty());
+        assert!(db.views().unwrap().is_empty());
+        assert!(db.schema().unwrap().objects.is_empty());
+        assert!(db.sequences().unwrap().is_empty());
+        assert!(db.schema_scanner().next_record().unwrap().is_none());
+        assert_eq!(0, db.table_row_count("sqlite_master").unwrap());
+        assert_eq!(
+            PageStats {
+                table_leaf: 1,
+                ..PageStats::default()
+            },
+            db.page_stats().unwrap()
+        );
+
+        assert!(db
+            .execute("SELECT * FROM sqlite_master")
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            vec![vec![Value::Int(0)]],
+            db.execute("SELECT COUNT(*) FROM sqlite_master")
+                .unwrap()
+                .rows
+        );
+        assert_eq!(
+            "no such table: t",
+            db.execute("SELECT * FROM t").unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn execute_tests() {
+        let mut db = Db::from_file("test.db").unwrap();
+        let result_set = db
+            .execute("SELECT two, one FROM tbl1 WHERE two = 20")
+            .unwrap();
+        assert_eq!(
+            ResultSet {
+                columns: vec!["two".to_string(), "one".to_string()],
+                rows: vec![vec![Value::Int(20), Value::String("goodbye".into())]],
+            },
+            result_set
+        );
+        // rows outlive the db
+        let rows = Db::from_file("test.db")
+            .unwrap()
+            .execute("SELECT * FROM tbl1")
+            .unwrap()
+            .rows;
+        assert_eq!(2, rows.len());
+        let result_set = db.execute("SELECT one AS greeting FROM tbl1").unwrap();
+        assert_eq!(
+            Some(vec![
+                &Value::String("hello!".into()),
+                &Value::String("goodbye".into())
+            ]),
+            result_set.column("greeting")
+        );
+        assert_eq!(None, result_set.column("one"));
+
+        let err = db
+            .execute("SELECT one FROM tbl1 WHERE two IN (1, 2)")
+            .unwrap_err();
+        assert_eq!(
+            Some(&SqlrError::UnsupportedSql(
+                "SELECT one FROM tbl1 WHERE two IN (1, 2)".to_string()
+            )),
+            err.downcast_ref::<SqlrError>()
+        );
+        // valid sql against an unknown table isn't a parser failure
+        let err = db.execute("SELECT one FROM tbl2").unwrap_err();
+        assert_eq!(None, err.downcast_ref::<SqlrError>());
+    }
+
+    #[test]
+    fn query_tests() {
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let mut rows = db
+            .query("SELECT id, name FROM people LIMIT 2 OFFSET 1")
+            .unwrap();
+        assert_eq!(["id", "name"], rows.columns());
+        assert_eq!(
+            vec![Value::Int(2), Value::String("name001".into())],
+            rows.next().unwrap().unwrap()
+        );
+        assert_eq!(3, rows.next().unwrap().unwrap()[0].as_int().unwrap());
+        assert!(rows.next().is_none());
+        assert!(rows.next().is_none());
+
+        // only the pages needed for the first row are read
+        db.pager.clear_cache();
+        let misses = db.pager_stats().misses;
+        let first = db.query("SELECT id FROM people").unwrap().next();
+        assert_eq!(vec![Value::Int(1)], first.unwrap().unwrap());
+        assert!(db.pager_stats().misses - misses < 5);
+
+        let ids = db
+            .query("SELECT id FROM people WHERE age = 10 ORDER BY id DESC LIMIT 3")
+            .unwrap()
+            .map(|row| row.unwrap()[0].as_int().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![461, 411, 361], ids);
+
+        assert!(db.query("SELECT * FROM").is_err());
+        assert!(db.query("SELECT missing FROM people").is_err());
+    }
+
+    #[test]
+    fn seek_row_tests() {
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let table = db.table("people").unwrap();
+        for row_id in [1, 250, 500] {
+            let record = db.seek_row(&table, row_id).unwrap().unwrap();
+            assert_eq!(Some(row_id), record.field(0).unwrap().unwrap().as_int());
+            assert_eq!(
+                Some(format!("name{:03}", row_id - 1).as_str()),
+                record.field(1).unwrap().unwrap().as_str()
+            );
+        }
+        assert!(db.seek_row(&table, 0).unwrap().is_none());
+        assert!(db.seek_row(&table, 501).unwrap().is_none());
+    }
+
+    #[test]
+    fn cyclic_tree_tests() {
+        // the rightmost pointer of an interior page, at offset 8 of its header, leads back to it
+        let point_to_itself = |bytes: &mut Vec<u8>, page_num: usize| {
+            let start = (page_num - 1) * 4096;
+            assert!(matches!(bytes[start], 0x02 | 0x05));
+            bytes[start + 8..start + 12].copy_from_slice(&(page_num as u32).to_be_bytes());
+        };
+
+        let mut bytes = std::fs::read("test_index.db").unwrap();
+        let mut db = Db::from_bytes(bytes.clone()).unwrap();
+        let table = db.table("people").unwrap();
+        let by_age = db.indexes().unwrap().remove(0);
+        point_to_itself(&mut bytes, table.root_page);
+        point_to_itself(&mut bytes, by_age.root_page);
+
+        let mut db = Db::from_bytes(bytes).unwrap();
+        assert_eq!(
+            format!("page {} visited twice", table.root_page),
+            db.seek_row(&table, 500).unwrap_err().to_string()
+        );
+        assert_eq!(
+            format!("page {} visited twice", by_age.root_page),
+            db.index_lookup(&by_age, &Value::Int(49))
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn deep_tree_tests() {
+        // 3000 rows on 512 byte pages take two levels of interior pages
+        let rows = (1..=3000)
+            .map(|i| {
+                vec![
+                    Value::Int(i * 7),
+                    Value::String(Cow::from(format!("row {}", i))),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let bytes = page_writer::write_table_db(512, "CREATE TABLE t(a int, b text)", &rows);
+        let mut db = Db::from_bytes(bytes).unwrap();
+        assert!(db.integrity_check().is_empty());
+        assert_eq!(3, db.page_stats().unwrap().table_interior);
+
+        let mut scanner = db.scanner_for("t").unwrap();
+        let mut row_ids = Vec::new();
+        while let Some(record) = scanner.next_record().unwrap() {
+            let a = record.field(0).unwrap().unwrap().as_int();
+            assert_eq!(Some(record.row_id * 7), a);
+            row_ids.push(record.row_id);
+        }
+        assert_eq!((1..=3000).collect::<Vec<_>>(), row_ids);
+
+        // every leaf is reached through both levels
+        let table = db.table("t").unwrap();
+        for row_id in 1..=3000 {
+            let record = db.seek_row(&table, row_id).unwrap().unwrap();
+            assert_eq!(row_id, record.row_id);
+        }
+        assert!(db.seek_row(&table, 0).unwrap().is_none());
+        assert!(db.seek_row(&table, 3001).unwrap().is_none());
+
+        let result = db.execute("SELECT b FROM t WHERE a = 14000").unwrap();
+        assert_eq!(
+            vec![vec![Value::String(Cow::from("row 2000"))]],
+            result.rows
+        );
+
+        // an empty table is a single leaf without cells
+        let bytes = page_writer::write_table_db(4096, "CREATE TABLE t(a)", &[]);
+        let mut db = Db::from_bytes(bytes).unwrap();
+        assert!(db
+            .scanner_for("t")
+            .unwrap()
+            .next_record()
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn scanner_for_tests() {
+        let mut db = Db::from_file("test.db").unwrap();
+        assert!(db.scanner_for("missing").is_err());
+        let mut scanner = db.scanner_for("TBL1").unwrap();
+        let record = scanner.next_record().unwrap().unwrap();
+        assert_eq!(Some("hello!"), record.field(0).unwrap().unwrap().as_str());
+        assert!(scanner.next_record().unwrap().is_some());
+        assert!(scanner.next_record().unwrap().is_none());
+
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        let mut scanner = db.scanner_for("people").unwrap();
+        let mut ids = Vec::new();
+        while let Some(record) = scanner.next_record().unwrap() {
+            ids.push(record.field(0).unwrap().unwrap().as_int().unwrap());
+        }
+        assert_eq!(vec![1, 5, 42], ids);
+    }
+
+    #[test]
+    fn scanner_reset_tests() {
+        fn rows(scanner: &mut Scanner) -> Vec<(i64, String)> {
+            let mut rows = Vec::new();
+            while let Some(record) = scanner.next_record().unwrap() {
+                let name = record.field(1).unwrap().unwrap().to_string();
+                rows.push((record.row_id, name));
+            }
+            rows
+        }
+
+        // people spans several pages
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let mut scanner = db.scanner_for("people").unwrap();
+        let first = rows(&mut scanner);
+        assert_eq!(500, first.len());
+        scanner.reset();
+        assert_eq!(first, rows(&mut scanner));
+
+        // a scan can be restarted midway
+        scanner.reset();
+        for _ in 0..300 {
+            scanner.next_record().unwrap();
+        }
+        scanner.reset();
+        assert_eq!(first, rows(&mut scanner));
+    }
+
+    #[test]
+    fn short_page_padding_tests() {
+        // the end of the first row of tbl1 is cut off
+        let mut bytes = std::fs::read("test.db").unwrap();
+        bytes.truncate(2 * 4096 - 6);
+        let mut db = Db::from_bytes(bytes.clone()).unwrap();
+        let err = db.scanner_for("tbl1").unwrap().next_record().unwrap_err();
+        assert!(format!("{:#}", err).contains("page 2 is short"));
+
+        let mut db = Db::from_bytes(bytes).unwrap().with_short_page_padding(true);
+        let mut scanner = db.scanner_for("tbl1").unwrap();
+        let rows = scanner
+            .rows::<(String, i64)>()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(("h\0\0\0\0\0".to_string(), 0), rows[0]);
+        assert_eq!(("goodbye".to_string(), 20), rows[1]);
+    }
+
+    #[test]
+    fn table_row_count_tests() {
+        let mut db = Db::from_file("test_index.db").unwrap();
+        assert_eq!(500, db.table_row_count("people").unwrap());
+        assert_eq!(5, db.table_row_count("sqlite_master").unwrap());
+        assert_eq!(
+            "no such table: nope",
+            db.table_row_count("nope").unwrap_err().to_string()
+        );
+        // deleted rows aren't counted
+        let mut db = Db::from_file("test_deleted.db").unwrap();
+        assert_eq!(149, db.table_row_count("people").unwrap());
+        // interior cells hold rows too
+        let mut db = Db::from_file("test_without_rowid.db").unwrap();
+        assert_eq!(300, db.table_row_count("words").unwrap());
+        assert_eq!(3, db.table_row_count("kv").unwrap());
+
+        let rows = (1..=3000).map(|i| vec![Value::Int(i)]).collect::<Vec<_>>();
+        let mut bytes = page_writer::write_table_db(512, "CREATE TABLE t(a int)", &rows);
+        let mut db = Db::from_bytes(bytes.clone()).unwrap();
+        assert_eq!(3000, db.table_row_count("t").unwrap());
+
+        // the root page points back to itself
+        let root_page = db.table("t").unwrap().root_page;
+        let rightmost_pointer = (root_page - 1) * 512 + 8;
+        bytes[rightmost_pointer..rightmost_pointer + 4]
+            .copy_from_slice(&(root_page as u32).to_be_bytes());
+        let mut db = Db::from_bytes(bytes).unwrap();
+        let err = db.table_row_count("t").unwrap_err();
+        assert_eq!(
+            format!(
+                "count the rows of t: page {} is referenced more than once",
+                root_page
+            ),
+            format!("{:#}", err)
+        );
+    }
+
+    #[test]
+    fn scanner_for_without_rowid_tests() {
+        let mut db = Db::from_file("test_without_rowid.db").unwrap();
+        let mut scanner = db.scanner_for("kv").unwrap();
+        let mut rows = Vec::new();
+        while let Some(record) = scanner.next_record().unwrap() {
+            rows.push((
+                record.field(0).unwrap().unwrap().to_string(),
+                record.field(1).unwrap().unwrap().to_string(),
+            ));
+        }
+        assert_eq!(
+            vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "2".to_string()),
+                ("c".to_string(), "".to_string())
+            ],
+            rows
+        );
+
+        // spans several index pages, the records held by interior cells must not be skipped
+        let mut scanner = db.scanner_for("words").unwrap();
+        let mut rows = Vec::new();
+        while let Some(record) = scanner.next_record().unwrap() {
+            let word = record.field(0).unwrap().unwrap().to_string();
+            let len = record.field(1).unwrap().unwrap().as_int().unwrap();
+            let id = record.field(2).unwrap().unwrap().as_int().unwrap();
+            rows.push((id, word, len));
+        }
+        assert_eq!(300, rows.len());
+        assert_eq!((0, "word000".to_string(), 7), rows[0]);
+        assert_eq!((0, "word150".to_string(), 7), rows[1]);
+        assert_eq!((1, "word007".to_string(), 5), rows[2]);
+        // rows come out in primary key order
+        assert!(rows
+            .windows(2)
+            .all(|w| (w[0].0, &w[0].1) < (w[1].0, &w[1].1)));
+    }
+
+    #[test]
+    fn pager_stats_tests() {
+        let mut db = Db::from_file("test.db").unwrap();
+        let initial = db.pager_stats();
+        assert_eq!(1, db.tables().unwrap().len());
+        let after_first = db.pager_stats();
+        assert!(after_first.misses > initial.misses);
+        db.tables().unwrap();
+        let after_second = db.pager_stats();
+        assert_eq!(after_first.misses, after_second.misses);
+        assert!(after_second.hits > after_first.hits);
+    }
+
+    #[test]
+    fn sequences_tests() {
+        let mut db = Db::from_file("test_autoincrement.db").unwrap();
+        assert_eq!(
+            HashMap::from([("events".to_string(), 3), ("jobs".to_string(), 100)]),
+            db.sequences().unwrap()
+        );
+
+        let mut db = Db::from_file("test.db").unwrap();
+        assert!(db.sequences().unwrap().is_empty());
+    }
+
+    #[test]
+    fn check_size_tests() {
+        let bytes = std::fs::read("test.db").unwrap();
+        assert!(Db::from_bytes(bytes.clone()).unwrap().check_size().is_ok());
+        assert!(Db::from_file("test.db").unwrap().check_size().is_ok());
+
+        let truncated = bytes[..4096].to_vec();
+        let err = Db::from_bytes(truncated).unwrap().check_size().unwrap_err();
+        assert!(err.to_string().contains("truncated"));
+
+        let mut trailing = bytes.clone();
+        trailing.extend([0; 10]);
+        let err = Db::from_bytes(trailing).unwrap().check_size().unwrap_err();
+        assert!(err.to_string().contains("10 trailing bytes"));
+
+        // a stale change counter makes the in-header size untrustworthy
+        let mut stale = bytes[..4096].to_vec();
+        stale[27] += 1;
+        let mut db = Db::from_bytes(stale.clone()).unwrap();
+        assert!(!db.header.database_size_is_valid());
+        assert!(db.check_size().is_ok());
+        stale.push(0);
+        assert!(Db::from_bytes(stale).unwrap().check_size().is_err());
+    }
+
+    #[test]
+    fn max_payload_size_tests() {
+        // the roots of t and idx_t_data, the other pages are overflow pages
+        fn read_pages(mut db: Db<impl Pager>) -> anyhow::Result<()> {
+            for page_num in [2, 3] {
+                db.pager.read_page(page_num)?;
+            }
+            Ok(())
+        }
+
+        // the overflowing rows are well within the default
+        assert!(read_pages(Db::from_file("test_overflow.db").unwrap()).is_ok());
+        let bytes = std::fs::read("test_overflow.db").unwrap();
+        let db = Db::from_bytes(bytes).unwrap().with_max_payload_size(1000);
+        let err = read_pages(db).unwrap_err();
+        assert!(format!("{:#}", err).contains("exceeds the maximum of 1000"));
+    }
+
+    #[test]
+    fn page_count_tests() {
+        let mut bytes = std::fs::read("test.db").unwrap();
+        assert_eq!(
+            2,
+            Db::from_bytes(bytes.clone()).unwrap().page_count().unwrap()
+        );
+
+        // the header is trusted while the change counters agree
+        bytes[28..32].copy_from_slice(&5u32.to_be_bytes());
+        assert_eq!(
+            5,
+            Db::from_bytes(bytes.clone()).unwrap().page_count().unwrap()
+        );
+
+        // past a change made by a legacy writer, only the file size can be trusted
+        bytes[27] += 1;
+        let mut db = Db::from_bytes(bytes.clone()).unwrap();
+        assert!(!db.header.database_size_is_valid());
+        assert_eq!(2, db.page_count().unwrap());
+
+        // a zero size isn't valid either
+        bytes[24..32].copy_from_slice(&[0; 8]);
+        bytes[92..96].copy_from_slice(&[0; 4]);
+        let mut db = Db::from_bytes(bytes).unwrap();
+        assert!(!db.header.database_size_is_valid());
+        assert_eq!(2, db.page_count().unwrap());
+    }
+
+    #[test]
+    fn lock_byte_page_tests() {
+        let mut header = Db::from_file("test.db").unwrap().header;
+        assert_eq!(262145, header.lock_byte_page());
+        header.page_size = 65536;
+        assert_eq!(16385, header.lock_byte_page());
+
+        // with 1k pages a pointer map page would land on the lock-byte page
+        let header = Db::from_file("test_autovacuum.db").unwrap().header;
+        let lock_byte_page = header.lock_byte_page();
+        assert_eq!(1048577, lock_byte_page);
+        assert!(header.is_ptrmap_page(lock_byte_page - 205));
+        assert!(!header.is_ptrmap_page(lock_byte_page));
+        assert!(header.is_ptrmap_page(lock_byte_page + 1));
+        // the following ones are back in their usual place
+        assert!(header.is_ptrmap_page(lock_byte_page + 205));
+        assert!(!header.is_ptrmap_page(lock_byte_page + 206));
+    }
+
+    #[test]
+    fn page_stats_tests() {
+        let stats = Db::from_file("test.db").unwrap().page_stats().unwrap();
+        assert_eq!(
+            PageStats {
+                table_leaf: 2,
+                ..PageStats::default()
+            },
+            stats
+        );
+
+        let stats = Db::from_file("test_freelist.db")
+            .unwrap()
+            .page_stats()
+            .unwrap();
+        assert_eq!(
+            PageStats {
+                table_leaf: 8,
+                table_interior: 1,
+                index_leaf: 1,
+                freelist: 51,
+                ..PageStats::default()
+            },
+            stats
+        );
+
+        // 1k pages, two pointer map pages and large blobs spilling onto overflow pages
+        let mut db = Db::from_file("test_autovacuum.db").unwrap();
+        assert!(db.header.is_ptrmap_page(2));
+        assert!(db.header.is_ptrmap_page(207));
+        assert!(!db.header.is_ptrmap_page(3));
+        let stats = db.page_stats().unwrap();
+        assert_eq!(
+            PageStats {
+                table_leaf: 76,
+                table_interior: 1,
+                index_leaf: 65,
+                index_interior: 21,
+                overflow: 20,
+                freelist: 38,
+                ptrmap: 2,
+            },
+            stats
+        );
+        assert_eq!(223, stats.total());
+
+        let bytes = std::fs::read("test.db").unwrap();
+        let err = Db::from_bytes(bytes[..4096].to_vec())
+            .unwrap()
+            .page_stats()
+            .unwrap_err();
+        assert!(err.to_string().contains("truncated"));
+    }
+
+    #[test]
+    fn recover_deleted_tests() {
+        // rows 100 to 250 were deleted without secure_delete, freeing whole leaves
+        let mut db = Db::from_file("test_deleted.db").unwrap();
+        let recovered = db.recover_deleted("people").unwrap();
+        let row_ids = recovered.iter().map(|c| c.row_id).collect::<Vec<_>>();
+        // the leaf holding the first deleted rows became the freelist trunk
+        assert_eq!((171..=250).collect::<Vec<_>>(), row_ids);
+        for record in &recovered {
+            let fields = record.fields().unwrap();
+            assert_eq!(Value::Int(record.row_id), fields[0]);
+            assert_eq!(
+                Some(format!("name{:03}", record.row_id).as_str()),
+                fields[1].as_str()
+            );
+            assert_eq!(Value::Int(record.row_id % 50), fields[2]);
+        }
+
+        // nothing was ever deleted from test.db
+        let mut db = Db::from_file("test.db").unwrap();
+        assert!(db.recover_deleted("tbl1").unwrap().is_empty());
+        assert!(db.recover_deleted("nope").is_err());
+        let mut db = Db::from_file("test_without_rowid.db").unwrap();
+        let table = db.tables().unwrap().remove(0);
+        assert!(db.recover_deleted(&table.name).is_err());
+
+        // a(x int, y text) and b(p int, q text) with 50 and 600 rows, rows 51 to 599 were deleted
+        // from b, its freed leaf also holds a stale copy of its row 600
+        let mut db = Db::from_file("test_recover.db").unwrap();
+        assert!(db.recover_deleted("a").unwrap().is_empty());
+        let recovered = db.recover_deleted("b").unwrap();
+        let row_ids = recovered.iter().map(|c| c.row_id).collect::<Vec<_>>();
+        assert_eq!((311..=599).collect::<Vec<_>>(), row_ids);
+        for record in &recovered {
+            assert_eq!(
+                Some(format!("b{:03}", record.row_id).as_str()),
+                record.field(1).unwrap().unwrap().as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn fits_affinities_tests() {
+        let mut db = Db::from_file("test_recover.db").unwrap();
+        let mut table = db.table("b").unwrap();
+        let record = db.seek_row(&table, 1).unwrap().unwrap();
+        assert!(fits_affinities(&table, &record));
+        table.columns[0].affinity = Affinity::Real;
+        assert!(fits_affinities(&table, &record));
+        table.columns[0].affinity = Affinity::Blob;
+        assert!(fits_affinities(&table, &record));
+        // text affinity would have stored p as text
+        table.columns[0].affinity = Affinity::Text;
+        assert!(!fits_affinities(&table, &record));
+        // q doesn't look like a number, any affinity but text keeps it as it is
+        table.columns[0].affinity = Affinity::Integer;
+        table.columns[1].affinity = Affinity::Numeric;
+        assert!(fits_affinities(&table, &record));
+        // a row id alias is stored as null
+        table.columns[0].primary_key = true;
+        table.columns[0].type_name = Some("INTEGER".to_string());
+        assert!(!fits_affinities(&table, &record));
+    }
+
+    #[test]
+    fn integrity_check_tests() {
+        for file in [
+            "test.db",
+            "test_rowid.db",
+            "test_without_rowid.db",
+            "test_autoincrement.db",
+            "test_freelist.db",
+            "test_index.db",
+            "test_wal.db",
+            "test_64k.db",
+            "test_reserved.db",
+            "test_freeblock.db",
+            "test_alter.db",
+            "test_view.db",
+            "test_join.db",
+            "test_deleted.db",
+            "test_empty.db",
+            "test_recover.db",
+            "test_overflow.db",
+            "test_autovacuum.db",
+            "test_expr_index.db",
+        ] {
+            let problems = Db::from_file(file).unwrap().integrity_check();
+            assert!(problems.is_empty(), "{}: {:?}", file, problems);
+        }
+
+        // the freelist count lives at offset 36
+        let mut bytes = std::fs::read("test_freelist.db").unwrap();
+        bytes[39] += 1;
+        assert_eq!(
+            vec!["freelist: the header counts 52 pages but 51 were found".to_string()],
+            Db::from_bytes(bytes).unwrap().integrity_check()
+        );
+
+        // tbl1's root page points past the end of the file, leaving page 2 unused, its schema
+        // record starts at 4040 and the rootpage field sits after "table", "tbl1" and "tbl1"
+        let mut bytes = std::fs::read("test.db").unwrap();
+        assert_eq!(2, bytes[4059]);
+        bytes[4059] = 9;
+        let problems = Db::from_bytes(bytes).unwrap().integrity_check();
+        assert_eq!(
+            vec![
+                "tbl1: page 9 referenced by the schema is out of range (page count is 2)"
+                    .to_string(),
+                "page 2 is never used".to_string()
+            ],
+            problems
+        );
+
+        // the overflow chain 4 -> 5 -> 6 is cut after page 5
+        let mut bytes = std::fs::read("test_overflow.db").unwrap();
+        assert_eq!([0, 0, 0, 6], bytes[4 * 4096..4 * 4096 + 4]);
+        bytes[4 * 4096 + 3] = 0;
+        let problems = Db::from_bytes(bytes).unwrap().integrity_check();
+        assert_eq!(
+            vec![
+                "idx_t_data: overflow chain of cell 0 of page 3 is missing 1 pages".to_string(),
+                "page 6 is never used".to_string()
+            ],
+            problems
+        );
+    }
 }
@@ -0,0 +1,285 @@
+use std::fmt;
+
+// failures that library consumers may want to tell apart, everything else is an anyhow::Error
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlrError {
+    // the file doesn't start with "SQLite format 3\0", it isn't a sqlite database
+    BadMagic {
+        expected: &'static [u8],
+        actual: Vec<u8>,
+    },
+    // the magic is there but the file ends before the 100 bytes of the header
+    TruncatedHeader {
+        expected: usize,
+        actual: usize,
+    },
+    InvalidPageSize(u16),
+    UnsupportedSchemaFormat(u32),
+    UnknownPageType(u8),
+    TruncatedPage {
+        expected: usize,
+        actual: usize,
+    },
+    // the input ends partway through the page, or before it, e.g. after a truncated transfer
+    ShortPage {
+        page_num: usize,
+        expected: usize,
+        actual: usize,
+    },
+    // a cell pointer pointing outside of the page's cell content area
+    InvalidCellPointer {
+        pointer: usize,
+        range: std::ops::Range<usize>,
+    },
+    // a freeblock overlapping the page header, the cell pointers, the previous freeblock or
+    // running past the end of the page
+    InvalidFreeblock {
+        offset: usize,
+        range: std::ops::Range<usize>,
+    },
+    // a cell count whose pointers don't fit between the page header and the end of the page
+    TooManyCells {
+        cell_count: usize,
+        capacity: usize,
+    },
+    // a cell running past the end of the page or whose size can't be read
+    CellOutOfBounds {
+        pointer: usize,
+        page_size: usize,
+    },
+    // a cell claiming a payload larger than the configured maximum, negative sizes included
+    PayloadTooLarge {
+        size: i64,
+        max: usize,
+    },
+    UnsupportedSerialType(i64),
+    InvalidRecordHeader {
+        header_length: i64,
+        payload_length: usize,
+    },
+    // a record header describing more data than the payload holds
+    FieldOutOfBounds {
+        offset: usize,
+        size: usize,
+        payload_length: usize,
+    },
+    // sql the query parser doesn't handle, attached as context to the parse error
+    UnsupportedSql(String),
+}
+
+pub type Result<T> = std::result::Result<T, SqlrError>;
+
+impl SqlrError {
+    // where the faulty structure starts, relative to the page header like cell pointers are,
+    // for the errors which point into a page
+    pub fn offset_in_page(&self) -> Option<usize> {
+        match self {
+            SqlrError::InvalidCellPointer { pointer, .. }
+            | SqlrError::CellOutOfBounds { pointer, .. } => Some(*pointer),
+            SqlrError::InvalidFreeblock { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SqlrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SqlrError::BadMagic { expected, actual } => write!(
+                f,
+                "not a sqlite database: expected header prefix {}, got {}",
+                hex(expected),
+                hex(actual)
+            ),
+            SqlrError::TruncatedHeader { expected, actual } => write!(
+                f,
+                "truncated database header: expected {} bytes, got {}",
+                expected, actual
+            ),
+            SqlrError::InvalidPageSize(size) => {
+                write!(f, "page size is not a power of 2: {}", size)
+            }
+            SqlrError::UnsupportedSchemaFormat(format) => {
+                write!(f, "unsupported schema format: {}, expected 1 to 4", format)
+            }
+            SqlrError::UnknownPageType(page_type) => write!(f, "unknown page type: {}", page_type),
+            SqlrError::TruncatedPage { expected, actual } => write!(
+                f,
+                "truncated page: expected at least {} bytes, got {}",
+                expected, actual
+            ),
+            SqlrError::ShortPage {
+                page_num,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "page {} is short: expected {} bytes, got {}",
+                page_num, expected, actual
+            ),
+            SqlrError::InvalidCellPointer { pointer, range } => write!(
+                f,
+                "cell pointer {} is outside of the cell content area {}..{}",
+                pointer, range.start, range.end
+            ),
+            SqlrError::InvalidFreeblock { offset, range } => write!(
+                f,
+                "freeblock at offset {} doesn't fit in {}..{}",
+                offset, range.start, range.end
+            ),
+            SqlrError::TooManyCells {
+                cell_count,
+                capacity,
+            } => write!(
+                f,
+                "{} cells don't fit in the page, it has room for {} cell pointers",
+                cell_count, capacity
+            ),
+            SqlrError::CellOutOfBounds { pointer, page_size } => write!(
+                f,
+                "cell at offset {} runs past the end of the page of {} bytes",
+                pointer, page_size
+            ),
+            SqlrError::PayloadTooLarge { size, max } => write!(
+                f,
+                "payload of {} bytes exceeds the maximum of {}",
+                size, max
+            ),
+            SqlrError::UnsupportedSerialType(serial_type) => {
+                write!(f, "unsupported field type: {}", serial_type)
+            }
+            SqlrError::InvalidRecordHeader {
+                header_length,
+                payload_length,
+            } => write!(
+                f,
+                "record header length {} is invalid for a payload of {} bytes",
+                header_length, payload_length
+            ),
+            SqlrError::FieldOutOfBounds {
+                offset,
+                size,
+                payload_length,
+            } => write!(
+                f,
+                "field of size {} at offset {} exceeds payload of length {}",
+                size, offset, payload_length
+            ),
+            SqlrError::UnsupportedSql(sql) => write!(f, "unsupported sql: {}", sql),
+        }
+    }
+}
+
+impl std::error::Error for SqlrError {}
+
+fn hex(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "nothing".to_owned();
+    }
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display_tests() {
+        assert_eq!(
+            "unsupported schema format: 5, expected 1 to 4",
+            SqlrError::UnsupportedSchemaFormat(5).to_string()
+        );
+        assert_eq!(
+            "unknown page type: 12",
+            SqlrError::UnknownPageType(12).to_string()
+        );
+        assert_eq!(
+            "truncated page: expected at least 8 bytes, got 1",
+            SqlrError::TruncatedPage {
+                expected: 8,
+                actual: 1
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "page 3 is short: expected 4096 bytes, got 0",
+            SqlrError::ShortPage {
+                page_num: 3,
+                expected: 4096,
+                actual: 0
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "freeblock at offset 8 doesn't fit in 10..4096",
+            SqlrError::InvalidFreeblock {
+                offset: 8,
+                range: 10..4096
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "cell at offset 4090 runs past the end of the page of 4096 bytes",
+            SqlrError::CellOutOfBounds {
+                pointer: 4090,
+                page_size: 4096
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "payload of -1 bytes exceeds the maximum of 1024",
+            SqlrError::PayloadTooLarge {
+                size: -1,
+                max: 1024
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "not a sqlite database: expected header prefix 5351, got 0001ff",
+            SqlrError::BadMagic {
+                expected: b"SQ",
+                actual: vec![0, 1, 255]
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "not a sqlite database: expected header prefix 5351, got nothing",
+            SqlrError::BadMagic {
+                expected: b"SQ",
+                actual: vec![]
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn into_anyhow_tests() {
+        let parse = || -> anyhow::Result<()> { Err(SqlrError::UnsupportedSerialType(10))? };
+        let err = parse().unwrap_err();
+        assert_eq!(
+            Some(&SqlrError::UnsupportedSerialType(10)),
+            err.downcast_ref::<SqlrError>()
+        );
+    }
+
+    #[test]
+    fn offset_in_page_tests() {
+        assert_eq!(
+            Some(4090),
+            SqlrError::CellOutOfBounds {
+                pointer: 4090,
+                page_size: 4096
+            }
+            .offset_in_page()
+        );
+        assert_eq!(
+            Some(8),
+            SqlrError::InvalidFreeblock {
+                offset: 8,
+                range: 10..4096
+            }
+            .offset_in_page()
+        );
+        assert_eq!(None, SqlrError::UnknownPageType(12).offset_in_page());
+    }
+}
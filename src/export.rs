@@ -0,0 +1,172 @@
+use std::{borrow::Cow, io::Write};
+
+use anyhow::Context;
+
+use crate::{db::Db, paging::pager::Pager, utils, value::Value};
+
+// https://www.rfc-editor.org/rfc/rfc4180
+pub fn to_csv<P: Pager, W: Write>(db: &mut Db<P>, table: &str, mut out: W) -> anyhow::Result<()> {
+    let table = db.table(table)?;
+
+    let header = table
+        .columns
+        .iter()
+        .map(|c| escape_csv_field(&c.name))
+        .collect::<Vec<_>>();
+    write_csv_line(&mut out, &header)?;
+
+    let mut scanner = db.scanner_for(&table.name)?;
+    while let Some(record) = scanner.next_record()? {
+        let fields = record
+            .fields()?
+            .iter()
+            .map(|value| escape_csv_field(&value.to_string()).into_owned())
+            .collect::<Vec<_>>();
+        write_csv_line(&mut out, &fields)?;
+    }
+
+    out.flush().context("flush csv output")
+}
+
+// one json object per line, keyed by column name, blobs are base64 encoded
+pub fn to_ndjson<P: Pager, W: Write>(
+    db: &mut Db<P>,
+    table: &str,
+    mut out: W,
+) -> anyhow::Result<()> {
+    let table = db.table(table)?;
+    let keys = table
+        .columns
+        .iter()
+        .map(|c| escape_json_string(&c.name))
+        .collect::<Vec<_>>();
+
+    let mut scanner = db.scanner_for(&table.name)?;
+    while let Some(record) = scanner.next_record()? {
+        let members = keys
+            .iter()
+            .zip(record.fields()?)
+            .map(|(key, value)| format!("{}:{}", key, to_json_value(&value)))
+            .collect::<Vec<_>>();
+        writeln!(out, "{{{}}}", members.join(",")).context("write json line")?;
+    }
+
+    out.flush().context("flush json output")
+}
+
+// same mapping as the serde implementation of Value
+pub fn to_json_value(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::String(s) => escape_json_string(s),
+        Value::Blob(b) => format!("\"{}\"", utils::encode_base64(b)),
+        Value::Int(i) => i.to_string(),
+        // json has no representation for nan and infinities
+        Value::Float(f) if !f.is_finite() => "null".to_string(),
+        Value::Float(_) => value.to_string(),
+    }
+}
+
+fn escape_json_string(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if c.is_control() => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+// fields containing a separator, a quote or a line break are quoted, with quotes doubled
+pub fn escape_csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+fn write_csv_line<W: Write, S: AsRef<str>>(out: &mut W, fields: &[S]) -> anyhow::Result<()> {
+    let line = fields
+        .iter()
+        .map(|f| f.as_ref())
+        .collect::<Vec<_>>()
+        .join(",");
+    write!(out, "{}\r\n", line).context("write csv line")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escape_csv_field_tests() {
+        assert_eq!("abc", escape_csv_field("abc"));
+        assert_eq!("", escape_csv_field(""));
+        assert_eq!("\"a,b\"", escape_csv_field("a,b"));
+        assert_eq!("\"a \"\"b\"\"\"", escape_csv_field("a \"b\""));
+        assert_eq!("\"a\nb\"", escape_csv_field("a\nb"));
+        assert_eq!("\"a\r\nb\"", escape_csv_field("a\r\nb"));
+    }
+
+    #[test]
+    fn to_json_value_tests() {
+        assert_eq!("null", to_json_value(&Value::Null));
+        assert_eq!("12", to_json_value(&Value::Int(12)));
+        assert_eq!("1.5", to_json_value(&Value::Float(1.5)));
+        assert_eq!("null", to_json_value(&Value::Float(f64::NAN)));
+        assert_eq!(
+            "\"a\\\"b\\\\c\\n\\u0001\"",
+            to_json_value(&Value::String(Cow::from("a\"b\\c\n\u{1}")))
+        );
+        assert_eq!(
+            "\"3q2+7w==\"",
+            to_json_value(&Value::Blob(Cow::from(&[0xde, 0xad, 0xbe, 0xef][..])))
+        );
+    }
+
+    #[test]
+    fn to_ndjson_tests() {
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        let mut out = Vec::new();
+        to_ndjson(&mut db, "people", &mut out).unwrap();
+        assert_eq!(
+            concat!(
+                "{\"id\":1,\"name\":\"alice\",\"age\":30}\n",
+                "{\"id\":5,\"name\":\"bob\",\"age\":null}\n",
+                "{\"id\":42,\"name\":\"carol\",\"age\":25}\n"
+            ),
+            String::from_utf8(out).unwrap()
+        );
+        assert!(to_ndjson(&mut db, "missing", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn to_csv_tests() {
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        let mut out = Vec::new();
+        to_csv(&mut db, "people", &mut out).unwrap();
+        assert_eq!(
+            "id,name,age\r\n1,alice,30\r\n5,bob,\r\n42,carol,25\r\n",
+            String::from_utf8(out).unwrap()
+        );
+        assert!(to_csv(&mut db, "missing", Vec::new()).is_err());
+
+        // the first two rows predate columns b, c and d
+        let mut db = Db::from_file("test_alter.db").unwrap();
+        let mut out = Vec::new();
+        to_csv(&mut db, "t", &mut out).unwrap();
+        assert_eq!(
+            "a,b,c,d\r\none,7,,-1.5\r\ntwo,7,,-1.5\r\nthree,3,x,0\r\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+}
@@ -0,0 +1,14 @@
+pub mod cursor;
+pub mod db;
+pub mod error;
+pub mod export;
+pub mod output;
+pub mod paging;
+pub mod query;
+pub mod record;
+pub mod row;
+pub mod scanner;
+pub mod schema;
+pub mod sql;
+pub mod utils;
+pub mod value;
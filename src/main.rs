@@ -1,35 +1,53 @@
-use std::io::{stdin, BufRead, Write};
+use std::{
+    fs::File,
+    io::{stdin, stdout, BufRead, BufReader, BufWriter, Write},
+};
 
 use anyhow::Context;
-use db::Db;
-
-mod cursor;
-mod db;
-mod paging;
-mod record;
-mod scanner;
-mod utils;
-mod value;
+use sqlr::{
+    db::Db,
+    output::{OutputMode, Renderer},
+    paging::{cell::Cell, page_header::PageHeader},
+    query::{executor::Plan, parser},
+    schema::ObjectKind,
+    value::Value,
+};
 
 fn main() -> anyhow::Result<()> {
-    let db = Db::from_file(
-        std::env::args()
-            .nth(1)
-            .context("missing db file argument")?,
-    )?;
+    let mut args = std::env::args().skip(1);
+    let filename = args.next().context("missing db file argument")?;
+    // --page-size skips the header, for files where it's damaged
+    let db = match args.next().as_deref() {
+        Some("--page-size") => {
+            let page_size = args.next().context("missing --page-size value")?;
+            let page_size = page_size
+                .parse()
+                .with_context(|| format!("invalid page size: {}", page_size))?;
+            Db::from_file_with_page_size(filename, page_size)?
+        }
+        Some(arg) => anyhow::bail!("unknown argument: {}", arg),
+        None => Db::from_file(filename)?,
+    };
     cli(db)
 }
 
-fn cli(mut db: Db) -> anyhow::Result<()> {
+fn cli(db: Db) -> anyhow::Result<()> {
+    let mut session = Session {
+        db,
+        mode: OutputMode::List,
+        headers: false,
+        null_value: String::new(),
+        out: Box::new(stdout()),
+        script_depth: 0,
+    };
+
     print_flushed("sqlr> ")?;
 
     let mut line_buffer = String::new();
 
-    while stdin().lock().read_line(&mut line_buffer).is_ok() {
-        match line_buffer.trim() {
-            ".exit" => break,
-            ".tables" => display_tables(&mut db).context("display tables")?,
-            other => println!("unrecognized command '{}'", other),
+    while let Ok(n) = stdin().lock().read_line(&mut line_buffer) {
+        if n == 0 || !session.run_line(line_buffer.trim())? {
+            break;
         }
 
         print_flushed("sqlr> ")?;
@@ -37,30 +55,368 @@ fn cli(mut db: Db) -> anyhow::Result<()> {
         line_buffer.clear();
     }
 
+    session.out.flush().context("flush output")
+}
+
+// state carried over from one line to the next, whether it comes from stdin or a script
+struct Session {
+    db: Db,
+    mode: OutputMode,
+    headers: bool,
+    // see .nullvalue
+    null_value: String,
+    // where results and dot-command output go, see .output
+    out: Box<dyn Write>,
+    // number of scripts being read, see .read
+    script_depth: usize,
+}
+
+// a script reading itself would otherwise recurse until the stack overflows
+const MAX_SCRIPT_DEPTH: usize = 16;
+
+impl Session {
+    // returns false once .exit is reached
+    fn run_line(&mut self, line: &str) -> anyhow::Result<bool> {
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, Some(arg.trim())),
+            None => (line, None),
+        };
+
+        let (db, out) = (&mut self.db, &mut self.out);
+        match (command, arg) {
+            (".exit", _) => return Ok(false),
+            (".tables", _) => display_tables(db, out).context("display tables")?,
+            (".indexes", table) => display_indexes(db, out, table).context("display indexes")?,
+            (".dbinfo", _) => display_dbinfo(db, out).context("display db info")?,
+            (".dump", _) => dump(db, out).context("dump")?,
+            (".stats", _) => display_stats(db, out).context("display stats")?,
+            (".pageinfo", Some(arg)) => {
+                if let Err(e) = display_page_info(db, out, arg) {
+                    eprintln!("error: {:#}", e);
+                }
+            }
+            (".pageinfo", None) => println!("usage: .pageinfo PAGE"),
+            (".integrity", _) => {
+                display_integrity_check(db, out).context("display integrity check")?
+            }
+            (".mode", None) => println!("current output mode: {}", self.mode.name()),
+            (".mode", Some(arg)) => match OutputMode::parse(arg) {
+                Ok(mode) => self.mode = mode,
+                Err(e) => eprintln!("error: {:#}", e),
+            },
+            (".headers", Some(arg)) if arg.eq_ignore_ascii_case("on") => self.headers = true,
+            (".headers", Some(arg)) if arg.eq_ignore_ascii_case("off") => self.headers = false,
+            (".headers", _) => println!("usage: .headers on|off"),
+            // quotes allow going back to the empty default
+            (".nullvalue", Some(arg)) => {
+                self.null_value = match arg.as_bytes() {
+                    [b'"', .., b'"'] | [b'\'', .., b'\''] => arg[1..arg.len() - 1].to_string(),
+                    _ => arg.to_string(),
+                }
+            }
+            (".nullvalue", None) => println!("usage: .nullvalue STRING"),
+            (".read", Some(filename)) => match self.read_script(filename) {
+                Ok(keep_going) => return Ok(keep_going),
+                Err(e) => eprintln!("error: {:#}", e),
+            },
+            (".read", None) => println!("usage: .read FILE"),
+            (".output", target) => {
+                if let Err(e) = self.redirect_output(target) {
+                    eprintln!("error: {:#}", e);
+                }
+            }
+            (".schema", name) => display_schema(db, out, name).context("display schema")?,
+            ("", _) => (),
+            (other, _) if other.starts_with('.') => println!("unrecognized command '{}'", line),
+            _ => {
+                let renderer = Renderer::new(self.mode).with_null_value(&self.null_value);
+                if let Err(e) = run_select(db, out, line, renderer, self.headers) {
+                    eprintln!("error: {:#}", e);
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    // no target or "stdout" switches back to the standard output, the previous file is flushed
+    // before being closed
+    fn redirect_output(&mut self, target: Option<&str>) -> anyhow::Result<()> {
+        let out: Box<dyn Write> = match target {
+            None | Some("stdout") => Box::new(stdout()),
+            Some(filename) => {
+                let file =
+                    File::create(filename).with_context(|| format!("create {}", filename))?;
+                Box::new(BufWriter::new(file))
+            }
+        };
+        let mut previous = std::mem::replace(&mut self.out, out);
+        previous.flush().context("flush output")
+    }
+
+    // a failing line is reported and the script carries on, only reading the file stops it
+    fn read_script(&mut self, filename: &str) -> anyhow::Result<bool> {
+        if self.script_depth >= MAX_SCRIPT_DEPTH {
+            anyhow::bail!(
+                "{}: scripts are nested more than {} deep",
+                filename,
+                MAX_SCRIPT_DEPTH
+            );
+        }
+        self.script_depth += 1;
+        let keep_going = self.run_script(filename);
+        self.script_depth -= 1;
+        keep_going
+    }
+
+    fn run_script(&mut self, filename: &str) -> anyhow::Result<bool> {
+        let file = File::open(filename).with_context(|| format!("open {}", filename))?;
+        for line in BufReader::new(file).lines() {
+            let line = line.with_context(|| format!("read {}", filename))?;
+            match self.run_line(line.trim()) {
+                Ok(true) => (),
+                Ok(false) => return Ok(false),
+                Err(e) => eprintln!("error: {:#}", e),
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn display_tables(db: &mut Db, out: &mut dyn Write) -> anyhow::Result<()> {
+    for table in db.tables()? {
+        writeln!(out, "{} ", table.name)?;
+    }
+
+    Ok(())
+}
+
+fn display_indexes(db: &mut Db, out: &mut dyn Write, table: Option<&str>) -> anyhow::Result<()> {
+    for index in db.schema()?.indexes(table) {
+        writeln!(out, "{}", index.name)?;
+    }
+
+    Ok(())
+}
+
+// labels mirror the ones printed by sqlite3's .dbinfo
+fn display_dbinfo(db: &mut Db, out: &mut dyn Write) -> anyhow::Result<()> {
+    let header = *db.header();
+    // virtual tables count too, like in sqlite3
+    let table_count = db
+        .schema()?
+        .objects
+        .iter()
+        .filter(|o| o.kind == ObjectKind::Table)
+        .count();
+    // a legacy writer may have left a stale size in the header
+    let page_count = db.page_count()?;
+
+    writeln!(out, "database page size:  {}", header.page_size)?;
+    writeln!(out, "reserved bytes:      {}", header.reserved_space)?;
+    // a freshly created db has no encoding until something is written to it
+    match header.text_encoding {
+        0 => writeln!(out, "text encoding:       0")?,
+        encoding => writeln!(
+            out,
+            "text encoding:       {} ({})",
+            encoding,
+            header.text_encoding_name()
+        )?,
+    }
+    writeln!(out, "database page count: {}", page_count)?;
+    writeln!(out, "freelist page count: {}", header.freelist_count)?;
+    writeln!(out, "schema cookie:       {}", header.schema_cookie)?;
+    writeln!(out, "schema format:       {}", header.schema_format)?;
+    writeln!(out, "user version:        {}", header.user_version)?;
+    writeln!(out, "application id:      {}", header.application_id)?;
+    writeln!(out, "number of tables:    {}", table_count)?;
+
     Ok(())
 }
 
-fn display_tables(db: &mut Db) -> anyhow::Result<()> {
-    let mut scanner = db.scanner(1);
+// offsets are from the start of the page, including the db header on the first page
+fn display_page_info(db: &mut Db, out: &mut dyn Write, arg: &str) -> anyhow::Result<()> {
+    let page_num = arg
+        .parse::<usize>()
+        .with_context(|| format!("invalid page number: {}", arg))?;
+    let page = db.page(page_num)?;
+    let header = page.header;
+    let page_type = match header {
+        PageHeader::TableInteriorPageHeader { .. } => "table interior",
+        PageHeader::TableLeafPageHeader { .. } => "table leaf",
+        PageHeader::IndexInteriorPageHeader { .. } => "index interior",
+        PageHeader::IndexLeafPageHeader { .. } => "index leaf",
+    };
 
-    while let Some(record) = scanner.next_record()? {
-        let type_value = record
-            .field(0)
-            .context("missing type field")
-            .context("invalid type field")?;
+    writeln!(out, "page type:           {}", page_type)?;
+    writeln!(out, "cell count:          {}", header.cell_count())?;
+    writeln!(out, "cell content offset: {}", header.cell_content_offset())?;
+    writeln!(
+        out,
+        "freeblocks:          {} ({} bytes)",
+        page.free_blocks.len(),
+        page.free_block_bytes()
+    )?;
+    writeln!(
+        out,
+        "fragmented bytes:    {}",
+        header.fragmented_bytes_count()
+    )?;
+    writeln!(out, "unallocated bytes:   {}", page.unallocated_bytes())?;
+    if let Some(rightmost_pointer) = header.rightmost_pointer() {
+        writeln!(out, "rightmost pointer:   {}", rightmost_pointer)?;
+    }
+    for (pointer, cell) in page.cell_pointers.iter().zip(&page.cells) {
+        let offset = *pointer as usize + page.header_offset as usize;
+        match cell {
+            Cell::TableLeaf(c) => writeln!(
+                out,
+                "cell at {}: row id {}, {} bytes",
+                offset, c.row_id, c.size
+            )?,
+            Cell::TableInterior(c) => writeln!(
+                out,
+                "cell at {}: key {}, left child {}",
+                offset, c.key, c.left_child_page
+            )?,
+            Cell::IndexLeaf(c) => writeln!(out, "cell at {}: {} bytes", offset, c.size)?,
+            Cell::IndexInterior(c) => writeln!(
+                out,
+                "cell at {}: {} bytes, left child {}",
+                offset, c.size, c.left_child_page
+            )?,
+        }
+    }
+
+    Ok(())
+}
+
+fn display_stats(db: &Db, out: &mut dyn Write) -> anyhow::Result<()> {
+    let stats = db.pager_stats();
+    writeln!(out, "page cache hits:     {}", stats.hits)?;
+    writeln!(out, "page cache misses:   {}", stats.misses)?;
+
+    Ok(())
+}
+
+// prints "ok" when no problem is found, like PRAGMA integrity_check
+fn display_integrity_check(db: &mut Db, out: &mut dyn Write) -> anyhow::Result<()> {
+    let problems = db.integrity_check();
+    if problems.is_empty() {
+        writeln!(out, "ok")?;
+    }
+    for problem in problems {
+        writeln!(out, "{}", problem)?;
+    }
+
+    Ok(())
+}
 
-        if type_value.as_str() == Some("table") {
-            let name_value = record
-                .field(1)
-                .context("missing name field")
-                .context("invalid name field")?;
-            println!("{} ", name_value.as_str().unwrap());
+fn display_schema(db: &mut Db, out: &mut dyn Write, name: Option<&str>) -> anyhow::Result<()> {
+    let schema = db.schema()?;
+    let objects = match name {
+        Some(name) => schema.find(name).into_iter().collect(),
+        None => schema.objects.iter().collect::<Vec<_>>(),
+    };
+
+    // indexes created for UNIQUE and PRIMARY KEY constraints have no sql
+    for sql in objects.iter().filter_map(|o| o.sql.as_ref()) {
+        writeln!(out, "{};", sql)?;
+    }
+
+    Ok(())
+}
+
+// mirrors sqlite3's .dump output for tables
+fn dump(db: &mut Db, out: &mut dyn Write) -> anyhow::Result<()> {
+    writeln!(out, "PRAGMA foreign_keys=OFF;")?;
+    writeln!(out, "BEGIN TRANSACTION;")?;
+
+    // the transaction is closed even when the schema can't be read, so that what was dumped
+    // can still be loaded
+    let result = dump_tables(db, out);
+    writeln!(out, "COMMIT;")?;
+
+    result
+}
+
+fn dump_tables(db: &mut Db, out: &mut dyn Write) -> anyhow::Result<()> {
+    for table in db.tables()? {
+        // sqlite_sequence is created implicitly along with the first AUTOINCREMENT table
+        if table.name.eq_ignore_ascii_case("sqlite_sequence") {
+            writeln!(out, "DELETE FROM sqlite_sequence;")?;
+        } else {
+            writeln!(out, "{};", table.sql)?;
+        }
+
+        let mut scanner = db.scanner_for(&table.name)?;
+        // rows which can't be read are reported and skipped, the scanner moves past them unless
+        // the same error comes back, e.g. when the root page itself is unreadable
+        let mut last_error: Option<String> = None;
+        loop {
+            let row = scanner.next_record().and_then(|record| {
+                record
+                    .map(|record| {
+                        let fields = record.fields()?;
+                        Ok(fields.iter().map(Value::to_sql_literal).collect::<Vec<_>>())
+                    })
+                    .transpose()
+            });
+            let values = match row {
+                Ok(Some(values)) => values,
+                Ok(None) => break,
+                Err(e) => {
+                    let message = format!("{:#}", e);
+                    if last_error.as_ref() == Some(&message) {
+                        break;
+                    }
+                    eprintln!("error: dump {}: {}", table.name, message);
+                    last_error = Some(message);
+                    continue;
+                }
+            };
+            last_error = None;
+            writeln!(
+                out,
+                "INSERT INTO {} VALUES({});",
+                quote_identifier(&table.name),
+                values.join(",")
+            )?;
         }
     }
 
     Ok(())
 }
 
+fn quote_identifier(name: &str) -> String {
+    let is_plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_plain {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+fn run_select(
+    db: &mut Db,
+    mut out: &mut dyn Write,
+    sql: &str,
+    renderer: Renderer,
+    headers: bool,
+) -> anyhow::Result<()> {
+    let select = parser::parse_select(sql)?;
+    let plan = Plan::new(db, &select)?;
+
+    let mut renderer = renderer.with_header(headers.then(|| plan.column_names.clone()));
+    plan.execute(db, |row| renderer.render_row(&mut out, row))?;
+    renderer.finish(&mut out)
+}
+
 fn print_flushed(s: &str) -> anyhow::Result<()> {
     print!("{}", s);
     std::io::stdout().flush().context("flush stdout")
@@ -0,0 +1,283 @@
+use std::io::Write;
+
+use anyhow::Context;
+
+use crate::{export::escape_csv_field, value::Value};
+
+// how SELECT results are printed, mirrors sqlite3's .mode
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OutputMode {
+    // values separated by |
+    List,
+    // https://www.rfc-editor.org/rfc/rfc4180
+    Csv,
+    // values left aligned in fixed width columns
+    Column,
+}
+
+impl OutputMode {
+    pub fn parse(mode: &str) -> anyhow::Result<OutputMode> {
+        match mode.to_ascii_lowercase().as_str() {
+            "list" => Ok(OutputMode::List),
+            "csv" => Ok(OutputMode::Csv),
+            "column" => Ok(OutputMode::Column),
+            _ => anyhow::bail!("unknown mode {}, expected one of list, csv, column", mode),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputMode::List => "list",
+            OutputMode::Csv => "csv",
+            OutputMode::Column => "column",
+        }
+    }
+}
+
+// renders the rows of a single query, finish must be called once every row went through
+#[derive(Debug)]
+pub struct Renderer {
+    mode: OutputMode,
+    // column widths, known once the first batch of rows was seen in column mode
+    widths: Option<Vec<usize>>,
+    batch: Vec<Vec<String>>,
+    // column names, printed before the first row and only if there is one, like sqlite3 does
+    header: Option<Vec<String>>,
+    // printed in place of nulls, empty by default to match sqlite3
+    null_value: String,
+}
+
+impl Renderer {
+    // rows buffered in column mode to compute the widths, later rows wider than that overflow
+    const COLUMN_BATCH_SIZE: usize = 100;
+
+    pub fn new(mode: OutputMode) -> Self {
+        Self {
+            mode,
+            widths: None,
+            batch: Vec::new(),
+            header: None,
+            null_value: String::new(),
+        }
+    }
+
+    pub fn with_header(mut self, header: Option<Vec<String>>) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn with_null_value(mut self, null_value: &str) -> Self {
+        self.null_value = null_value.to_string();
+        self
+    }
+
+    pub fn render_row<W: Write>(&mut self, out: &mut W, row: &[Value]) -> anyhow::Result<()> {
+        let fields = row
+            .iter()
+            .map(|v| match v {
+                Value::Null => self.null_value.clone(),
+                v => v.to_string(),
+            })
+            .collect::<Vec<_>>();
+        match self.mode {
+            OutputMode::Column if self.widths.is_none() => {
+                self.batch.push(fields);
+                if self.batch.len() >= Self::COLUMN_BATCH_SIZE {
+                    self.flush_batch(out)?;
+                }
+                Ok(())
+            }
+            OutputMode::Column => self.write_columns(out, &fields),
+            OutputMode::List | OutputMode::Csv => {
+                if let Some(header) = self.header.take() {
+                    self.write_line(out, &header)?;
+                }
+                self.write_line(out, &fields)
+            }
+        }
+    }
+
+    pub fn finish<W: Write>(&mut self, out: &mut W) -> anyhow::Result<()> {
+        if self.widths.is_none() && !self.batch.is_empty() {
+            self.flush_batch(out)?;
+        }
+        out.flush().context("flush output")
+    }
+
+    fn write_line<W: Write>(&self, out: &mut W, fields: &[String]) -> anyhow::Result<()> {
+        match self.mode {
+            OutputMode::Csv => {
+                let fields = fields
+                    .iter()
+                    .map(|f| escape_csv_field(f))
+                    .collect::<Vec<_>>();
+                write!(out, "{}\r\n", fields.join(",")).context("write row")
+            }
+            OutputMode::List | OutputMode::Column => {
+                writeln!(out, "{}", fields.join("|")).context("write row")
+            }
+        }
+    }
+
+    // the header is part of the batch the widths are computed from, and underlined
+    fn flush_batch<W: Write>(&mut self, out: &mut W) -> anyhow::Result<()> {
+        let mut widths = Vec::new();
+        for fields in self.header.iter().chain(&self.batch) {
+            widths.resize(widths.len().max(fields.len()), 0);
+            for (width, field) in widths.iter_mut().zip(fields) {
+                *width = (*width).max(field.chars().count());
+            }
+        }
+        if let Some(header) = self.header.take() {
+            let underline = widths.iter().map(|&w| "-".repeat(w)).collect::<Vec<_>>();
+            self.widths = Some(widths);
+            self.write_columns(out, &header)?;
+            self.write_columns(out, &underline)?;
+        } else {
+            self.widths = Some(widths);
+        }
+        for fields in std::mem::take(&mut self.batch) {
+            self.write_columns(out, &fields)?;
+        }
+        Ok(())
+    }
+
+    fn write_columns<W: Write>(&self, out: &mut W, fields: &[String]) -> anyhow::Result<()> {
+        let widths = self.widths.as_deref().unwrap_or_default();
+        let line = fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| format!("{:width$}", f, width = widths.get(i).copied().unwrap_or(0)))
+            .collect::<Vec<_>>()
+            .join("  ");
+        writeln!(out, "{}", line.trim_end()).context("write row")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    fn render(mode: OutputMode, rows: &[Vec<Value>]) -> String {
+        render_with_header(mode, None, rows)
+    }
+
+    fn render_with_header(
+        mode: OutputMode,
+        header: Option<&[&str]>,
+        rows: &[Vec<Value>],
+    ) -> String {
+        let header = header.map(|h| h.iter().map(|c| c.to_string()).collect());
+        let mut out = Vec::new();
+        let mut renderer = Renderer::new(mode).with_header(header);
+        for row in rows {
+            renderer.render_row(&mut out, row).unwrap();
+        }
+        renderer.finish(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn rows() -> Vec<Vec<Value<'static>>> {
+        vec![
+            vec![
+                Value::Int(1),
+                Value::String(Cow::from("alice, jr")),
+                Value::Int(30),
+            ],
+            vec![Value::Int(42), Value::String(Cow::from("bob")), Value::Null],
+        ]
+    }
+
+    #[test]
+    fn parse_output_mode_tests() {
+        assert_eq!(OutputMode::List, OutputMode::parse("list").unwrap());
+        assert_eq!(OutputMode::Csv, OutputMode::parse("CSV").unwrap());
+        assert_eq!(OutputMode::Column, OutputMode::parse("column").unwrap());
+        assert!(OutputMode::parse("table").is_err());
+        assert_eq!("column", OutputMode::Column.name());
+    }
+
+    #[test]
+    fn render_list_tests() {
+        assert_eq!(
+            "1|alice, jr|30\n42|bob|\n",
+            render(OutputMode::List, &rows())
+        );
+        assert_eq!("", render(OutputMode::List, &[]));
+    }
+
+    #[test]
+    fn render_csv_tests() {
+        assert_eq!(
+            "1,\"alice, jr\",30\r\n42,bob,\r\n",
+            render(OutputMode::Csv, &rows())
+        );
+    }
+
+    #[test]
+    fn render_column_tests() {
+        assert_eq!(
+            "1   alice, jr  30\n42  bob\n",
+            render(OutputMode::Column, &rows())
+        );
+        assert_eq!("", render(OutputMode::Column, &[]));
+
+        // widths come from the first batch, later rows overflow
+        let mut rows = vec![vec![Value::Int(1), Value::Int(2)]; Renderer::COLUMN_BATCH_SIZE];
+        rows.push(vec![Value::Int(100), Value::Int(2)]);
+        let rendered = render(OutputMode::Column, &rows);
+        let lines = rendered.lines().collect::<Vec<_>>();
+        assert_eq!("1  2", lines[0]);
+        assert_eq!("100  2", lines[Renderer::COLUMN_BATCH_SIZE]);
+    }
+
+    #[test]
+    fn render_header_tests() {
+        let header = Some(&["id", "name", "age"][..]);
+        assert_eq!(
+            "id|name|age\n1|alice, jr|30\n42|bob|\n",
+            render_with_header(OutputMode::List, header, &rows())
+        );
+        assert_eq!(
+            "id,name,age\r\n1,\"alice, jr\",30\r\n42,bob,\r\n",
+            render_with_header(OutputMode::Csv, header, &rows())
+        );
+        assert_eq!(
+            concat!(
+                "id  name       age\n",
+                "--  ---------  ---\n",
+                "1   alice, jr  30\n",
+                "42  bob\n"
+            ),
+            render_with_header(OutputMode::Column, header, &rows())
+        );
+        // nothing at all without rows
+        for mode in [OutputMode::List, OutputMode::Csv, OutputMode::Column] {
+            assert_eq!("", render_with_header(mode, header, &[]));
+        }
+    }
+
+    #[test]
+    fn render_null_value_tests() {
+        let render = |mode: OutputMode| {
+            let mut out = Vec::new();
+            let mut renderer = Renderer::new(mode).with_null_value("NULL");
+            for row in rows() {
+                renderer.render_row(&mut out, &row).unwrap();
+            }
+            renderer.finish(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!("1|alice, jr|30\n42|bob|NULL\n", render(OutputMode::List));
+        assert_eq!(
+            "1,\"alice, jr\",30\r\n42,bob,NULL\r\n",
+            render(OutputMode::Csv)
+        );
+        assert_eq!(
+            "1   alice, jr  30\n42  bob        NULL\n",
+            render(OutputMode::Column)
+        );
+    }
+}
@@ -1,9 +1,93 @@
 use crate::utils;
 
+// larger payloads are rejected before anything is allocated for them, sqlite's own limit on the
+// length of a value is a billion bytes
+pub const DEFAULT_MAX_PAYLOAD_SIZE: usize = 1 << 30;
+
+use super::page_header::PageHeader;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Cell {
     TableLeaf(TableLeafCell),
     TableInterior(TableInteriorCell),
+    IndexLeaf(IndexLeafCell),
+    IndexInterior(IndexInteriorCell),
+}
+
+impl Cell {
+    // the kind of cell is determined by the page it lives in
+    pub fn parser(header: &PageHeader) -> fn(&[u8]) -> crate::error::Result<Cell> {
+        match header {
+            PageHeader::TableInteriorPageHeader { .. } => TableInteriorCell::parse,
+            PageHeader::TableLeafPageHeader { .. } => TableLeafCell::parse,
+            PageHeader::IndexInteriorPageHeader { .. } => IndexInteriorCell::parse,
+            PageHeader::IndexLeafPageHeader { .. } => IndexLeafCell::parse,
+        }
+    }
+
+    // the number of bytes the cell starting the buffer takes in its page, None when the buffer
+    // ends before its size does or when the size is negative
+    // payloads too large for the page only have their start stored in the cell, followed by the
+    // number of the first overflow page holding the rest
+    pub fn size_in_page(buffer: &[u8], header: &PageHeader, usable_size: usize) -> Option<usize> {
+        Self::layout(buffer, header, usable_size).map(|(size, _)| size)
+    }
+
+    // the number of the first overflow page of the cell starting the buffer, None when the whole
+    // payload is stored in the page or when the cell can't be read
+    pub fn overflow_page(buffer: &[u8], header: &PageHeader, usable_size: usize) -> Option<u32> {
+        match Self::layout(buffer, header, usable_size)? {
+            (size, true) => {
+                let pointer = buffer.get(size.checked_sub(4)?..size)?;
+                Some(utils::read_be_double_word_at(pointer, 0).1)
+            }
+            (_, false) => None,
+        }
+    }
+
+    // the size of the cell in its page and whether its payload spills onto overflow pages
+    fn layout(buffer: &[u8], header: &PageHeader, usable_size: usize) -> Option<(usize, bool)> {
+        let (mut offset, table) = match header {
+            PageHeader::TableInteriorPageHeader { .. } => {
+                let (n, _) = utils::try_read_varint_at(buffer, 4)?;
+                return Some((4 + n as usize, false));
+            }
+            PageHeader::TableLeafPageHeader { .. } => (0, true),
+            PageHeader::IndexInteriorPageHeader { .. } => (4, false),
+            PageHeader::IndexLeafPageHeader { .. } => (0, false),
+        };
+        let (n, payload_size) = utils::try_read_varint_at(buffer, offset)?;
+        offset += n as usize;
+        if table {
+            let (n, _) = utils::try_read_varint_at(buffer, offset)?;
+            offset += n as usize;
+        }
+        let payload_size = usize::try_from(payload_size).ok()?;
+        let local_size = local_payload_size(payload_size, usable_size, table);
+        let overflows = local_size < payload_size;
+        let overflow_pointer = if overflows { 4 } else { 0 };
+        Some((offset + local_size + overflow_pointer, overflows))
+    }
+
+    // the size of the payload as declared by the cell, which can exceed what the page holds
+    pub fn payload_size(&self) -> Option<i64> {
+        match self {
+            Cell::TableLeaf(cell) => Some(cell.size),
+            Cell::IndexLeaf(cell) => Some(cell.size),
+            Cell::IndexInterior(cell) => Some(cell.size),
+            Cell::TableInterior(_) => None,
+        }
+    }
+
+    // the record stored in the cell, interior table cells only hold a key
+    pub fn payload(&self) -> Option<&[u8]> {
+        match self {
+            Cell::TableLeaf(cell) => Some(&cell.payload),
+            Cell::IndexLeaf(cell) => Some(&cell.payload),
+            Cell::IndexInterior(cell) => Some(&cell.payload),
+            Cell::TableInterior(_) => None,
+        }
+    }
 }
 
 impl From<TableLeafCell> for Cell {
@@ -18,6 +102,18 @@ impl From<TableInteriorCell> for Cell {
     }
 }
 
+impl From<IndexLeafCell> for Cell {
+    fn from(cell: IndexLeafCell) -> Self {
+        Cell::IndexLeaf(cell)
+    }
+}
+
+impl From<IndexInteriorCell> for Cell {
+    fn from(cell: IndexInteriorCell) -> Self {
+        Cell::IndexInterior(cell)
+    }
+}
+
 // cells in an interior page are ordered by key
 #[derive(Debug, Clone, PartialEq)]
 pub struct TableInteriorCell {
@@ -28,7 +124,7 @@ pub struct TableInteriorCell {
 }
 
 impl TableInteriorCell {
-    pub fn parse(mut buffer: &[u8]) -> anyhow::Result<Cell> {
+    pub fn parse(mut buffer: &[u8]) -> crate::error::Result<Cell> {
         let (n, left_child_page) = utils::read_be_double_word_at(buffer, 0);
         buffer = &buffer[n as usize..];
 
@@ -53,35 +149,114 @@ impl TableLeafCell {
     // - size of the payload: varint
     // - row id: varint
     // - payload
-    pub fn parse(mut buffer: &[u8]) -> anyhow::Result<Cell> {
+    pub fn parse(mut buffer: &[u8]) -> crate::error::Result<Cell> {
         let (n, size) = utils::read_varint_at(buffer, 0);
         buffer = &buffer[n as usize..];
 
         let (n, row_id) = utils::read_varint_at(buffer, 0);
         buffer = &buffer[n as usize..];
 
-        let su = size as usize;
-        let payload = if su <= buffer.len() {
-            buffer[..su].to_vec()
-        } else {
-            buffer.to_vec()
-        };
-
         Ok(TableLeafCell {
             size,
             row_id,
-            payload,
+            payload: local_payload(buffer, size),
+        }
+        .into())
+    }
+}
+
+// cells in an index page are ordered by their record, there is no row id
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexLeafCell {
+    pub size: i64,
+    pub payload: Vec<u8>,
+}
+
+impl IndexLeafCell {
+    // format is:
+    // - size of the payload: varint
+    // - payload
+    pub fn parse(mut buffer: &[u8]) -> crate::error::Result<Cell> {
+        let (n, size) = utils::read_varint_at(buffer, 0);
+        buffer = &buffer[n as usize..];
+
+        Ok(IndexLeafCell {
+            size,
+            payload: local_payload(buffer, size),
+        }
+        .into())
+    }
+}
+
+// unlike table interior cells, index interior cells carry a full record which sorts after every
+// record in the left child page
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexInteriorCell {
+    pub left_child_page: u32,
+    pub size: i64,
+    pub payload: Vec<u8>,
+}
+
+impl IndexInteriorCell {
+    // format is:
+    // - left child page: 4 bytes
+    // - size of the payload: varint
+    // - payload
+    pub fn parse(mut buffer: &[u8]) -> crate::error::Result<Cell> {
+        let (n, left_child_page) = utils::read_be_double_word_at(buffer, 0);
+        buffer = &buffer[n as usize..];
+
+        let (n, size) = utils::read_varint_at(buffer, 0);
+        buffer = &buffer[n as usize..];
+
+        Ok(IndexInteriorCell {
+            left_child_page,
+            size,
+            payload: local_payload(buffer, size),
         }
         .into())
     }
 }
 
+// how much of a payload is stored in the cell, the thresholds depend on the kind of page
+// https://www.sqlite.org/fileformat2.html#b_tree_pages
+pub fn local_payload_size(payload_size: usize, usable_size: usize, table_leaf: bool) -> usize {
+    let max_local = if table_leaf {
+        usable_size.saturating_sub(35)
+    } else {
+        (usable_size.saturating_sub(12) * 64 / 255).saturating_sub(23)
+    };
+    if payload_size <= max_local {
+        return payload_size;
+    }
+    let min_local = (usable_size.saturating_sub(12) * 32 / 255).saturating_sub(23);
+    let local_size = min_local
+        + (payload_size - min_local)
+            .checked_rem(usable_size.saturating_sub(4))
+            .unwrap_or(0);
+    if local_size <= max_local {
+        local_size
+    } else {
+        min_local
+    }
+}
+
+// payloads spilling onto overflow pages are truncated to what's stored in the page
+fn local_payload(buffer: &[u8], size: i64) -> Vec<u8> {
+    let su = size as usize;
+    if su <= buffer.len() {
+        buffer[..su].to_vec()
+    } else {
+        buffer.to_vec()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
     #[test]
-    fn parse_table_leaf_cell_tests() -> () {
+    fn parse_table_leaf_cell_tests() {
         let size = 10;
         let row_id = 2;
         let payload = 127;
@@ -97,7 +272,76 @@ mod test {
     }
 
     #[test]
-    fn parse_table_interior_cell_tests() -> () {
+    fn local_payload_size_tests() {
+        // a 10000 byte blob in a 4096 byte page, as stored by sqlite
+        assert_eq!(1821, local_payload_size(10005, 4096, true));
+        assert_eq!(489, local_payload_size(10006, 4096, false));
+        assert_eq!(3004, local_payload_size(3004, 4096, true));
+        assert_eq!(1002, local_payload_size(1002, 4096, false));
+        assert_eq!(489, local_payload_size(1504, 4096, false));
+        // nonsensical usable sizes don't panic
+        assert_eq!(0, local_payload_size(10, 0, true));
+        assert_eq!(0, local_payload_size(10, 4, false));
+    }
+
+    #[test]
+    fn size_in_page_tests() {
+        let header = |page_type: u8| {
+            let mut buffer = [0; 12];
+            buffer[0] = page_type;
+            PageHeader::parse(&buffer).unwrap()
+        };
+        // left child page then key
+        assert_eq!(
+            Some(6),
+            Cell::size_in_page(&[0, 0, 0, 2, 0x81, 0], &header(5), 4096)
+        );
+        // payload size, row id then payload
+        assert_eq!(
+            Some(5),
+            Cell::size_in_page(&[3, 1, 1, 2, 3], &header(13), 4096)
+        );
+        // payload size then payload, followed by the overflow page number
+        let mut buffer = vec![0x8f, 0x50];
+        buffer.extend([0; 600]);
+        assert_eq!(
+            Some(2 + 489 + 4),
+            Cell::size_in_page(&buffer, &header(10), 4096)
+        );
+        assert_eq!(
+            Some(4 + 2 + 489 + 4),
+            Cell::size_in_page(&[&[0, 0, 0, 2][..], &buffer].concat(), &header(2), 4096)
+        );
+        assert_eq!(None, Cell::size_in_page(&[0x81], &header(13), 4096));
+        assert_eq!(None, Cell::size_in_page(&[0, 0, 0], &header(5), 4096));
+        assert_eq!(None, Cell::size_in_page(&[0xff; 9], &header(13), 4096));
+    }
+
+    #[test]
+    fn overflow_page_tests() {
+        let header = |page_type: u8| {
+            let mut buffer = [0; 12];
+            buffer[0] = page_type;
+            PageHeader::parse(&buffer).unwrap()
+        };
+        // the overflow page number follows the part of the payload stored in the page
+        let mut buffer = vec![0x8f, 0x50];
+        buffer.extend([0; 489]);
+        buffer.extend([0, 0, 0, 7]);
+        assert_eq!(Some(7), Cell::overflow_page(&buffer, &header(10), 4096));
+        assert_eq!(None, Cell::overflow_page(&buffer[..200], &header(10), 4096));
+        assert_eq!(
+            None,
+            Cell::overflow_page(&[3, 1, 1, 2, 3], &header(13), 4096)
+        );
+        assert_eq!(
+            None,
+            Cell::overflow_page(&[0, 0, 0, 2, 0x81, 0], &header(5), 4096)
+        );
+    }
+
+    #[test]
+    fn parse_table_interior_cell_tests() {
         let left_child_page = 10;
         let key = 127;
         let input = [0, 0, 0, left_child_page, key];
@@ -109,4 +353,35 @@ mod test {
         assert!(res.is_ok());
         assert_eq!(expected, res.unwrap());
     }
+
+    #[test]
+    fn parse_index_leaf_cell_tests() {
+        let res = IndexLeafCell::parse(&[2, 127, 1, 5]);
+        let expected = Cell::IndexLeaf(IndexLeafCell {
+            size: 2,
+            payload: vec![127, 1],
+        });
+        assert_eq!(expected, res.unwrap());
+        assert_eq!(Some(&[127, 1][..]), expected.payload());
+    }
+
+    #[test]
+    fn parse_index_interior_cell_tests() {
+        let res = IndexInteriorCell::parse(&[0, 0, 1, 0, 3, 2, 9, 13]);
+        let expected = Cell::IndexInterior(IndexInteriorCell {
+            left_child_page: 256,
+            size: 3,
+            payload: vec![2, 9, 13],
+        });
+        assert_eq!(expected, res.unwrap());
+        assert_eq!(Some(&[2, 9, 13][..]), expected.payload());
+        assert_eq!(
+            None,
+            Cell::from(TableInteriorCell {
+                left_child_page: 1,
+                key: 1
+            })
+            .payload()
+        );
+    }
 }
@@ -0,0 +1,134 @@
+use super::{
+    cell::Cell,
+    page::{Page, HEADER_SIZE},
+    page_header::PageHeader,
+};
+
+// keeps the raw page bytes around and only parses the cells that are asked for
+#[derive(Debug, Clone, PartialEq)]
+pub struct LazyPage {
+    pub header: PageHeader,
+    pub cell_pointers: Vec<u16>,
+    pub free_blocks: Vec<(u16, u16)>,
+    header_offset: u16,
+    // the page content, without the db header for the first page
+    buffer: Vec<u8>,
+}
+
+impl LazyPage {
+    // the reserved bytes past usable_size are dropped
+    pub fn parse(
+        mut buffer: Vec<u8>,
+        page_num: usize,
+        usable_size: usize,
+    ) -> anyhow::Result<LazyPage> {
+        let ptr_offset = if page_num == 1 { HEADER_SIZE } else { 0 };
+        if buffer.len() < ptr_offset.max(usable_size) {
+            anyhow::bail!("page buffer is too short: {} bytes", buffer.len());
+        }
+        buffer.truncate(usable_size);
+        buffer.drain(..ptr_offset.min(buffer.len()));
+
+        let header = PageHeader::parse(&buffer)?;
+        let pointers_buffer = buffer.get(header.byte_size()..).unwrap_or_default();
+        let cell_pointers = Page::parse_cell_pointers(
+            pointers_buffer,
+            header.cell_count() as usize,
+            ptr_offset as u16,
+        );
+        Page::check_cell_pointers(&header, &cell_pointers, buffer.len())?;
+        let free_blocks =
+            Page::parse_free_blocks(&buffer, &header, cell_pointers.len(), ptr_offset as u16)?;
+
+        Ok(LazyPage {
+            header,
+            cell_pointers,
+            free_blocks,
+            header_offset: ptr_offset as u16,
+            buffer,
+        })
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.cell_pointers.len()
+    }
+
+    pub fn cell(&self, i: usize) -> anyhow::Result<Cell> {
+        let ptr = *self.cell_pointers.get(i).ok_or_else(|| {
+            anyhow::anyhow!(
+                "cell index {} out of range for a page of {} cells",
+                i,
+                self.cell_count()
+            )
+        })? as usize;
+        let cell_buffer = self
+            .buffer
+            .get(ptr..)
+            .ok_or_else(|| anyhow::anyhow!("cell pointer {} is past the end of the page", ptr))?;
+        Ok(Cell::parser(&self.header)(cell_buffer)?)
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = anyhow::Result<Cell>> + '_ {
+        (0..self.cell_count()).map(|i| self.cell(i))
+    }
+
+    // parses every cell, equivalent to Page::parse over the same bytes
+    pub fn to_page(&self) -> anyhow::Result<Page> {
+        Ok(Page {
+            header: self.header,
+            cell_pointers: self.cell_pointers.clone(),
+            cells: self.cells().collect::<anyhow::Result<Vec<_>>>()?,
+            free_blocks: self.free_blocks.clone(),
+            header_offset: self.header_offset,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::paging::cell::TableLeafCell;
+
+    use super::*;
+
+    #[test]
+    fn cell_tests() {
+        assert!(LazyPage::parse(vec![12], 0, 1).is_err());
+        assert!(LazyPage::parse(vec![13; 50], 1, 50).is_err());
+
+        // the second cell pointer is out of bounds
+        let buffer = vec![13, 0, 0, 0, 2, 0, 0, 0, 0, 12, 0, 99, 10, 2, 127];
+        assert!(LazyPage::parse(buffer.clone(), 0, buffer.len()).is_err());
+
+        let buffer = vec![
+            // page header w/ 2 as cell count
+            13, 0, 0, 0, 2, 0, 0, 0, // cell pointers
+            0, 12, 0, 15, // leaf cells (size, row id, payload)
+            1, 2, 127, 1, 3, 128,
+        ];
+        let page = LazyPage::parse(buffer.clone(), 0, buffer.len()).unwrap();
+        assert_eq!(2, page.cell_count());
+        assert_eq!(
+            Cell::from(TableLeafCell {
+                size: 1,
+                row_id: 3,
+                payload: vec![128],
+            }),
+            page.cell(1).unwrap()
+        );
+        assert!(page.cell(2).is_err());
+        assert_eq!(2, page.to_page().unwrap().cells.len());
+    }
+
+    #[test]
+    fn to_page_tests() {
+        let bytes = std::fs::read("test.db").unwrap();
+        for page_num in 1..=2 {
+            let buffer = bytes[(page_num - 1) * 4096..page_num * 4096].to_vec();
+            let expected = Page::parse(&buffer, page_num, buffer.len()).unwrap();
+            let lazy = LazyPage::parse(buffer, page_num, 4096).unwrap();
+            assert_eq!(expected.cells.len(), lazy.cell_count());
+            assert_eq!(expected.cells[0], lazy.cell(0).unwrap());
+            assert_eq!(expected, lazy.to_page().unwrap());
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::{BTreeMap, HashMap};
+
+// keeps track of the order in which keys were last used
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lru {
+    clock: u64,
+    ticks: HashMap<usize, u64>,
+    by_tick: BTreeMap<u64, usize>,
+}
+
+impl Lru {
+    pub fn touch(&mut self, key: usize) {
+        self.clock += 1;
+        if let Some(previous) = self.ticks.insert(key, self.clock) {
+            self.by_tick.remove(&previous);
+        }
+        self.by_tick.insert(self.clock, key);
+    }
+
+    pub fn remove(&mut self, key: usize) {
+        if let Some(tick) = self.ticks.remove(&key) {
+            self.by_tick.remove(&tick);
+        }
+    }
+
+    // removes and returns the least recently used key
+    pub fn pop(&mut self) -> Option<usize> {
+        let (_, key) = self.by_tick.pop_first()?;
+        self.ticks.remove(&key);
+        Some(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lru_tests() {
+        let mut lru = Lru::default();
+        assert!(lru.is_empty());
+        assert_eq!(None, lru.pop());
+        lru.touch(1);
+        lru.touch(2);
+        lru.touch(3);
+        lru.touch(1);
+        assert_eq!(3, lru.len());
+        assert_eq!(Some(2), lru.pop());
+        lru.remove(3);
+        assert_eq!(Some(1), lru.pop());
+        assert_eq!(None, lru.pop());
+        assert!(lru.is_empty());
+    }
+}
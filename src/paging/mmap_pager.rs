@@ -0,0 +1,205 @@
+use std::{borrow::Cow, collections::HashMap, fs::File, path::Path, rc::Rc};
+
+use anyhow::Context;
+use memmap2::Mmap;
+
+use crate::{
+    error::SqlrError,
+    paging::{
+        cell::DEFAULT_MAX_PAYLOAD_SIZE,
+        page::{self, Page},
+        pager::Pager,
+        pager_stats::PagerStats,
+    },
+};
+
+// pages are sliced straight out of a read-only mapping of the whole file instead of being read
+// with a seek and a read each
+//
+// the mapping reflects the file as it changes: modifying it while it is mapped makes pages
+// inconsistent, and truncating it makes accessing the pages past the new end crash the process,
+// the file must be left alone for as long as the pager is alive
+#[derive(Debug)]
+pub struct MmapPager {
+    mmap: Mmap,
+    pub page_size: usize,
+    // bytes at the end of every page which b-tree content can't use
+    reserved_space: usize,
+    // pages with a cell claiming a larger payload fail to load
+    max_payload_size: usize,
+    pub pages: HashMap<usize, Rc<Page>>,
+    stats: PagerStats,
+    // a last page cut short is padded with zeros instead of failing to load
+    pad_short_page: bool,
+}
+
+// safety: the file is only read through the mapping, see MmapPager for what the caller must
+// guarantee about the file while it is mapped
+pub fn map(filename: impl AsRef<Path>) -> anyhow::Result<Mmap> {
+    let file = File::open(filename.as_ref()).context("open db file")?;
+    unsafe { Mmap::map(&file) }.context("map db file")
+}
+
+impl MmapPager {
+    pub fn new(mmap: Mmap, page_size: usize) -> Self {
+        Self {
+            mmap,
+            page_size,
+            reserved_space: 0,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
+            pages: HashMap::new(),
+            stats: PagerStats::default(),
+            pad_short_page: false,
+        }
+    }
+
+    pub fn with_reserved_space(mut self, reserved_space: usize) -> Self {
+        self.reserved_space = reserved_space;
+        self
+    }
+
+    pub fn with_max_payload_size(mut self, max_payload_size: usize) -> Self {
+        self.max_payload_size = max_payload_size;
+        self
+    }
+
+    pub fn open(filename: impl AsRef<Path>, page_size: usize) -> anyhow::Result<Self> {
+        Ok(Self::new(map(filename)?, page_size))
+    }
+
+    // like the file pager, a last page cut short is padded with zeros instead of failing to load
+    pub fn with_short_page_padding(mut self, pad_short_page: bool) -> Self {
+        self.pad_short_page = pad_short_page;
+        self
+    }
+
+    // only a padded short page is copied out of the mapping
+    fn page_bytes(&self, page_num: usize) -> anyhow::Result<Cow<'_, [u8]>> {
+        let offset = page_num.saturating_sub(1) * self.page_size;
+        let bytes = self.mmap.get(offset..).unwrap_or_default();
+        if bytes.len() >= self.page_size {
+            return Ok(Cow::Borrowed(&bytes[..self.page_size]));
+        }
+        if !self.pad_short_page || bytes.is_empty() {
+            return Err(SqlrError::ShortPage {
+                page_num,
+                expected: self.page_size,
+                actual: bytes.len(),
+            }
+            .into());
+        }
+        let mut buffer = bytes.to_vec();
+        buffer.resize(self.page_size, 0);
+        Ok(Cow::Owned(buffer))
+    }
+}
+
+impl Pager for MmapPager {
+    fn read_page(&mut self, page_num: usize) -> anyhow::Result<Rc<Page>> {
+        if let Some(page) = self.pages.get(&page_num) {
+            self.stats.hits += 1;
+            return Ok(Rc::clone(page));
+        }
+
+        self.stats.misses += 1;
+        let page = Rc::new(self.load_page(page_num)?);
+        self.pages.insert(page_num, Rc::clone(&page));
+        Ok(page)
+    }
+
+    fn load_page(&mut self, page_num: usize) -> anyhow::Result<Page> {
+        let usable_size = self.page_size.saturating_sub(self.reserved_space);
+        let locate = |e| page::locate_error(e, page_num, self.page_size);
+        let page =
+            Page::parse(&self.page_bytes(page_num)?, page_num, usable_size).map_err(locate)?;
+        page.check_payload_sizes(self.max_payload_size)
+            .map_err(locate)?;
+        Ok(page)
+    }
+
+    fn load_raw_page(&mut self, page_num: usize) -> anyhow::Result<Vec<u8>> {
+        self.page_bytes(page_num).map(Cow::into_owned)
+    }
+
+    fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    fn input_size(&mut self) -> anyhow::Result<u64> {
+        Ok(self.mmap.len() as u64)
+    }
+
+    fn clear_cache(&mut self) {
+        self.pages.clear();
+    }
+
+    fn invalidate(&mut self, page_num: usize) {
+        self.pages.remove(&page_num);
+    }
+
+    fn stats(&self) -> PagerStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::paging::pager::FilePager;
+
+    use super::*;
+
+    #[test]
+    fn mmap_pager_tests() {
+        let mut pager = MmapPager::open("test.db", 4096).unwrap();
+        let mut file_pager = FilePager::new(File::open("test.db").unwrap(), 4096);
+        for page_num in [1, 2] {
+            assert_eq!(
+                file_pager.load_page(page_num).unwrap(),
+                *pager.read_page(page_num).unwrap()
+            );
+            assert_eq!(
+                file_pager.load_raw_page(page_num).unwrap(),
+                pager.load_raw_page(page_num).unwrap()
+            );
+        }
+        assert!(pager.read_page(3).is_err());
+        assert_eq!(8192, pager.input_size().unwrap());
+
+        pager.read_page(2).unwrap();
+        assert_eq!(PagerStats { hits: 1, misses: 3 }, pager.stats());
+        pager.clear_cache();
+        assert!(pager.pages.is_empty());
+    }
+
+    #[test]
+    fn short_page_tests() {
+        // the last 6 bytes of page 2 are missing
+        let bytes = std::fs::read("test.db").unwrap();
+        let map = || {
+            let mut mmap = memmap2::MmapMut::map_anon(2 * 4096 - 6).unwrap();
+            mmap.copy_from_slice(&bytes[..2 * 4096 - 6]);
+            mmap.make_read_only().unwrap()
+        };
+        let mut pager = MmapPager::new(map(), 4096);
+        let err = pager.load_page(2).unwrap_err();
+        assert_eq!(
+            Some(&SqlrError::ShortPage {
+                page_num: 2,
+                expected: 4096,
+                actual: 4090
+            }),
+            err.downcast_ref::<SqlrError>()
+        );
+
+        let mut pager = MmapPager::new(map(), 4096).with_short_page_padding(true);
+        let raw = pager.load_raw_page(2).unwrap();
+        assert_eq!(4096, raw.len());
+        assert_eq!(&[0; 6], &raw[4090..]);
+        assert_eq!(2, pager.read_page(2).unwrap().cells.len());
+        // there is nothing to pad past the end of the mapping
+        assert!(matches!(
+            pager.read_page(3).unwrap_err().downcast_ref::<SqlrError>(),
+            Some(SqlrError::ShortPage { actual: 0, .. })
+        ));
+    }
+}
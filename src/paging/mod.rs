@@ -1,6 +1,15 @@
 pub mod cell;
+pub mod lazy_page;
+pub mod lru;
+#[cfg(feature = "mmap")]
+pub mod mmap_pager;
 pub mod page;
 pub mod page_header;
+pub mod page_stats;
 pub mod page_type;
+#[cfg(test)]
+pub mod page_writer;
 pub mod pager;
+pub mod pager_stats;
 pub mod positioned_page;
+pub mod wal;
@@ -1,66 +1,287 @@
-use crate::utils;
+use std::cmp::Ordering;
 
-use super::{
-    cell::{Cell, TableInteriorCell, TableLeafCell},
-    page_header::PageHeader,
-};
+use crate::{error::SqlrError, record::index_record::IndexRecord, utils, value::Value};
+
+use super::{cell::Cell, page_header::PageHeader};
 
 pub const HEADER_SIZE: usize = 100;
 
 // https://www.sqlite.org/fileformat.html
 
+// the position in the file of a byte of the given page, to look it up with a hex editor
+pub fn file_offset(page_num: usize, page_size: usize, offset_in_page: usize) -> u64 {
+    (page_num.saturating_sub(1) * page_size + offset_in_page) as u64
+}
+
+// points a page parsing error at the faulty cell or freeblock, or at the page header when the
+// error doesn't say where in the page it lies, the underlying error can still be downcast
+pub fn locate_error(
+    error: impl Into<anyhow::Error>,
+    page_num: usize,
+    page_size: usize,
+) -> anyhow::Error {
+    let error = error.into();
+    let header_offset = if page_num == 1 { HEADER_SIZE } else { 0 };
+    let offset_in_page = error
+        .downcast_ref::<SqlrError>()
+        .and_then(SqlrError::offset_in_page)
+        .unwrap_or(0);
+    let offset = file_offset(page_num, page_size, header_offset + offset_in_page);
+    error.context(format!("page {} at file offset {}", page_num, offset))
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Page {
     pub header: PageHeader,
     pub cell_pointers: Vec<u16>,
     pub cells: Vec<Cell>,
+    // (offset, size) of the unused blocks of the cell content area, in chain order
+    pub free_blocks: Vec<(u16, u16)>,
+    // where the page header starts, after the db header on the first page
+    pub header_offset: u16,
 }
 
 impl Page {
-    pub fn parse(buffer: &[u8], page_num: usize) -> anyhow::Result<Page> {
+    // only the first usable_size bytes of the page can hold b-tree content, the rest is reserved
+    pub fn parse(buffer: &[u8], page_num: usize, usable_size: usize) -> crate::error::Result<Page> {
         let ptr_offset = if page_num == 1 { HEADER_SIZE as u16 } else { 0 };
 
-        let content_buffer = &buffer[ptr_offset as usize..];
+        let usable_buffer = buffer.get(..usable_size).ok_or(SqlrError::TruncatedPage {
+            expected: usable_size,
+            actual: buffer.len(),
+        })?;
+        let content_buffer =
+            usable_buffer
+                .get(ptr_offset as usize..)
+                .ok_or(SqlrError::TruncatedPage {
+                    expected: ptr_offset as usize,
+                    actual: buffer.len(),
+                })?;
         let header = PageHeader::parse(content_buffer)?;
 
         let cell_pointers = Self::parse_cell_pointers(
-            &content_buffer[header.byte_size()..],
+            content_buffer.get(header.byte_size()..).unwrap_or_default(),
             header.cell_count() as usize,
             ptr_offset,
         );
 
-        let cell_parsing_fn = match header {
-            PageHeader::TableInteriorPageHeader { .. } => TableInteriorCell::parse,
-            PageHeader::TableLeafPageHeader { .. } => TableLeafCell::parse,
-        };
-
-        let cells = Self::parse_cells(content_buffer, &cell_pointers, cell_parsing_fn)?;
+        Self::check_cell_pointers(&header, &cell_pointers, content_buffer.len())?;
+        let cells = Self::parse_cells(content_buffer, &cell_pointers, Cell::parser(&header))?;
+        let free_blocks =
+            Self::parse_free_blocks(content_buffer, &header, cell_pointers.len(), ptr_offset)?;
 
         Ok(Self {
             header,
             cell_pointers,
             cells,
+            free_blocks,
+            header_offset: ptr_offset,
         })
     }
 
+    // for bytes which can't be trusted, e.g. when fuzzing: on top of what parse checks, the cell
+    // pointers must fit in the page and so must every cell, overflow pointer included, so that
+    // nothing is read past the end of the page or cut short
+    pub fn parse_checked(
+        buffer: &[u8],
+        page_num: usize,
+        usable_size: usize,
+    ) -> crate::error::Result<Page> {
+        let ptr_offset = if page_num == 1 { HEADER_SIZE } else { 0 };
+        let content_buffer = buffer
+            .get(..usable_size)
+            .and_then(|b| b.get(ptr_offset..))
+            .ok_or(SqlrError::TruncatedPage {
+                expected: usable_size.max(ptr_offset),
+                actual: buffer.len(),
+            })?;
+        let header = PageHeader::parse(content_buffer)?;
+        // interior page headers are longer than what PageHeader::parse requires
+        let pointers_buffer =
+            content_buffer
+                .get(header.byte_size()..)
+                .ok_or(SqlrError::TruncatedPage {
+                    expected: ptr_offset + header.byte_size(),
+                    actual: buffer.len(),
+                })?;
+
+        let cell_count = header.cell_count() as usize;
+        let capacity = pointers_buffer.len() / 2;
+        if cell_count > capacity {
+            return Err(SqlrError::TooManyCells {
+                cell_count,
+                capacity,
+            });
+        }
+        let cell_pointers =
+            Self::parse_cell_pointers(pointers_buffer, cell_count, ptr_offset as u16);
+        Self::check_cell_pointers(&header, &cell_pointers, content_buffer.len())?;
+        for &pointer in &cell_pointers {
+            let cell_buffer = &content_buffer[pointer as usize..];
+            match Cell::size_in_page(cell_buffer, &header, usable_size) {
+                Some(size) if size <= cell_buffer.len() => (),
+                _ => {
+                    return Err(SqlrError::CellOutOfBounds {
+                        pointer: pointer as usize + ptr_offset,
+                        page_size: usable_size,
+                    })
+                }
+            }
+        }
+
+        Self::parse(buffer, page_num, usable_size)
+    }
+
+    pub fn check_payload_sizes(&self, max_payload_size: usize) -> crate::error::Result<()> {
+        let too_large = |size: i64| usize::try_from(size).map_or(true, |s| s > max_payload_size);
+        match self
+            .cells
+            .iter()
+            .filter_map(Cell::payload_size)
+            .find(|&size| too_large(size))
+        {
+            Some(size) => Err(SqlrError::PayloadTooLarge {
+                size,
+                max: max_payload_size,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // the gap between the end of the cell pointer array and the start of the cell content area
+    // position of the first cell of an index page sorting at or after the key, which can be a
+    // prefix of the index key, or the cell count when there is none, in an interior page it's
+    // the cell whose left child is the one to descend into
+    pub fn index_lower_bound(
+        &self,
+        key: &[Value],
+        key_len: usize,
+        descending: &[bool],
+    ) -> anyhow::Result<usize> {
+        if !self.header.is_index() {
+            anyhow::bail!("not an index page");
+        }
+        let (mut low, mut high) = (0, self.cells.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let payload = self.cells[mid].payload().unwrap_or_default();
+            let record = IndexRecord::parse(payload, key_len)?;
+            if record.cmp_key(key, descending)? == Ordering::Less {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+
+    pub fn unallocated_bytes(&self) -> usize {
+        let pointers_end = self.header_offset as usize
+            + self.header.byte_size()
+            + 2 * self.header.cell_count() as usize;
+        (self.header.cell_content_offset() as usize).saturating_sub(pointers_end)
+    }
+
+    pub fn free_block_bytes(&self) -> usize {
+        self.free_blocks
+            .iter()
+            .map(|&(_, size)| size as usize)
+            .sum()
+    }
+
+    // every byte of the page a cell could be stored in, what vacuuming the page would reclaim
+    pub fn free_bytes(&self) -> usize {
+        self.unallocated_bytes()
+            + self.free_block_bytes()
+            + self.header.fragmented_bytes_count() as usize
+    }
+
+    // each freeblock starts with the offset of the next one, 0 for the last, and its own size,
+    // the chain is sorted by offset so a block can only start after the end of the previous one,
+    // which also rules out loops
+    pub fn parse_free_blocks(
+        buffer: &[u8],
+        header: &PageHeader,
+        cell_count: usize,
+        ptr_offset: u16,
+    ) -> crate::error::Result<Vec<(u16, u16)>> {
+        let mut range = header.byte_size() + 2 * cell_count..buffer.len();
+        let mut free_blocks = Vec::new();
+        let mut next = header.first_freeblock();
+        while next != 0 {
+            let offset = next.saturating_sub(ptr_offset) as usize;
+            let invalid = |range: &std::ops::Range<usize>| SqlrError::InvalidFreeblock {
+                offset,
+                range: range.clone(),
+            };
+            // the 4 bytes of the block header must fit before its size can be read
+            if offset < range.start || offset + 4 > range.end {
+                return Err(invalid(&range));
+            }
+            let following = utils::read_be_word_at(buffer, offset).1;
+            let size = utils::read_be_word_at(buffer, offset + 2).1;
+            if size < 4 || offset + size as usize > range.end {
+                return Err(invalid(&range));
+            }
+            free_blocks.push((offset as u16, size));
+            range.start = offset + size as usize;
+            next = following;
+        }
+        Ok(free_blocks)
+    }
+
+    // pointers come straight from disk, cells can only live after the pointer array and must
+    // start before the end of the page
+    pub fn check_cell_pointers(
+        header: &PageHeader,
+        cell_pointers: &[u16],
+        page_size: usize,
+    ) -> crate::error::Result<()> {
+        let range = header.byte_size() + 2 * cell_pointers.len()..page_size;
+        match cell_pointers
+            .iter()
+            .find(|&&ptr| !range.contains(&(ptr as usize)))
+        {
+            Some(&ptr) => Err(SqlrError::InvalidCellPointer {
+                pointer: ptr as usize,
+                range,
+            }),
+            None => Ok(()),
+        }
+    }
+
     fn parse_cells(
         buffer: &[u8],
         cell_pointers: &[u16],
-        parse_fn: impl Fn(&[u8]) -> anyhow::Result<Cell>,
-    ) -> anyhow::Result<Vec<Cell>> {
+        parse_fn: impl Fn(&[u8]) -> crate::error::Result<Cell>,
+    ) -> crate::error::Result<Vec<Cell>> {
         cell_pointers
             .iter()
-            .map(|&ptr| parse_fn(&buffer[ptr as usize..]))
+            .map(|&ptr| {
+                let cell_buffer =
+                    buffer
+                        .get(ptr as usize..)
+                        .ok_or(SqlrError::InvalidCellPointer {
+                            pointer: ptr as usize,
+                            range: 0..buffer.len(),
+                        })?;
+                parse_fn(cell_buffer)
+            })
             .collect()
     }
 
     // turns [u8] into [u16]
-    fn parse_cell_pointers(buffer: &[u8], n: usize, ptr_offset: u16) -> Vec<u16> {
+    pub fn parse_cell_pointers(buffer: &[u8], n: usize, ptr_offset: u16) -> Vec<u16> {
         let mut pointers = Vec::with_capacity(n);
         for i in 0..n {
             let offset = 2 * i;
             if offset + 2 <= buffer.len() {
-                pointers.push(utils::read_be_word_at(buffer, offset).1 - ptr_offset);
+                // a pointer into the db header is invalid, saturating keeps it out of bounds
+                pointers.push(
+                    utils::read_be_word_at(buffer, offset)
+                        .1
+                        .saturating_sub(ptr_offset),
+                );
             } else {
                 break;
             }
@@ -71,10 +292,17 @@ impl Page {
 
 #[cfg(test)]
 mod test {
+    use std::borrow::Cow;
+
+    use crate::{
+        paging::cell::{TableInteriorCell, TableLeafCell},
+        record::serialize,
+    };
+
     use super::*;
 
     #[test]
-    fn parse_interior_cells_tests() -> () {
+    fn parse_interior_cells_tests() {
         let buffer = [
             1, 0, 0, 0, 127, // interior cell 1
             0, 0, 0, 1, 12, // interior cell 2
@@ -99,7 +327,7 @@ mod test {
     }
 
     #[test]
-    fn parse_leaf_cells_tests() -> () {
+    fn parse_leaf_cells_tests() {
         let buffer = [
             2, 1, 127, 128, // leaf cell 1
             1, 2, 127, // leaf cell 2
@@ -126,19 +354,19 @@ mod test {
     }
 
     #[test]
-    fn parse_table_interior_page_tests() -> () {
-        assert!(Page::parse(&[12], 0).is_err());
+    fn parse_table_interior_page_tests() {
+        assert!(Page::parse(&[12], 0, 1).is_err());
         let buffer = [
             // page header w/ 2 as cell count
-            5, 0, 12, 0, 2, 0, 0, 0, 0, 0, 0, 21, // cell pointer
+            5, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 21, // cell pointer
             0, 16, 0, 21, // interior cell (left_child_page, key)
             0, 0, 0, 1, 10, 1, 0, 0, 0, 129, 0,
         ];
-        let res = Page::parse(&buffer, 0);
+        let res = Page::parse(&buffer, 0, buffer.len());
         assert!(res.is_ok());
         let expected = Page {
             header: PageHeader::TableInteriorPageHeader {
-                first_freeblock: 12,
+                first_freeblock: 0,
                 cell_count: 2,
                 cell_content_offset: 65536,
                 fragmented_bytes_count: 0,
@@ -157,24 +385,26 @@ mod test {
                 }
                 .into(),
             ],
+            free_blocks: vec![],
+            header_offset: 0,
         };
         assert_eq!(expected, res.unwrap());
     }
 
     #[test]
-    fn parse_table_leaf_page_tests() -> () {
-        assert!(Page::parse(&[12], 0).is_err());
+    fn parse_table_leaf_page_tests() {
+        assert!(Page::parse(&[12], 0, 1).is_err());
         let buffer = [
             // page header w/ 1 as cell count
-            13, 0, 12, 0, 1, 0, 0, 0, // cell pointer
+            13, 0, 0, 0, 1, 0, 0, 0, // cell pointer
             0, 10, // leaf cell (size, row id, payload)
             10, 2, 127,
         ];
-        let res = Page::parse(&buffer, 0);
+        let res = Page::parse(&buffer, 0, buffer.len());
         assert!(res.is_ok());
         let expected = Page {
             header: PageHeader::TableLeafPageHeader {
-                first_freeblock: 12,
+                first_freeblock: 0,
                 cell_count: 1,
                 cell_content_offset: 65536,
                 fragmented_bytes_count: 0,
@@ -186,12 +416,88 @@ mod test {
                 payload: vec![127],
             }
             .into()],
+            free_blocks: vec![],
+            header_offset: 0,
         };
         assert_eq!(expected, res.unwrap());
     }
 
     #[test]
-    fn parse_cell_pointers_test() -> () {
+    fn check_payload_sizes_tests() {
+        let buffer = [
+            13, 0, 0, 0, 1, 0, 0, 0, // cell pointer
+            0, 10, // leaf cell claiming 10 bytes
+            10, 2, 127,
+        ];
+        let page = Page::parse(&buffer, 0, buffer.len()).unwrap();
+        assert!(page.check_payload_sizes(10).is_ok());
+        assert!(matches!(
+            page.check_payload_sizes(9),
+            Err(SqlrError::PayloadTooLarge { size: 10, max: 9 })
+        ));
+
+        // a 9 byte varint with the high bit set is a negative size
+        let mut buffer = vec![13, 0, 0, 0, 1, 0, 0, 0, 0, 10];
+        buffer.extend([0xff; 9]);
+        buffer.extend([2, 127]);
+        let page = Page::parse(&buffer, 0, buffer.len()).unwrap();
+        assert!(matches!(
+            page.check_payload_sizes(usize::MAX),
+            Err(SqlrError::PayloadTooLarge { size: -1, .. })
+        ));
+
+        // interior table cells have no payload
+        let buffer = [5, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 9, 0, 14, 0, 0, 0, 3, 2];
+        let page = Page::parse(&buffer, 0, buffer.len()).unwrap();
+        assert!(page.check_payload_sizes(0).is_ok());
+    }
+
+    #[test]
+    fn index_lower_bound_tests() {
+        // an index leaf on (int, text) with entries (1, a), (2, b), (2, c) and (5, a)
+        let entries = [(1, "a"), (2, "b"), (2, "c"), (5, "a")];
+        let cells = entries
+            .iter()
+            .enumerate()
+            .map(|(row_id, &(n, s))| {
+                let payload = serialize(&[
+                    Value::Int(n),
+                    Value::String(Cow::from(s)),
+                    Value::Int(row_id as i64),
+                ]);
+                [vec![payload.len() as u8], payload].concat()
+            })
+            .collect::<Vec<_>>();
+        let mut buffer = vec![10, 0, 0, 0, entries.len() as u8, 0, 0, 0];
+        let mut offset = buffer.len() + 2 * cells.len();
+        for cell in &cells {
+            buffer.extend((offset as u16).to_be_bytes());
+            offset += cell.len();
+        }
+        buffer.extend(cells.concat());
+        let page = Page::parse(&buffer, 0, buffer.len()).unwrap();
+
+        let bound = |key: &[Value], descending: &[bool]| {
+            page.index_lower_bound(key, 2, descending).unwrap()
+        };
+        let text = |s: &'static str| Value::String(Cow::from(s));
+        assert_eq!(0, bound(&[], &[]));
+        assert_eq!(0, bound(&[Value::Int(0)], &[]));
+        assert_eq!(1, bound(&[Value::Int(2)], &[]));
+        assert_eq!(2, bound(&[Value::Int(2), text("c")], &[]));
+        assert_eq!(3, bound(&[Value::Int(2), text("d")], &[]));
+        assert_eq!(3, bound(&[Value::Int(3)], &[]));
+        assert_eq!(4, bound(&[Value::Int(9)], &[]));
+        // every entry sorts before the key once the column is DESC
+        assert_eq!(4, bound(&[Value::Int(0)], &[true]));
+
+        let table_page = [13, 0, 0, 0, 0, 0, 0, 0];
+        let table_page = Page::parse(&table_page, 0, table_page.len()).unwrap();
+        assert!(table_page.index_lower_bound(&[], 1, &[]).is_err());
+    }
+
+    #[test]
+    fn parse_cell_pointers_test() {
         assert_eq!(vec![65535], Page::parse_cell_pointers(&[255, 255], 1, 0));
         assert_eq!(vec![65535], Page::parse_cell_pointers(&[255, 255], 2, 0));
         assert_eq!(
@@ -199,4 +505,243 @@ mod test {
             Page::parse_cell_pointers(&[255, 255], 1, HEADER_SIZE as u16)
         );
     }
+
+    #[test]
+    fn parse_invalid_cell_pointer_tests() {
+        // leaf page w/ 1 cell whose pointer is past the end of the page
+        let buffer = [13, 0, 0, 0, 1, 0, 0, 0, 0, 13, 1, 1, 1];
+        assert_eq!(
+            Err(SqlrError::InvalidCellPointer {
+                pointer: 13,
+                range: 10..13
+            }),
+            Page::parse(&buffer, 0, buffer.len())
+        );
+        // pointing into the pointer array
+        let buffer = [13, 0, 0, 0, 1, 0, 0, 0, 0, 8, 1, 1, 1];
+        assert!(matches!(
+            Page::parse(&buffer, 0, buffer.len()),
+            Err(SqlrError::InvalidCellPointer { pointer: 8, .. })
+        ));
+        // pointing into the db header of the first page
+        let mut buffer = vec![0; HEADER_SIZE];
+        buffer.extend_from_slice(&[13, 0, 0, 0, 1, 0, 0, 0, 0, 50, 1, 1, 1]);
+        assert!(matches!(
+            Page::parse(&buffer, 1, buffer.len()),
+            Err(SqlrError::InvalidCellPointer { pointer: 0, .. })
+        ));
+        // the last byte of the page is a valid start
+        let buffer = [13, 0, 0, 0, 1, 0, 0, 0, 0, 10, 0];
+        assert!(Page::parse(&buffer, 0, buffer.len()).is_ok());
+    }
+
+    #[test]
+    fn parse_reserved_space_tests() {
+        // leaf page w/ 1 cell in the last 3 bytes of the page
+        let buffer = [13, 0, 0, 0, 1, 0, 0, 0, 0, 10, 1, 7, 42];
+        assert!(Page::parse(&buffer, 0, buffer.len()).is_ok());
+        // which are reserved
+        assert_eq!(
+            Err(SqlrError::InvalidCellPointer {
+                pointer: 10,
+                range: 10..10
+            }),
+            Page::parse(&buffer, 0, 10)
+        );
+        // a payload running into the reserved bytes is cut at the usable size
+        let page = Page::parse(&buffer, 0, 12).unwrap();
+        assert_eq!(
+            vec![Cell::from(TableLeafCell {
+                size: 1,
+                row_id: 7,
+                payload: vec![],
+            })],
+            page.cells
+        );
+        assert!(Page::parse(&buffer, 0, 14).is_err());
+    }
+
+    #[test]
+    fn parse_64k_page_tests() {
+        // leaf page w/ 1 cell in the last 3 bytes of a 64 KiB page
+        let mut buffer = vec![0; 65536];
+        buffer[..10].copy_from_slice(&[13, 0, 0, 0, 1, 255, 253, 0, 255, 253]);
+        buffer[65533..].copy_from_slice(&[1, 7, 42]);
+        let page = Page::parse(&buffer, 2, buffer.len()).unwrap();
+        assert_eq!(vec![65533], page.cell_pointers);
+        assert_eq!(
+            vec![Cell::from(TableLeafCell {
+                size: 1,
+                row_id: 7,
+                payload: vec![42],
+            })],
+            page.cells
+        );
+
+        // on the first page, pointers are still relative to the start of the page
+        buffer[..10].fill(0);
+        buffer[HEADER_SIZE..HEADER_SIZE + 10]
+            .copy_from_slice(&[13, 0, 0, 0, 1, 255, 253, 0, 255, 253]);
+        let page = Page::parse(&buffer, 1, buffer.len()).unwrap();
+        assert_eq!(vec![65433], page.cell_pointers);
+        assert_eq!(1, page.cells.len());
+    }
+
+    #[test]
+    fn parse_free_blocks_tests() {
+        // leaf page w/ 1 cell at 21 and freeblocks at 10 and 15
+        let mut buffer = vec![13, 0, 10, 0, 1, 0, 0, 0, 0, 21];
+        buffer.extend_from_slice(&[0, 15, 0, 5, 0, 0, 0, 0, 6, 0, 0, 1, 7, 42]);
+        let page = Page::parse(&buffer, 0, buffer.len()).unwrap();
+        assert_eq!(vec![(10, 5), (15, 6)], page.free_blocks);
+        assert_eq!(1, page.cells.len());
+
+        let invalid = |buffer: &[u8]| match Page::parse(buffer, 0, buffer.len()) {
+            Err(SqlrError::InvalidFreeblock { offset, .. }) => Some(offset),
+            _ => None,
+        };
+        // looping back to the first block
+        let mut looping = buffer.clone();
+        looping[16] = 10;
+        assert_eq!(Some(10), invalid(&looping));
+        // overlapping the cell pointers
+        let mut overlapping = buffer.clone();
+        overlapping[2] = 8;
+        assert_eq!(Some(8), invalid(&overlapping));
+        // running past the end of the page
+        let mut past_end = buffer.clone();
+        past_end[18] = 12;
+        assert_eq!(Some(15), invalid(&past_end));
+        // too small to hold its own header
+        let mut small = buffer.clone();
+        small[13] = 3;
+        assert_eq!(Some(10), invalid(&small));
+
+        // rows 2 and 4 of 5 were deleted
+        let bytes = std::fs::read("test_freeblock.db").unwrap();
+        let page = Page::parse(&bytes[4096..8192], 2, 4096).unwrap();
+        assert_eq!(vec![(4065, 8), (4082, 7)], page.free_blocks);
+        assert_eq!(3, page.cells.len());
+    }
+
+    #[test]
+    fn free_bytes_tests() {
+        // the same as the unused bytes reported by sqlite3's dbstat
+        let bytes = std::fs::read("test_freeblock.db").unwrap();
+        let page = Page::parse(&bytes[..4096], 1, 4096).unwrap();
+        assert_eq!(3948, page.unallocated_bytes());
+        assert_eq!(0, page.free_block_bytes());
+        assert_eq!(3948, page.free_bytes());
+        let page = Page::parse(&bytes[4096..8192], 2, 4096).unwrap();
+        assert_eq!(4043, page.unallocated_bytes());
+        assert_eq!(15, page.free_block_bytes());
+        assert_eq!(4058, page.free_bytes());
+
+        // fragments count towards the free bytes, an empty 64 KiB page is all unallocated
+        let mut buffer = vec![0; 65536];
+        buffer[..8].copy_from_slice(&[13, 0, 0, 0, 0, 0, 0, 2]);
+        let page = Page::parse(&buffer, 2, buffer.len()).unwrap();
+        assert_eq!(65528, page.unallocated_bytes());
+        assert_eq!(65530, page.free_bytes());
+    }
+
+    #[test]
+    fn check_cell_pointers_tests() {
+        let header = PageHeader::TableInteriorPageHeader {
+            first_freeblock: 0,
+            cell_count: 2,
+            cell_content_offset: 0,
+            fragmented_bytes_count: 0,
+            rightmost_pointer: 0,
+        };
+        assert!(Page::check_cell_pointers(&header, &[16, 4095], 4096).is_ok());
+        assert!(Page::check_cell_pointers(&header, &[15, 20], 4096).is_err());
+        assert!(Page::check_cell_pointers(&header, &[16, 4096], 4096).is_err());
+        assert!(Page::check_cell_pointers(&header, &[], 0).is_ok());
+    }
+
+    #[test]
+    fn parse_checked_tests() {
+        // every b-tree page of a file, overflowing cells included, parses the same either way
+        for file in ["test_overflow.db", "test_index.db", "test_64k.db"] {
+            let bytes = std::fs::read(file).unwrap();
+            let page_size = match u16::from_be_bytes([bytes[16], bytes[17]]) {
+                1 => 65536,
+                size => size as usize,
+            };
+            for (i, buffer) in bytes.chunks(page_size).enumerate() {
+                if let Ok(page) = Page::parse(buffer, i + 1, page_size) {
+                    assert_eq!(page, Page::parse_checked(buffer, i + 1, page_size).unwrap());
+                }
+            }
+        }
+
+        let mut buffer = vec![0; 512];
+        buffer[..8].copy_from_slice(&[13, 0, 0, 0, 1, 1, 0, 0]);
+        buffer[8..10].copy_from_slice(&500u16.to_be_bytes());
+        // a 4 byte record at 500
+        buffer[500..506].copy_from_slice(&[4, 1, 2, 0, 1, 7]);
+        assert!(Page::parse_checked(&buffer, 2, 512).is_ok());
+        // the record claims 20 bytes but the page ends after 12
+        buffer[500] = 20;
+        assert_eq!(
+            Err(SqlrError::CellOutOfBounds {
+                pointer: 500,
+                page_size: 512
+            }),
+            Page::parse_checked(&buffer, 2, 512)
+        );
+        // the row id varint never ends
+        buffer[500..512].fill(0xff);
+        buffer[500] = 4;
+        assert!(Page::parse_checked(&buffer, 2, 512).is_err());
+        // 300 cell pointers don't fit after the header
+        buffer[3..5].copy_from_slice(&300u16.to_be_bytes());
+        assert_eq!(
+            Err(SqlrError::TooManyCells {
+                cell_count: 300,
+                capacity: 252
+            }),
+            Page::parse_checked(&buffer, 2, 512)
+        );
+        // an interior page header cut short
+        assert!(Page::parse_checked(&[5, 0, 0, 0, 0, 0, 0, 0, 0, 0], 2, 10).is_err());
+        assert!(Page::parse_checked(&buffer, 1, 50).is_err());
+        assert!(Page::parse_checked(&buffer, 2, 1024).is_err());
+    }
+
+    #[test]
+    fn parse_checked_garbage_tests() {
+        // a small xorshift generator keeps the garbage the same from one run to the next
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let bytes = std::fs::read("test_overflow.db").unwrap();
+        for _ in 0..2000 {
+            let page_num = 1 + (next() % 4) as usize;
+            let mut buffer = bytes[(page_num - 1) * 4096..page_num * 4096].to_vec();
+            for _ in 0..1 + next() % 8 {
+                let offset = (next() % 4096) as usize;
+                // mostly the header and cell pointers, where the structure is described
+                let offset = if next() % 2 == 0 {
+                    offset % 128
+                } else {
+                    offset
+                };
+                buffer[offset] = next() as u8;
+            }
+            // errors are fine, panics aren't
+            let _ = Page::parse_checked(&buffer, page_num, 4096);
+        }
+        for _ in 0..2000 {
+            let mut buffer = (0..512).map(|_| next() as u8).collect::<Vec<_>>();
+            buffer[0] = [2, 5, 10, 13][(next() % 4) as usize];
+            buffer[3..5].copy_from_slice(&((next() % 64) as u16).to_be_bytes());
+            let _ = Page::parse_checked(&buffer, 2, 512);
+        }
+    }
 }
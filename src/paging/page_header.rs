@@ -1,4 +1,4 @@
-use crate::utils;
+use crate::{error::SqlrError, utils};
 
 use super::page_type::PageType;
 
@@ -20,6 +20,19 @@ pub enum PageHeader {
         // points to the root of the subtree that contains keys > any keys in the page's cells
         rightmost_pointer: u32,
     },
+    IndexLeafPageHeader {
+        first_freeblock: u16,
+        cell_count: u16,
+        cell_content_offset: u32,
+        fragmented_bytes_count: u8,
+    },
+    IndexInteriorPageHeader {
+        first_freeblock: u16,
+        cell_count: u16,
+        cell_content_offset: u32,
+        fragmented_bytes_count: u8,
+        rightmost_pointer: u32,
+    },
 }
 
 impl PageHeader {
@@ -38,9 +51,12 @@ impl PageHeader {
     // word at 5 byte offset => offset of the first cell
     // 7th byte => number of fragmented free bytes in the page (probably used for vaccuuming?)
     // 2 words at 8 byte offset => right most pointer in interior b-tree pages
-    pub fn parse(buffer: &[u8]) -> anyhow::Result<PageHeader> {
-        if buffer.len() < 7 {
-            return Err(anyhow::anyhow!("page header must be at least 7-byte long"));
+    pub fn parse(buffer: &[u8]) -> crate::error::Result<PageHeader> {
+        if buffer.len() < Self::PAGE_HEADER_SIZE_LEAF {
+            return Err(SqlrError::TruncatedPage {
+                expected: Self::PAGE_HEADER_SIZE_LEAF,
+                actual: buffer.len(),
+            });
         }
 
         let page_type = PageType::parse(buffer)?;
@@ -54,34 +70,108 @@ impl PageHeader {
             };
         let fragmented_bytes_count = buffer[Self::PAGE_FRAGMENTED_BYTES_COUNT_OFFSET];
 
-        let header = if page_type == PageType::TableInterior {
-            PageHeader::TableInteriorPageHeader {
+        let rightmost_pointer =
+            || utils::read_be_double_word_at(buffer, Self::PAGE_RIGHTMOST_POINTER_OFFSET).1;
+        let header = match page_type {
+            PageType::TableInterior => PageHeader::TableInteriorPageHeader {
                 first_freeblock,
                 cell_count,
                 cell_content_offset,
                 fragmented_bytes_count,
-                rightmost_pointer: utils::read_be_double_word_at(
-                    buffer,
-                    Self::PAGE_RIGHTMOST_POINTER_OFFSET,
-                )
-                .1,
-            }
-        } else {
-            PageHeader::TableLeafPageHeader {
+                rightmost_pointer: rightmost_pointer(),
+            },
+            PageType::TableLeaf => PageHeader::TableLeafPageHeader {
                 first_freeblock,
                 cell_count,
                 cell_content_offset,
                 fragmented_bytes_count,
-            }
+            },
+            PageType::IndexInterior => PageHeader::IndexInteriorPageHeader {
+                first_freeblock,
+                cell_count,
+                cell_content_offset,
+                fragmented_bytes_count,
+                rightmost_pointer: rightmost_pointer(),
+            },
+            PageType::IndexLeaf => PageHeader::IndexLeafPageHeader {
+                first_freeblock,
+                cell_count,
+                cell_content_offset,
+                fragmented_bytes_count,
+            },
         };
 
         Ok(header)
     }
 
+    // 0 when there are no freeblocks
+    pub fn first_freeblock(&self) -> u16 {
+        match *self {
+            PageHeader::TableInteriorPageHeader {
+                first_freeblock, ..
+            }
+            | PageHeader::TableLeafPageHeader {
+                first_freeblock, ..
+            }
+            | PageHeader::IndexInteriorPageHeader {
+                first_freeblock, ..
+            }
+            | PageHeader::IndexLeafPageHeader {
+                first_freeblock, ..
+            } => first_freeblock,
+        }
+    }
+
+    // 65536 when stored as 0
+    pub fn cell_content_offset(&self) -> u32 {
+        match *self {
+            PageHeader::TableInteriorPageHeader {
+                cell_content_offset,
+                ..
+            }
+            | PageHeader::TableLeafPageHeader {
+                cell_content_offset,
+                ..
+            }
+            | PageHeader::IndexInteriorPageHeader {
+                cell_content_offset,
+                ..
+            }
+            | PageHeader::IndexLeafPageHeader {
+                cell_content_offset,
+                ..
+            } => cell_content_offset,
+        }
+    }
+
+    // free fragments of 1 to 3 bytes in the cell content area, too small to be freeblocks
+    pub fn fragmented_bytes_count(&self) -> u8 {
+        match *self {
+            PageHeader::TableInteriorPageHeader {
+                fragmented_bytes_count,
+                ..
+            }
+            | PageHeader::TableLeafPageHeader {
+                fragmented_bytes_count,
+                ..
+            }
+            | PageHeader::IndexInteriorPageHeader {
+                fragmented_bytes_count,
+                ..
+            }
+            | PageHeader::IndexLeafPageHeader {
+                fragmented_bytes_count,
+                ..
+            } => fragmented_bytes_count,
+        }
+    }
+
     pub fn cell_count(&self) -> u16 {
         match *self {
             PageHeader::TableInteriorPageHeader { cell_count, .. }
-            | PageHeader::TableLeafPageHeader { cell_count, .. } => cell_count,
+            | PageHeader::TableLeafPageHeader { cell_count, .. }
+            | PageHeader::IndexInteriorPageHeader { cell_count, .. }
+            | PageHeader::IndexLeafPageHeader { cell_count, .. } => cell_count,
         }
     }
 
@@ -89,17 +179,28 @@ impl PageHeader {
         match *self {
             PageHeader::TableInteriorPageHeader {
                 rightmost_pointer, ..
+            }
+            | PageHeader::IndexInteriorPageHeader {
+                rightmost_pointer, ..
             } => Some(rightmost_pointer),
-            PageHeader::TableLeafPageHeader { .. } => None,
+            PageHeader::TableLeafPageHeader { .. } | PageHeader::IndexLeafPageHeader { .. } => None,
         }
     }
 
     pub fn byte_size(&self) -> usize {
-        match self {
-            PageHeader::TableInteriorPageHeader { .. } => Self::PAGE_HEADER_SIZE_INTERIOR,
-            PageHeader::TableLeafPageHeader { .. } => Self::PAGE_HEADER_SIZE_LEAF,
+        match self.rightmost_pointer() {
+            Some(_) => Self::PAGE_HEADER_SIZE_INTERIOR,
+            None => Self::PAGE_HEADER_SIZE_LEAF,
         }
     }
+
+    // index b-trees hold records keyed by their content, table b-trees are keyed by row id
+    pub fn is_index(&self) -> bool {
+        matches!(
+            self,
+            PageHeader::IndexInteriorPageHeader { .. } | PageHeader::IndexLeafPageHeader { .. }
+        )
+    }
 }
 
 #[cfg(test)]
@@ -107,7 +208,7 @@ mod test {
     use super::*;
 
     #[test]
-    fn page_header_accessor_tests() -> () {
+    fn page_header_accessor_tests() {
         let leaf = PageHeader::TableLeafPageHeader {
             first_freeblock: 12,
             cell_count: 1,
@@ -130,10 +231,20 @@ mod test {
     }
 
     #[test]
-    fn parse_page_header_tests() -> () {
+    fn parse_page_header_tests() {
         // first byte must be 13 for a table b-tree leaf
-        assert!(PageHeader::parse(&[12]).is_err());
-        assert!(PageHeader::parse(&[12, 0, 12, 0, 11, 0, 10, 0]).is_err());
+        assert_eq!(
+            Err(SqlrError::TruncatedPage {
+                expected: 8,
+                actual: 1
+            }),
+            PageHeader::parse(&[12])
+        );
+        assert!(PageHeader::parse(&[13, 0, 12, 0, 11, 0, 10]).is_err());
+        assert_eq!(
+            Err(SqlrError::UnknownPageType(12)),
+            PageHeader::parse(&[12, 0, 12, 0, 11, 0, 10, 0])
+        );
         assert_eq!(
             PageHeader::TableLeafPageHeader {
                 first_freeblock: 12,
@@ -164,5 +275,19 @@ mod test {
             },
             PageHeader::parse(&[5, 0, 12, 0, 11, 0, 0, 0]).unwrap(),
         );
+        // index
+        assert_eq!(
+            PageHeader::IndexLeafPageHeader {
+                first_freeblock: 0,
+                cell_count: 3,
+                cell_content_offset: 100,
+                fragmented_bytes_count: 1,
+            },
+            PageHeader::parse(&[10, 0, 0, 0, 3, 0, 100, 1]).unwrap()
+        );
+        let index_interior = PageHeader::parse(&[2, 0, 0, 0, 3, 0, 100, 1, 0, 0, 0, 7]).unwrap();
+        assert_eq!(Some(7), index_interior.rightmost_pointer());
+        assert_eq!(12, index_interior.byte_size());
+        assert!(index_interior.is_index());
     }
 }
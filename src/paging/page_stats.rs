@@ -0,0 +1,44 @@
+// how many pages of a database file are of each kind
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageStats {
+    pub table_leaf: usize,
+    pub table_interior: usize,
+    pub index_leaf: usize,
+    pub index_interior: usize,
+    // overflow chains aren't followed, every page which isn't anything else is counted here
+    pub overflow: usize,
+    // trunk and leaf pages alike
+    pub freelist: usize,
+    // only auto-vacuum databases have pointer map pages
+    pub ptrmap: usize,
+}
+
+impl PageStats {
+    pub fn total(&self) -> usize {
+        self.table_leaf
+            + self.table_interior
+            + self.index_leaf
+            + self.index_interior
+            + self.overflow
+            + self.freelist
+            + self.ptrmap
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn total_tests() {
+        assert_eq!(0, PageStats::default().total());
+        let stats = PageStats {
+            table_leaf: 3,
+            index_leaf: 2,
+            overflow: 1,
+            ptrmap: 1,
+            ..PageStats::default()
+        };
+        assert_eq!(7, stats.total());
+    }
+}
@@ -1,22 +1,34 @@
+use crate::error::SqlrError;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum PageType {
     TableLeaf,
     TableInterior,
+    IndexLeaf,
+    IndexInterior,
 }
 
 impl PageType {
     const PAGE_LEAF_TABLE_ID: u8 = 13;
     const PAGE_INTERIOR_TABLE_ID: u8 = 5;
+    const PAGE_LEAF_INDEX_ID: u8 = 10;
+    const PAGE_INTERIOR_INDEX_ID: u8 = 2;
 
     // 2: interior index b-tree page
     // 5: interior table b-tree page
     // 10: leaf index b-tree page
     // 13: leaf table b-tree page
-    pub fn parse(buffer: &[u8]) -> anyhow::Result<PageType> {
-        match buffer[0] {
-            Self::PAGE_LEAF_TABLE_ID => Ok(PageType::TableLeaf),
-            Self::PAGE_INTERIOR_TABLE_ID => Ok(PageType::TableInterior),
-            _ => Err(anyhow::anyhow!("unknown page type: {}", buffer[0])),
+    pub fn parse(buffer: &[u8]) -> crate::error::Result<PageType> {
+        match buffer.first() {
+            Some(&Self::PAGE_LEAF_TABLE_ID) => Ok(PageType::TableLeaf),
+            Some(&Self::PAGE_INTERIOR_TABLE_ID) => Ok(PageType::TableInterior),
+            Some(&Self::PAGE_LEAF_INDEX_ID) => Ok(PageType::IndexLeaf),
+            Some(&Self::PAGE_INTERIOR_INDEX_ID) => Ok(PageType::IndexInterior),
+            Some(&other) => Err(SqlrError::UnknownPageType(other)),
+            None => Err(SqlrError::TruncatedPage {
+                expected: 1,
+                actual: 0,
+            }),
         }
     }
 }
@@ -26,10 +38,17 @@ mod test {
     use super::*;
 
     #[test]
-    fn parse_page_type_tests() -> () {
-        assert!(PageType::parse(&[12]).is_err());
+    fn parse_page_type_tests() {
+        assert_eq!(Err(SqlrError::UnknownPageType(12)), PageType::parse(&[12]));
+        assert!(matches!(
+            PageType::parse(&[]),
+            Err(SqlrError::TruncatedPage { .. })
+        ));
         let res = PageType::parse(&[13]);
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), PageType::TableLeaf);
+        assert_eq!(PageType::TableInterior, PageType::parse(&[5]).unwrap());
+        assert_eq!(PageType::IndexLeaf, PageType::parse(&[10]).unwrap());
+        assert_eq!(PageType::IndexInterior, PageType::parse(&[2]).unwrap());
     }
 }
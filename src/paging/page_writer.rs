@@ -0,0 +1,168 @@
+use std::borrow::Cow;
+
+use crate::{record::serialize, utils, value::Value};
+
+use super::page::HEADER_SIZE;
+
+const TABLE_LEAF: u8 = 13;
+const TABLE_INTERIOR: u8 = 5;
+
+// writes a database holding a single table whose rows have sequential row ids from 1, without
+// going through sqlite3, small pages make for trees deep enough to exercise interior pages
+pub fn write_table_db(page_size: usize, create_sql: &str, rows: &[Vec<Value>]) -> Vec<u8> {
+    // payloads spilling onto overflow pages aren't written
+    let max_local = page_size - 35;
+    let cells = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let payload = serialize(row);
+            assert!(
+                payload.len() <= max_local,
+                "row {} doesn't fit in a page",
+                i
+            );
+            let row_id = i as i64 + 1;
+            let mut cell = utils::write_varint(payload.len() as i64);
+            utils::write_varint_into(&mut cell, row_id);
+            cell.extend(payload);
+            (cell, row_id)
+        })
+        .collect::<Vec<_>>();
+
+    // page 1 is left for the schema, every level is written after the one below it
+    let mut pages = vec![Vec::new()];
+    let mut level = Vec::new();
+    for chunk in fill(&cells, page_size, 8) {
+        let (cells, keys): (Vec<_>, Vec<_>) = chunk.iter().cloned().unzip();
+        pages.push(write_page(TABLE_LEAF, &cells, None, 0, page_size));
+        level.push((pages.len() as u32, keys.last().copied().unwrap_or(0)));
+    }
+    while level.len() > 1 {
+        // each cell points to a child along with its largest key, the last child is the
+        // rightmost pointer
+        let children = level
+            .iter()
+            .map(|&(page_num, key)| {
+                let mut cell = page_num.to_be_bytes().to_vec();
+                utils::write_varint_into(&mut cell, key);
+                (cell, (page_num, key))
+            })
+            .collect::<Vec<_>>();
+        let mut next_level = Vec::new();
+        for chunk in fill(&children, page_size, 12) {
+            let (cells, children): (Vec<_>, Vec<_>) = chunk.iter().cloned().unzip();
+            let &(rightmost, key) = children.last().unwrap();
+            let cells = &cells[..cells.len() - 1];
+            pages.push(write_page(
+                TABLE_INTERIOR,
+                cells,
+                Some(rightmost),
+                0,
+                page_size,
+            ));
+            next_level.push((pages.len() as u32, key));
+        }
+        level = next_level;
+    }
+    let root_page = level.first().map_or(0, |&(page_num, _)| page_num);
+
+    let schema_row = serialize(&[
+        Value::String(Cow::from("table")),
+        Value::String(Cow::from("t")),
+        Value::String(Cow::from("t")),
+        Value::Int(root_page as i64),
+        Value::String(Cow::from(create_sql)),
+    ]);
+    let mut schema_cell = utils::write_varint(schema_row.len() as i64);
+    utils::write_varint_into(&mut schema_cell, 1);
+    schema_cell.extend(schema_row);
+    let mut first_page = write_page(TABLE_LEAF, &[schema_cell], None, HEADER_SIZE, page_size);
+    first_page[..HEADER_SIZE].copy_from_slice(&write_header(page_size, pages.len()));
+    pages[0] = first_page;
+
+    pages.concat()
+}
+
+// splits the cells into runs which each fit in a page with the given header size, the last
+// child of an interior page is counted although it only takes the rightmost pointer
+fn fill<T>(cells: &[(Vec<u8>, T)], page_size: usize, header_size: usize) -> Vec<&[(Vec<u8>, T)]> {
+    let mut starts = vec![0];
+    let mut used = header_size;
+    for (i, (cell, _)) in cells.iter().enumerate() {
+        // 2 bytes for the cell pointer
+        if used + cell.len() + 2 > page_size && i > starts[starts.len() - 1] {
+            starts.push(i);
+            used = header_size;
+        }
+        used += cell.len() + 2;
+    }
+    // an interior page needs at least one cell besides its rightmost pointer, the page before
+    // only gets emptier by giving up its last child
+    let last = starts.len() - 1;
+    if last > 0 && starts[last] == cells.len() - 1 {
+        starts[last] -= 1;
+    }
+    // an empty table still has its root page
+    let ends = starts.iter().skip(1).copied().chain([cells.len()]);
+    starts
+        .iter()
+        .zip(ends)
+        .map(|(&start, end)| &cells[start..end])
+        .collect()
+}
+
+// cells are laid out from the end of the page, in reverse order, like sqlite does
+fn write_page(
+    page_type: u8,
+    cells: &[Vec<u8>],
+    rightmost_pointer: Option<u32>,
+    header_offset: usize,
+    page_size: usize,
+) -> Vec<u8> {
+    let mut page = vec![0; page_size];
+    let mut content_offset = page_size;
+    let mut pointers = Vec::new();
+    for cell in cells {
+        content_offset -= cell.len();
+        page[content_offset..content_offset + cell.len()].copy_from_slice(cell);
+        pointers.extend((content_offset as u16).to_be_bytes());
+    }
+
+    let mut header = vec![page_type, 0, 0];
+    header.extend((cells.len() as u16).to_be_bytes());
+    // 0 stands for 65536
+    header.extend((content_offset as u16).to_be_bytes());
+    header.push(0);
+    if let Some(pointer) = rightmost_pointer {
+        header.extend(pointer.to_be_bytes());
+    }
+    header.extend(pointers);
+    page[header_offset..header_offset + header.len()].copy_from_slice(&header);
+    page
+}
+
+// https://www.sqlite.org/fileformat.html#the_database_header
+fn write_header(page_size: usize, page_count: usize) -> [u8; HEADER_SIZE] {
+    let mut header = [0; HEADER_SIZE];
+    header[..16].copy_from_slice(b"SQLite format 3\0");
+    // 1 stands for 65536
+    let page_size = if page_size == 65536 {
+        1
+    } else {
+        page_size as u16
+    };
+    header[16..18].copy_from_slice(&page_size.to_be_bytes());
+    // legacy journal, no reserved space and the payload fractions sqlite requires
+    header[18..24].copy_from_slice(&[1, 1, 0, 64, 32, 32]);
+    // change counter, database size, schema cookie, schema format and text encoding
+    header[24..28].copy_from_slice(&1u32.to_be_bytes());
+    header[28..32].copy_from_slice(&(page_count as u32).to_be_bytes());
+    header[40..44].copy_from_slice(&1u32.to_be_bytes());
+    header[44..48].copy_from_slice(&4u32.to_be_bytes());
+    header[56..60].copy_from_slice(&1u32.to_be_bytes());
+    // version-valid-for matches the change counter so the database size is trusted
+    header[92..96].copy_from_slice(&1u32.to_be_bytes());
+    header[96..100].copy_from_slice(&3_045_000u32.to_be_bytes());
+    header
+}
@@ -1,44 +1,136 @@
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::HashMap,
     io::{Read, Seek},
+    rc::Rc,
 };
 
 use anyhow::Context;
 
-use crate::paging::page::Page;
+use crate::{
+    error::SqlrError,
+    paging::{
+        cell::DEFAULT_MAX_PAYLOAD_SIZE,
+        lazy_page::LazyPage,
+        lru::Lru,
+        page::{self, Page},
+        pager_stats::PagerStats,
+        wal::Wal,
+    },
+};
 
 pub trait Pager {
-    fn read_page(&mut self, page_num: usize) -> anyhow::Result<&Page>;
+    // cached pages are shared, handing one out only bumps its reference count
+    fn read_page(&mut self, page_num: usize) -> anyhow::Result<Rc<Page>>;
     fn load_page(&mut self, page_num: usize) -> anyhow::Result<Page>;
+    // the page's bytes, for pages which aren't b-tree pages such as freelist pages
+    fn load_raw_page(&mut self, page_num: usize) -> anyhow::Result<Vec<u8>>;
+    fn page_size(&self) -> usize;
+    // size in bytes of the underlying input
+    fn input_size(&mut self) -> anyhow::Result<u64>;
+    // drops every cached page, the next reads will hit the input again
+    fn clear_cache(&mut self);
+    // drops a single cached page if present
+    fn invalidate(&mut self, page_num: usize);
+
+    // pagers that don't cache have nothing to report
+    fn stats(&self) -> PagerStats {
+        PagerStats::default()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct FilePager<I: Read + Seek = std::fs::File> {
     input: I,
     pub page_size: usize,
-    pub pages: HashMap<usize, Page>,
+    // bytes at the end of every page which b-tree content can't use
+    reserved_space: usize,
+    // pages with a cell claiming a larger payload fail to load
+    max_payload_size: usize,
+    pub pages: HashMap<usize, Rc<Page>>,
+    // unbounded when None
+    max_pages: Option<usize>,
+    lru: Lru,
+    stats: PagerStats,
+    // pages following a missed one which are read along with it, 0 disables read-ahead
+    readahead: usize,
+    // committed pages found in the wal shadow the ones in the input
+    wal: Option<Wal<I>>,
+    // a last page cut short by the end of the input is padded with zeros instead of failing
+    pad_short_page: bool,
 }
 
-impl Pager for FilePager {
-    fn read_page(&mut self, page_num: usize) -> anyhow::Result<&Page> {
-        if let Entry::Vacant(_) = self.pages.entry(page_num) {
-            let page = self.load_page(page_num)?;
-            self.pages.insert(page_num, page);
+impl<I: Read + Seek> Pager for FilePager<I> {
+    fn read_page(&mut self, page_num: usize) -> anyhow::Result<Rc<Page>> {
+        if let Some(page) = self.pages.get(&page_num) {
+            self.stats.hits += 1;
+            self.lru.touch(page_num);
+            return Ok(Rc::clone(page));
+        }
+
+        self.stats.misses += 1;
+        // page 0 doesn't exist, it is read like page 1 and there is nothing after it to prefetch,
+        // reading ahead in the input would also skip over the pages found in the wal
+        if self.readahead > 0 && page_num > 0 && self.wal.is_none() {
+            return self.read_pages_ahead(page_num);
         }
-        Ok(self.pages.get(&page_num).unwrap())
+        let page = Rc::new(self.load_page(page_num)?);
+        self.cache_page(page_num, Rc::clone(&page));
+        Ok(page)
     }
 
     fn load_page(&mut self, page_num: usize) -> anyhow::Result<Page> {
+        let buffer = self.load_raw_page(page_num)?;
+        self.parse_page(&buffer, page_num)
+    }
+
+    fn load_raw_page(&mut self, page_num: usize) -> anyhow::Result<Vec<u8>> {
+        if let Some(wal) = &mut self.wal {
+            if let Some(buffer) = wal.read_page(page_num)? {
+                return Ok(buffer);
+            }
+        }
+
         let offset = page_num.saturating_sub(1) * self.page_size;
 
         self.input
             .seek(std::io::SeekFrom::Start(offset as u64))
             .context("seek to page start")?;
 
-        let mut buffer = vec![0; self.page_size];
-        self.input.read_exact(&mut buffer).context("read page")?;
+        let mut buffer = Vec::with_capacity(self.page_size);
+        (&mut self.input)
+            .take(self.page_size as u64)
+            .read_to_end(&mut buffer)
+            .context("read page")?;
+
+        self.complete_page(buffer, page_num)
+    }
+
+    fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    // with a wal, the size of the database as of its last commit
+    fn input_size(&mut self) -> anyhow::Result<u64> {
+        if let Some(database_size) = self.wal.as_ref().and_then(|w| w.database_size) {
+            return Ok(database_size as u64 * self.page_size as u64);
+        }
+        self.input
+            .seek(std::io::SeekFrom::End(0))
+            .context("seek to input end")
+    }
+
+    fn clear_cache(&mut self) {
+        self.pages.clear();
+        self.lru = Lru::default();
+    }
 
-        Page::parse(&buffer, page_num)
+    fn invalidate(&mut self, page_num: usize) {
+        self.pages.remove(&page_num);
+        self.lru.remove(page_num);
+    }
+
+    fn stats(&self) -> PagerStats {
+        self.stats
     }
 }
 
@@ -47,19 +139,152 @@ impl<I: Read + Seek> FilePager<I> {
         Self {
             input,
             page_size,
+            reserved_space: 0,
+            max_payload_size: DEFAULT_MAX_PAYLOAD_SIZE,
             pages: HashMap::new(),
+            max_pages: None,
+            lru: Lru::default(),
+            stats: PagerStats::default(),
+            readahead: 0,
+            wal: None,
+            pad_short_page: false,
+        }
+    }
+
+    pub fn with_reserved_space(mut self, reserved_space: usize) -> Self {
+        self.reserved_space = reserved_space;
+        self
+    }
+
+    pub fn usable_size(&self) -> usize {
+        self.page_size.saturating_sub(self.reserved_space)
+    }
+
+    pub fn with_max_payload_size(mut self, max_payload_size: usize) -> Self {
+        self.max_payload_size = max_payload_size;
+        self
+    }
+
+    fn parse_page(&self, buffer: &[u8], page_num: usize) -> anyhow::Result<Page> {
+        let locate = |e| page::locate_error(e, page_num, self.page_size);
+        let page = Page::parse(buffer, page_num, self.usable_size()).map_err(locate)?;
+        page.check_payload_sizes(self.max_payload_size)
+            .map_err(locate)?;
+        Ok(page)
+    }
+
+    // the cells of a truncated last page which made it into the file can then still be read,
+    // a page entirely past the end of the input is an error either way
+    pub fn with_short_page_padding(mut self, pad_short_page: bool) -> Self {
+        self.pad_short_page = pad_short_page;
+        self
+    }
+
+    fn complete_page(&self, mut buffer: Vec<u8>, page_num: usize) -> anyhow::Result<Vec<u8>> {
+        if buffer.len() < self.page_size {
+            if !self.pad_short_page || buffer.is_empty() {
+                return Err(SqlrError::ShortPage {
+                    page_num,
+                    expected: self.page_size,
+                    actual: buffer.len(),
+                }
+                .into());
+            }
+            buffer.resize(self.page_size, 0);
+        }
+        Ok(buffer)
+    }
+
+    pub fn with_wal(mut self, wal: Wal<I>) -> Self {
+        self.wal = Some(wal);
+        self
+    }
+
+    // on a miss, the given number of following pages are read in the same call and cached, which
+    // pays off when scanning tables whose pages are contiguous
+    pub fn with_readahead(mut self, readahead: usize) -> Self {
+        self.readahead = readahead;
+        self
+    }
+
+    fn cache_page(&mut self, page_num: usize, page: Rc<Page>) {
+        // evicting before inserting so that the page returned is never evicted
+        if let Some(max_pages) = self.max_pages {
+            while self.pages.len() >= max_pages {
+                let Some(evicted) = self.lru.pop() else {
+                    break;
+                };
+                self.pages.remove(&evicted);
+            }
+        }
+        self.pages.insert(page_num, page);
+        self.lru.touch(page_num);
+    }
+
+    // the following pages may not be b-tree pages, e.g. overflow or freelist pages, they are only
+    // cached when they parse, the requested page is cached last so that it is the most recent
+    fn read_pages_ahead(&mut self, page_num: usize) -> anyhow::Result<Rc<Page>> {
+        let offset = page_num.saturating_sub(1) * self.page_size;
+        self.input
+            .seek(std::io::SeekFrom::Start(offset as u64))
+            .context("seek to page start")?;
+
+        // the window is cut short at the end of the input
+        let mut buffer = Vec::with_capacity((self.readahead + 1) * self.page_size);
+        (&mut self.input)
+            .take(buffer.capacity() as u64)
+            .read_to_end(&mut buffer)
+            .context("read pages")?;
+        // a short page can only be the last one, nothing follows it
+        if buffer.len() < self.page_size {
+            let buffer = self.complete_page(buffer, page_num)?;
+            let page = Rc::new(self.parse_page(&buffer, page_num)?);
+            self.cache_page(page_num, Rc::clone(&page));
+            return Ok(page);
+        }
+
+        let mut chunks = buffer.chunks_exact(self.page_size);
+        let page = Rc::new(self.parse_page(chunks.next().unwrap(), page_num)?);
+        for (i, chunk) in chunks.enumerate() {
+            let next_num = page_num + i + 1;
+            if self.pages.contains_key(&next_num) {
+                continue;
+            }
+            if let Ok(next) = self.parse_page(chunk, next_num) {
+                self.cache_page(next_num, Rc::new(next));
+            }
+        }
+        self.cache_page(page_num, Rc::clone(&page));
+        Ok(page)
+    }
+
+    // bypasses the cache, cells are only parsed when accessed
+    pub fn load_lazy_page(&mut self, page_num: usize) -> anyhow::Result<LazyPage> {
+        let buffer = self.load_raw_page(page_num)?;
+        LazyPage::parse(buffer, page_num, self.usable_size())
+            .map_err(|e| page::locate_error(e, page_num, self.page_size))
+    }
+
+    // caches at most max_pages pages, evicting the least recently used ones
+    pub fn with_capacity(input: I, page_size: usize, max_pages: usize) -> Self {
+        Self {
+            max_pages: Some(max_pages.max(1)),
+            ..Self::new(input, page_size)
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::paging::{cell::TableLeafCell, page_header::PageHeader};
+    use crate::{
+        error::SqlrError,
+        paging::{cell::TableLeafCell, page_header::PageHeader},
+    };
 
     use super::*;
 
     #[test]
-    fn load_page_tests() -> () {
+    fn load_page_tests() {
         let file = std::fs::File::open("test.db").unwrap();
         let mut pager = FilePager::new(file, 4096);
         assert!(pager.load_page(10).is_err());
@@ -93,13 +318,87 @@ mod test {
                         32, 105, 110, 116, 41
                     ]
                 }
-                .into()]
+                .into()],
+                free_blocks: vec![],
+                header_offset: 100,
             },
         )
     }
 
     #[test]
-    fn read_page_tests() -> () {
+    fn load_page_error_offset_tests() {
+        let mut bytes = std::fs::read("test.db").unwrap();
+        // page 2 has an unknown type
+        bytes[4096] = 1;
+        let mut pager = FilePager::new(std::io::Cursor::new(bytes.clone()), 4096);
+        let err = pager.load_page(2).unwrap_err();
+        assert_eq!(
+            "page 2 at file offset 4096: unknown page type: 1",
+            format!("{:#}", err)
+        );
+        assert_eq!(
+            Some(&SqlrError::UnknownPageType(1)),
+            err.downcast_ref::<SqlrError>()
+        );
+        assert!(pager
+            .load_lazy_page(2)
+            .is_err_and(|e| e.to_string() == "page 2 at file offset 4096"));
+
+        // its first cell pointer points into the pointer array
+        bytes[4096] = 13;
+        bytes[4096 + 8..4096 + 10].copy_from_slice(&5u16.to_be_bytes());
+        let mut pager = FilePager::new(std::io::Cursor::new(bytes), 4096);
+        let err = pager.read_page(2).unwrap_err();
+        assert_eq!("page 2 at file offset 4101", err.to_string());
+
+        // the first page is offset by the db header
+        let mut bytes = std::fs::read("test.db").unwrap();
+        bytes[100] = 1;
+        let mut pager = FilePager::new(std::io::Cursor::new(bytes), 4096);
+        assert_eq!(
+            "page 1 at file offset 100",
+            pager.load_page(1).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn short_page_tests() {
+        // the last 6 bytes of page 2 are missing
+        let mut bytes = std::fs::read("test.db").unwrap();
+        bytes.truncate(2 * 4096 - 6);
+        let mut pager = FilePager::new(std::io::Cursor::new(bytes.clone()), 4096);
+        let err = pager.load_page(2).unwrap_err();
+        assert_eq!(
+            Some(&SqlrError::ShortPage {
+                page_num: 2,
+                expected: 4096,
+                actual: 4090
+            }),
+            err.downcast_ref::<SqlrError>()
+        );
+        assert_eq!(
+            "page 2 is short: expected 4096 bytes, got 4090",
+            err.to_string()
+        );
+
+        for readahead in [0, 4] {
+            let mut pager = FilePager::new(std::io::Cursor::new(bytes.clone()), 4096)
+                .with_short_page_padding(true)
+                .with_readahead(readahead);
+            let raw = pager.load_raw_page(2).unwrap();
+            assert_eq!(4096, raw.len());
+            assert_eq!(&[0; 6], &raw[4090..]);
+            assert_eq!(2, pager.read_page(2).unwrap().cells.len());
+            // there is nothing to pad past the end of the input
+            assert!(matches!(
+                pager.read_page(3).unwrap_err().downcast_ref::<SqlrError>(),
+                Some(SqlrError::ShortPage { actual: 0, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn read_page_tests() {
         let file = std::fs::File::open("test.db").unwrap();
         let mut pager = FilePager::new(file, 4096);
         let pages = pager.pages.clone();
@@ -112,4 +411,200 @@ mod test {
         let page_opt = pages.get(&2).cloned();
         assert_eq!(Some(page), page_opt);
     }
+
+    #[test]
+    fn read_page_shares_cached_page_tests() {
+        let file = std::fs::File::open("test.db").unwrap();
+        let mut pager = FilePager::new(file, 4096);
+        let first = pager.read_page(2).unwrap();
+        let second = pager.read_page(2).unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(3, Rc::strong_count(&first));
+
+        // an evicted page stays alive as long as someone holds it
+        pager.invalidate(2);
+        assert_eq!(2, Rc::strong_count(&first));
+        let third = pager.read_page(2).unwrap();
+        assert!(!Rc::ptr_eq(&first, &third));
+        assert_eq!(first, third);
+    }
+
+    #[test]
+    fn load_page_64k_tests() {
+        let file = std::fs::File::open("test_64k.db").unwrap();
+        let mut pager = FilePager::new(file, 65536);
+        assert_eq!(65536, pager.load_raw_page(2).unwrap().len());
+
+        // cells are packed at the very end of the page
+        let page = pager.load_page(2).unwrap();
+        assert_eq!(3, page.cells.len());
+        assert!(page.cell_pointers.iter().all(|&ptr| ptr > 65500));
+
+        // an empty page stores its cell content offset as 0
+        let page = pager.load_page(3).unwrap();
+        assert_eq!(
+            PageHeader::TableLeafPageHeader {
+                first_freeblock: 0,
+                cell_count: 0,
+                cell_content_offset: 65536,
+                fragmented_bytes_count: 0,
+            },
+            page.header
+        );
+        assert!(pager.load_page(4).is_err());
+    }
+
+    #[test]
+    fn read_page_from_any_reader_tests() {
+        let bytes = std::fs::read("test.db").unwrap();
+        let expected = FilePager::new(std::fs::File::open("test.db").unwrap(), 4096)
+            .load_page(2)
+            .unwrap();
+
+        let mut pager = FilePager::new(std::io::Cursor::new(bytes.clone()), 4096);
+        assert_eq!(expected, *pager.read_page(2).unwrap());
+
+        let mut pager = FilePager::new(std::io::Cursor::new(bytes.as_slice()), 4096);
+        assert_eq!(expected, *pager.read_page(2).unwrap());
+        assert!(pager.read_page(3).is_err());
+
+        let mut pager: Box<dyn Pager> = Box::new(FilePager::new(std::io::Cursor::new(bytes), 4096));
+        assert_eq!(expected, *pager.read_page(2).unwrap());
+    }
+
+    #[test]
+    fn read_page_eviction_tests() {
+        let file = std::fs::File::open("test.db").unwrap();
+        let mut pager = FilePager::with_capacity(file, 4096, 1);
+        assert!(pager.read_page(1).is_ok());
+        assert!(pager.read_page(2).is_ok());
+        assert_eq!(vec![&2], pager.pages.keys().collect::<Vec<_>>());
+        assert!(pager.read_page(1).is_ok());
+        assert_eq!(vec![&1], pager.pages.keys().collect::<Vec<_>>());
+
+        let file = std::fs::File::open("test.db").unwrap();
+        let mut pager = FilePager::with_capacity(file, 4096, 2);
+        assert!(pager.read_page(1).is_ok());
+        assert!(pager.read_page(2).is_ok());
+        assert!(pager.read_page(1).is_ok());
+        assert_eq!(2, pager.pages.len());
+        assert_eq!(Some(2), pager.lru.pop());
+    }
+
+    #[test]
+    fn stats_tests() {
+        let file = std::fs::File::open("test.db").unwrap();
+        let mut pager = FilePager::with_capacity(file, 4096, 1);
+        assert_eq!(PagerStats::default(), pager.stats());
+        assert!(pager.read_page(1).is_ok());
+        assert!(pager.read_page(1).is_ok());
+        assert!(pager.read_page(2).is_ok());
+        assert!(pager.read_page(1).is_ok());
+        assert!(pager.read_page(3).is_err());
+        assert_eq!(PagerStats { hits: 1, misses: 4 }, pager.stats());
+        // loading directly bypasses the cache
+        assert!(pager.load_page(2).is_ok());
+        assert_eq!(PagerStats { hits: 1, misses: 4 }, pager.stats());
+    }
+
+    #[test]
+    fn clear_cache_tests() {
+        let file = std::fs::File::open("test.db").unwrap();
+        let mut pager = FilePager::new(file, 4096);
+        assert!(pager.read_page(1).is_ok());
+        assert!(pager.read_page(2).is_ok());
+        assert_eq!(2, pager.pages.len());
+
+        pager.invalidate(1);
+        assert_eq!(vec![&2], pager.pages.keys().collect::<Vec<_>>());
+        assert_eq!(1, pager.lru.len());
+        // invalidating a page that isn't cached is a no-op
+        pager.invalidate(1);
+        pager.invalidate(42);
+        assert_eq!(1, pager.pages.len());
+
+        assert!(pager.read_page(1).is_ok());
+        pager.clear_cache();
+        assert!(pager.pages.is_empty());
+        assert!(pager.lru.is_empty());
+
+        // pages are read again from the input
+        assert!(pager.read_page(2).is_ok());
+        assert_eq!(PagerStats { hits: 0, misses: 4 }, pager.stats());
+    }
+
+    #[test]
+    fn load_lazy_page_tests() {
+        let file = std::fs::File::open("test.db").unwrap();
+        let mut pager = FilePager::new(file, 4096);
+        assert!(pager.load_lazy_page(3).is_err());
+        let lazy = pager.load_lazy_page(2).unwrap();
+        assert_eq!(pager.load_page(2).unwrap(), lazy.to_page().unwrap());
+        assert!(pager.pages.is_empty());
+    }
+
+    #[test]
+    fn input_size_tests() {
+        let file = std::fs::File::open("test.db").unwrap();
+        let mut pager = FilePager::new(file, 4096);
+        assert_eq!(8192, pager.input_size().unwrap());
+        // reading still works after seeking to the end
+        assert!(pager.read_page(2).is_ok());
+        let mut pager = FilePager::new(std::io::Cursor::new(vec![0; 10]), 4096);
+        assert_eq!(10, pager.input_size().unwrap());
+    }
+
+    // counts the calls made to the underlying reader
+    struct CountingReader<R> {
+        inner: R,
+        reads: Rc<std::cell::Cell<usize>>,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads.set(self.reads.get() + 1);
+            self.inner.read(buf)
+        }
+    }
+
+    impl<R: Seek> Seek for CountingReader<R> {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn read_page_readahead_tests() {
+        let bytes = std::fs::read("test_index.db").unwrap();
+        let reads = Rc::new(std::cell::Cell::new(0));
+        let reader = CountingReader {
+            inner: std::io::Cursor::new(bytes.clone()),
+            reads: Rc::clone(&reads),
+        };
+        let mut pager = FilePager::new(reader, 4096).with_readahead(4);
+
+        let mut expected = FilePager::new(std::io::Cursor::new(bytes), 4096);
+        assert_eq!(expected.load_page(2).unwrap(), *pager.read_page(2).unwrap());
+        let after_first = reads.get();
+        for page_num in 3..=6 {
+            assert_eq!(
+                expected.load_page(page_num).unwrap(),
+                *pager.read_page(page_num).unwrap()
+            );
+        }
+        assert_eq!(after_first, reads.get());
+        assert_eq!(PagerStats { hits: 4, misses: 1 }, pager.stats());
+
+        // the window stops at the end of the input
+        assert!(pager.read_page(19).is_ok());
+        assert!(pager.pages.contains_key(&20));
+        assert!(pager.read_page(21).is_err());
+
+        // a bounded cache keeps the requested page
+        let file = std::fs::File::open("test_index.db").unwrap();
+        let mut pager = FilePager::with_capacity(file, 4096, 2).with_readahead(4);
+        assert!(pager.read_page(2).is_ok());
+        assert_eq!(2, pager.pages.len());
+        assert!(pager.pages.contains_key(&2));
+    }
 }
@@ -0,0 +1,30 @@
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PagerStats {
+    // pages served from the cache
+    pub hits: u64,
+    // pages that had to be loaded from the input
+    pub misses: u64,
+}
+
+impl PagerStats {
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            None
+        } else {
+            Some(self.hits as f64 / total as f64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hit_ratio_tests() {
+        assert_eq!(None, PagerStats::default().hit_ratio());
+        assert_eq!(Some(0.75), PagerStats { hits: 3, misses: 1 }.hit_ratio());
+        assert_eq!(Some(0.0), PagerStats { hits: 0, misses: 2 }.hit_ratio());
+    }
+}
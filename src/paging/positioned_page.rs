@@ -1,8 +1,14 @@
-use super::{cell::Cell, page::Page, page_header::PageHeader};
+use std::rc::Rc;
+
+use super::{
+    cell::Cell,
+    page::{self, Page},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct PositionedPage {
-    pub page: Page,
+    pub page: Rc<Page>,
+    pub page_num: usize,
     pub cell_num: usize,
 }
 
@@ -14,29 +20,46 @@ impl PositionedPage {
             .inspect(|_| self.cell_num += 1)
     }
 
+    // the rightmost pointer is handed out exactly once, after every cell has been visited
     pub fn next_page_pointer(&mut self) -> Option<u32> {
-        match self.page.header {
-            PageHeader::TableInteriorPageHeader { .. } => {
-                if self.cell_num >= self.page.cells.len() {
-                    self.cell_num = self.page.cells.len() + 1;
-                    self.page.header.rightmost_pointer()
-                } else {
-                    None
-                }
-            }
-            _ => None,
+        let rightmost_pointer = self.page.header.rightmost_pointer()?;
+        if self.cell_num == self.page.cells.len() {
+            self.cell_num += 1;
+            Some(rightmost_pointer)
+        } else {
+            None
         }
     }
+
+    // the cell whose left child was descended into last, if any
+    pub fn previous_cell(&self) -> Option<&Cell> {
+        self.cell_num
+            .checked_sub(1)
+            .and_then(|i| self.page.cells.get(i))
+    }
+
+    // where the given cell starts in the file, to report records which can't be parsed
+    pub fn cell_file_offset(&self, cell_num: usize, page_size: usize) -> Option<u64> {
+        let pointer = *self.page.cell_pointers.get(cell_num)? as usize;
+        Some(page::file_offset(
+            self.page_num,
+            page_size,
+            self.page.header_offset as usize + pointer,
+        ))
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::paging::cell::{TableInteriorCell, TableLeafCell};
+    use crate::paging::{
+        cell::{TableInteriorCell, TableLeafCell},
+        page_header::PageHeader,
+    };
 
     use super::*;
 
     #[test]
-    fn next_page_pointer_tests() -> () {
+    fn next_page_pointer_tests() {
         let leaf_header = PageHeader::TableLeafPageHeader {
             first_freeblock: 0,
             cell_count: 2,
@@ -47,9 +70,12 @@ mod test {
             header: leaf_header,
             cell_pointers: vec![],
             cells: vec![],
+            free_blocks: vec![],
+            header_offset: 0,
         };
         let mut leaf_p_page = PositionedPage {
-            page: leaf_page,
+            page: Rc::new(leaf_page),
+            page_num: 2,
             cell_num: 0,
         };
         assert_eq!(None, leaf_p_page.next_page_pointer());
@@ -66,29 +92,41 @@ mod test {
             cell_count: 2,
             cell_content_offset: 0,
             fragmented_bytes_count: 0,
-            rightmost_pointer: rightmost_pointer,
+            rightmost_pointer,
         };
         let int_page = Page {
             header: int_header,
             cell_pointers: vec![],
             cells: vec![c1],
+            free_blocks: vec![],
+            header_offset: 0,
         };
         let mut int_p_page = PositionedPage {
-            page: int_page.clone(),
+            page: Rc::new(int_page.clone()),
+            page_num: 2,
             cell_num: 1,
         };
         assert_eq!(Some(rightmost_pointer), int_p_page.next_page_pointer());
         assert_eq!(2, int_p_page.cell_num);
         let mut int_p_page_2 = PositionedPage {
-            page: int_page,
+            page: Rc::new(int_page.clone()),
+            page_num: 2,
             cell_num: 0,
         };
         assert_eq!(None, int_p_page_2.next_page_pointer());
         assert_eq!(0, int_p_page_2.cell_num);
+        let mut int_p_page_3 = PositionedPage {
+            page: Rc::new(int_page),
+            page_num: 2,
+            cell_num: 1,
+        };
+        assert_eq!(Some(rightmost_pointer), int_p_page_3.next_page_pointer());
+        assert_eq!(None, int_p_page_3.next_page_pointer());
+        assert_eq!(None, int_p_page_3.next_cell());
     }
 
     #[test]
-    fn next_cell_leaf_tests() -> () {
+    fn next_cell_leaf_tests() {
         let c1: Cell = TableLeafCell {
             size: 2,
             row_id: 12,
@@ -110,9 +148,12 @@ mod test {
             },
             cell_pointers: vec![1, 10, 12],
             cells: vec![c1.clone(), c2.clone()],
+            free_blocks: vec![],
+            header_offset: 0,
         };
         let mut p_page = PositionedPage {
-            page: page,
+            page: Rc::new(page),
+            page_num: 2,
             cell_num: 0,
         };
         let res1 = p_page.next_cell().cloned();
@@ -125,7 +166,7 @@ mod test {
     }
 
     #[test]
-    fn next_cell_interior_tests() -> () {
+    fn next_cell_interior_tests() {
         let c1: Cell = TableInteriorCell {
             left_child_page: 1,
             key: 12,
@@ -146,9 +187,12 @@ mod test {
             },
             cell_pointers: vec![1, 10, 12],
             cells: vec![c1.clone(), c2.clone()],
+            free_blocks: vec![],
+            header_offset: 0,
         };
         let mut p_page = PositionedPage {
-            page: page,
+            page: Rc::new(page),
+            page_num: 2,
             cell_num: 0,
         };
         let res1 = p_page.next_cell().cloned();
@@ -159,4 +203,38 @@ mod test {
         assert_eq!(None, res3);
         assert_eq!(2, p_page.cell_num);
     }
+
+    #[test]
+    fn cell_file_offset_tests() {
+        let page = Page {
+            header: PageHeader::TableLeafPageHeader {
+                first_freeblock: 0,
+                cell_count: 1,
+                cell_content_offset: 0,
+                fragmented_bytes_count: 0,
+            },
+            cell_pointers: vec![3938],
+            cells: vec![],
+            free_blocks: vec![],
+            header_offset: 0,
+        };
+        let p_page = PositionedPage {
+            page: Rc::new(page.clone()),
+            page_num: 3,
+            cell_num: 0,
+        };
+        assert_eq!(Some(2 * 4096 + 3938), p_page.cell_file_offset(0, 4096));
+        assert_eq!(None, p_page.cell_file_offset(1, 4096));
+
+        // pointers on the first page are relative to the page header, after the db header
+        let p_page = PositionedPage {
+            page: Rc::new(Page {
+                header_offset: 100,
+                ..page
+            }),
+            page_num: 1,
+            cell_num: 0,
+        };
+        assert_eq!(Some(4038), p_page.cell_file_offset(0, 4096));
+    }
 }
@@ -0,0 +1,216 @@
+use std::{
+    collections::HashMap,
+    io::{ErrorKind, Read, Seek},
+};
+
+use anyhow::Context;
+
+use crate::utils;
+
+const HEADER_SIZE: usize = 32;
+const FRAME_HEADER_SIZE: usize = 24;
+// the lowest bit gives the byte order of the checksums, set for big-endian
+const MAGIC: u32 = 0x377f_0682;
+
+// https://www.sqlite.org/fileformat.html#the_write_ahead_log
+// only frames up to the last valid commit frame are visible, frames written after it belong to
+// a transaction which was never committed, or to a previous generation of the wal when their
+// salts or checksums don't match
+#[derive(Debug, Clone)]
+pub struct Wal<I: Read + Seek> {
+    input: I,
+    // 0 when the wal is empty
+    pub page_size: usize,
+    // offset in the wal of the latest committed content of each page
+    frames: HashMap<usize, u64>,
+    // size in pages of the database as of the last commit, None when nothing was committed
+    pub database_size: Option<u32>,
+}
+
+impl<I: Read + Seek> Wal<I> {
+    pub fn parse(input: I) -> anyhow::Result<Self> {
+        let mut wal = Wal {
+            input,
+            page_size: 0,
+            frames: HashMap::new(),
+            database_size: None,
+        };
+        wal.read_frames()?;
+        Ok(wal)
+    }
+
+    fn read_frames(&mut self) -> anyhow::Result<()> {
+        let input = &mut self.input;
+        let mut header = [0; HEADER_SIZE];
+        if !read_exact_or_eof(input, &mut header).context("read wal header")? {
+            return Ok(());
+        }
+        let magic = utils::read_be_double_word_at(&header, 0).1;
+        if magic & !1 != MAGIC {
+            anyhow::bail!("invalid wal magic: {:#x}", magic);
+        }
+        let big_endian = magic & 1 == 1;
+        let page_size = utils::read_be_double_word_at(&header, 8).1 as usize;
+        // frames are allocated with this size
+        if !page_size.is_power_of_two() || !(512..=65536).contains(&page_size) {
+            anyhow::bail!("invalid wal page size: {}", page_size);
+        }
+        self.page_size = page_size;
+        let salts = &header[16..24];
+        let mut sums = checksum(&header[..24], big_endian, (0, 0));
+        // a wal whose header doesn't check out holds no valid frame
+        if sums != read_sums(&header[24..]) {
+            return Ok(());
+        }
+
+        let mut uncommitted = Vec::new();
+        let mut frame = vec![0; FRAME_HEADER_SIZE + self.page_size];
+        let mut offset = HEADER_SIZE as u64;
+        while read_exact_or_eof(input, &mut frame).context("read wal frame")? {
+            if &frame[8..16] != salts {
+                break;
+            }
+            sums = checksum(&frame[..8], big_endian, sums);
+            sums = checksum(&frame[FRAME_HEADER_SIZE..], big_endian, sums);
+            if sums != read_sums(&frame[16..24]) {
+                break;
+            }
+
+            let page_num = utils::read_be_double_word_at(&frame, 0).1 as usize;
+            uncommitted.push((page_num, offset + FRAME_HEADER_SIZE as u64));
+            // set on the last frame of a transaction only
+            let commit_size = utils::read_be_double_word_at(&frame, 4).1;
+            if commit_size != 0 {
+                self.frames.extend(uncommitted.drain(..));
+                self.database_size = Some(commit_size);
+            }
+            offset += frame.len() as u64;
+        }
+
+        Ok(())
+    }
+
+    // None when the page wasn't changed by a committed transaction in the wal
+    pub fn read_page(&mut self, page_num: usize) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(&offset) = self.frames.get(&page_num) else {
+            return Ok(None);
+        };
+        self.input
+            .seek(std::io::SeekFrom::Start(offset))
+            .context("seek to wal frame")?;
+        let mut buffer = vec![0; self.page_size];
+        self.input
+            .read_exact(&mut buffer)
+            .context("read wal frame")?;
+        Ok(Some(buffer))
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+// false when the input ends before the buffer is filled, whatever was read is then meaningless
+fn read_exact_or_eof<R: Read>(input: &mut R, buffer: &mut [u8]) -> std::io::Result<bool> {
+    match input.read_exact(buffer) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn read_sums(buffer: &[u8]) -> (u32, u32) {
+    (
+        utils::read_be_double_word_at(buffer, 0).1,
+        utils::read_be_double_word_at(buffer, 4).1,
+    )
+}
+
+// https://www.sqlite.org/fileformat.html#checksum_algorithm, data is a multiple of 8 bytes
+fn checksum(data: &[u8], big_endian: bool, (mut s0, mut s1): (u32, u32)) -> (u32, u32) {
+    let word = |bytes: &[u8]| {
+        let bytes = bytes.try_into().unwrap();
+        if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    };
+    for chunk in data.chunks_exact(8) {
+        s0 = s0.wrapping_add(word(&chunk[..4])).wrapping_add(s1);
+        s1 = s1.wrapping_add(word(&chunk[4..])).wrapping_add(s0);
+    }
+    (s0, s1)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    // frame header + page
+    const FRAME_SIZE: usize = 24 + 4096;
+
+    fn wal(bytes: Vec<u8>) -> Wal<Cursor<Vec<u8>>> {
+        Wal::parse(Cursor::new(bytes)).unwrap()
+    }
+
+    #[test]
+    fn parse_wal_tests() {
+        // one transaction writing pages 1, 2 and 3, then another one writing page 2
+        let bytes = std::fs::read("test_wal.db-wal").unwrap();
+        let mut full = wal(bytes.clone());
+        assert_eq!(4096, full.page_size);
+        assert_eq!(Some(3), full.database_size);
+        assert_eq!(3, full.page_count());
+        let last_frame = 32 + 3 * FRAME_SIZE + 24;
+        assert_eq!(
+            Some(bytes[last_frame..last_frame + 4096].to_vec()),
+            full.read_page(2).unwrap()
+        );
+        assert_eq!(None, full.read_page(4).unwrap());
+
+        // the second transaction is lost when its frame is incomplete or doesn't check out
+        let first_frame_of_2 = 32 + FRAME_SIZE + 24;
+        let expected = Some(bytes[first_frame_of_2..first_frame_of_2 + 4096].to_vec());
+        let mut truncated = wal(bytes[..bytes.len() - 1].to_vec());
+        assert_eq!(expected, truncated.read_page(2).unwrap());
+        let mut corrupted = bytes.clone();
+        corrupted[last_frame + 100] ^= 1;
+        assert_eq!(expected, wal(corrupted).read_page(2).unwrap());
+        let mut old_salt = bytes.clone();
+        old_salt[32 + 3 * FRAME_SIZE + 8] ^= 1;
+        assert_eq!(expected, wal(old_salt).read_page(2).unwrap());
+
+        // nothing is committed before the third frame
+        let uncommitted = wal(bytes[..32 + 2 * FRAME_SIZE].to_vec());
+        assert_eq!(None, uncommitted.database_size);
+        assert_eq!(0, uncommitted.page_count());
+
+        let mut bad_header = bytes.clone();
+        bad_header[12] ^= 1;
+        assert_eq!(0, wal(bad_header).page_count());
+        assert_eq!(0, wal(Vec::new()).page_count());
+        assert!(Wal::parse(Cursor::new(vec![0; 32])).is_err());
+
+        // the page size is checked before anything is allocated for the frames
+        for page_size in [0u32, 1000, 131072, u32::MAX] {
+            let mut bad_page_size = bytes.clone();
+            bad_page_size[8..12].copy_from_slice(&page_size.to_be_bytes());
+            let err = Wal::parse(Cursor::new(bad_page_size)).unwrap_err();
+            assert_eq!(
+                format!("invalid wal page size: {}", page_size),
+                err.to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn checksum_tests() {
+        let data = [0, 0, 0, 1, 0, 0, 0, 2];
+        assert_eq!((1, 3), checksum(&data, true, (0, 0)));
+        assert_eq!((1 << 24, 3 << 24), checksum(&data[..], false, (0, 0)));
+        assert_eq!((4, 8), checksum(&data, true, (1, 2)));
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    cursor::Cursor,
+    value::{parse_numeric, Value},
+};
+
+use super::executor::{column_value, ColumnIndex};
+
+// a result column of an aggregate query, resolved against the table
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggregateColumn {
+    // the value of the GROUP BY column shared by the rows of the group
+    Group,
+    CountRows,
+    CountDistinct(ColumnIndex),
+    Sum(ColumnIndex),
+    Min(ColumnIndex),
+    Max(ColumnIndex),
+    Avg(ColumnIndex),
+}
+
+// the records of an aggregate query are folded into one row per group, or into a single row
+// without GROUP BY
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregation {
+    pub group_by: Option<ColumnIndex>,
+    pub columns: Vec<AggregateColumn>,
+    // groups are emitted in ascending order of their key unless ordered by it descending
+    pub descending: bool,
+}
+
+impl Aggregation {
+    pub fn run(
+        &self,
+        mut next_record: impl FnMut() -> anyhow::Result<Option<Cursor>>,
+    ) -> anyhow::Result<Vec<Vec<Value<'static>>>> {
+        let mut groups = HashMap::new();
+        while let Some(record) = next_record()? {
+            let key = match self.group_by {
+                Some(i) => column_value(&record, i)?.into_owned(),
+                None => Value::Null,
+            };
+            let accumulators = groups.entry(key).or_insert_with(|| self.accumulators());
+            for accumulator in accumulators.iter_mut() {
+                accumulator.add(&record)?;
+            }
+        }
+        // an aggregate over no rows still yields a row, unless there are no groups
+        if self.group_by.is_none() && groups.is_empty() {
+            groups.insert(Value::Null, self.accumulators());
+        }
+
+        let mut groups = groups.into_iter().collect::<Vec<_>>();
+        groups.sort_by(|(a, _), (b, _)| {
+            let ordering = a.cmp_sqlite(b);
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        groups
+            .into_iter()
+            .map(|(key, accumulators)| {
+                accumulators
+                    .into_iter()
+                    .map(|a| a.finish(&key))
+                    .collect::<anyhow::Result<_>>()
+            })
+            .collect()
+    }
+
+    fn accumulators(&self) -> Vec<Accumulator> {
+        self.columns
+            .iter()
+            .map(|column| match *column {
+                AggregateColumn::Group => Accumulator::Group,
+                AggregateColumn::CountRows => Accumulator::CountRows(0),
+                AggregateColumn::CountDistinct(i) => Accumulator::CountDistinct(i, HashSet::new()),
+                AggregateColumn::Sum(i) => Accumulator::Sum(i, Sum::default()),
+                AggregateColumn::Min(i) => Accumulator::Extremum(i, Ordering::Less, None),
+                AggregateColumn::Max(i) => Accumulator::Extremum(i, Ordering::Greater, None),
+                AggregateColumn::Avg(i) => Accumulator::Avg(i, Sum::default()),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+enum Accumulator {
+    Group,
+    CountRows(i64),
+    // nulls aren't counted
+    CountDistinct(ColumnIndex, HashSet<Value<'static>>),
+    Sum(ColumnIndex, Sum),
+    // the smallest or largest non-null value in sqlite's sort order depending on the ordering a
+    // value must have against the current one to replace it, ties keep the first value
+    Extremum(ColumnIndex, Ordering, Option<Value<'static>>),
+    Avg(ColumnIndex, Sum),
+}
+
+impl Accumulator {
+    fn add(&mut self, record: &Cursor) -> anyhow::Result<()> {
+        let column = match *self {
+            Accumulator::Group | Accumulator::CountRows(_) => None,
+            Accumulator::CountDistinct(i, _)
+            | Accumulator::Sum(i, _)
+            | Accumulator::Extremum(i, _, _)
+            | Accumulator::Avg(i, _) => Some(i),
+        };
+        let value = match column {
+            Some(i) => column_value(record, i)?,
+            None => Value::Null,
+        };
+        self.add_value(value);
+        Ok(())
+    }
+
+    fn add_value(&mut self, value: Value) {
+        match self {
+            Accumulator::Group => (),
+            Accumulator::CountRows(count) => *count += 1,
+            Accumulator::CountDistinct(_, values) => {
+                if value != Value::Null {
+                    values.insert(value.into_owned());
+                }
+            }
+            Accumulator::Sum(_, sum) | Accumulator::Avg(_, sum) => sum.add(&value),
+            Accumulator::Extremum(_, ordering, current) => {
+                let replace = current
+                    .as_ref()
+                    .is_none_or(|c| value.cmp_sqlite(c) == *ordering);
+                if value != Value::Null && replace {
+                    *current = Some(value.into_owned());
+                }
+            }
+        }
+    }
+
+    fn finish(self, key: &Value<'static>) -> anyhow::Result<Value<'static>> {
+        let value = match self {
+            Accumulator::Group => key.clone(),
+            Accumulator::CountRows(count) => Value::Int(count),
+            Accumulator::CountDistinct(_, values) => Value::Int(values.len() as i64),
+            Accumulator::Sum(_, sum) => sum.total()?,
+            Accumulator::Extremum(_, _, value) => value.unwrap_or(Value::Null),
+            Accumulator::Avg(_, sum) => sum.average(),
+        };
+        Ok(value)
+    }
+}
+
+// like sqlite, integers are summed exactly until a value which isn't one is added or the sum
+// overflows, from then on the sum is a float
+#[derive(Debug, Default)]
+struct Sum {
+    int: i64,
+    float: f64,
+    approximate: bool,
+    // only an error for SUM, AVG carries on with the float
+    overflow: bool,
+    // non-null values added
+    count: usize,
+}
+
+impl Sum {
+    fn add(&mut self, value: &Value) {
+        let value = match numeric(value) {
+            Value::Null => return,
+            Value::Int(i) if !self.approximate => match self.int.checked_add(i) {
+                Some(int) => {
+                    self.int = int;
+                    self.count += 1;
+                    return;
+                }
+                None => {
+                    self.overflow = true;
+                    i as f64
+                }
+            },
+            other => other.as_f64().unwrap_or(0.0),
+        };
+        if !self.approximate {
+            self.approximate = true;
+            self.float = self.int as f64;
+        }
+        self.float += value;
+        self.count += 1;
+    }
+
+    // null when every value was null
+    fn total(self) -> anyhow::Result<Value<'static>> {
+        let total = match self {
+            Sum { count: 0, .. } => Value::Null,
+            Sum { overflow: true, .. } => anyhow::bail!("integer overflow"),
+            Sum {
+                approximate: true,
+                float,
+                ..
+            } => Value::Float(float),
+            Sum { int, .. } => Value::Int(int),
+        };
+        Ok(total)
+    }
+
+    // always a float, null when every value was null
+    fn average(self) -> Value<'static> {
+        match self {
+            Sum { count: 0, .. } => Value::Null,
+            Sum {
+                approximate: true,
+                float,
+                count,
+                ..
+            } => Value::Float(float / count as f64),
+            Sum { int, count, .. } => Value::Float(int as f64 / count as f64),
+        }
+    }
+}
+
+// text and blobs holding a number are summed as that number, other ones count as 0.0
+fn numeric(value: &Value) -> Value<'static> {
+    let text = match value {
+        Value::String(s) => s.as_ref(),
+        Value::Blob(b) => std::str::from_utf8(b).unwrap_or_default(),
+        Value::Null => return Value::Null,
+        Value::Int(i) => return Value::Int(*i),
+        Value::Float(f) => return Value::Float(*f),
+    };
+    // parse_numeric turns reals like 2.0 into ints, a sum of reals stays a real though
+    if parse_numeric(text).is_none() {
+        return Value::Float(0.0);
+    }
+    let text = text.trim();
+    text.parse::<i64>()
+        .map(Value::Int)
+        .or_else(|_| text.parse::<f64>().map(Value::Float))
+        .unwrap_or(Value::Float(0.0))
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    fn add(values: &[Value]) -> Sum {
+        let mut sum = Sum::default();
+        for value in values {
+            sum.add(value);
+        }
+        sum
+    }
+
+    fn sum(values: &[Value]) -> anyhow::Result<Value<'static>> {
+        add(values).total()
+    }
+
+    #[test]
+    fn sum_tests() {
+        assert_eq!(Value::Null, sum(&[]).unwrap());
+        assert_eq!(Value::Null, sum(&[Value::Null, Value::Null]).unwrap());
+        let total = sum(&[Value::Int(1), Value::Null, Value::Int(2)]).unwrap();
+        assert!(matches!(total, Value::Int(3)));
+        let total = sum(&[Value::Int(1), Value::Float(0.5), Value::Int(2)]).unwrap();
+        assert!(matches!(total, Value::Float(f) if f == 3.5));
+        let total = sum(&[Value::Int(1), Value::String(Cow::from(" 2 "))]).unwrap();
+        assert!(matches!(total, Value::Int(3)));
+        let total = sum(&[Value::Int(1), Value::String(Cow::from("abc"))]).unwrap();
+        assert!(matches!(total, Value::Float(f) if f == 1.0));
+        assert!(sum(&[Value::Int(i64::MAX), Value::Int(1)]).is_err());
+        // past the first float, large integers no longer overflow
+        let total = sum(&[Value::Float(0.0), Value::Int(i64::MAX), Value::Int(1)]).unwrap();
+        assert!(matches!(total, Value::Float(_)));
+    }
+
+    #[test]
+    fn average_tests() {
+        assert_eq!(Value::Null, add(&[]).average());
+        assert_eq!(Value::Null, add(&[Value::Null]).average());
+        let average = add(&[Value::Int(1), Value::Null, Value::Int(2)]).average();
+        assert!(matches!(average, Value::Float(f) if f == 1.5));
+        let average = add(&[Value::Float(1.0), Value::Int(4)]).average();
+        assert!(matches!(average, Value::Float(f) if f == 2.5));
+        // an overflowing sum isn't an error
+        let average = add(&[Value::Int(i64::MAX), Value::Int(i64::MAX)]).average();
+        assert!(matches!(average, Value::Float(f) if f == i64::MAX as f64));
+    }
+
+    #[test]
+    fn extremum_tests() {
+        let extremum = |ordering: Ordering, values: Vec<Value<'static>>| {
+            let mut accumulator = Accumulator::Extremum(ColumnIndex::Field(0), ordering, None);
+            for value in values {
+                accumulator.add_value(value);
+            }
+            accumulator.finish(&Value::Null).unwrap()
+        };
+        let mixed = || {
+            vec![
+                Value::Null,
+                Value::String(Cow::from("a")),
+                Value::Float(2.5),
+                Value::Blob(Cow::from(&[0][..])),
+                Value::Int(3),
+                Value::Null,
+            ]
+        };
+        // numbers sort before text which sorts before blobs
+        assert_eq!(Value::Float(2.5), extremum(Ordering::Less, mixed()));
+        assert_eq!(
+            Value::Blob(Cow::from(&[0][..])),
+            extremum(Ordering::Greater, mixed())
+        );
+        assert_eq!(Value::Null, extremum(Ordering::Less, vec![Value::Null]));
+        assert_eq!(Value::Null, extremum(Ordering::Greater, vec![]));
+        // the first of equal values is kept
+        assert!(matches!(
+            extremum(Ordering::Less, vec![Value::Int(1), Value::Float(1.0)]),
+            Value::Int(1)
+        ));
+    }
+
+    #[test]
+    fn numeric_tests() {
+        assert_eq!(Value::Int(3), numeric(&Value::String(Cow::from("3"))));
+        assert_eq!(
+            Value::Float(2.5),
+            numeric(&Value::Blob(Cow::from(&b"2.5"[..])))
+        );
+        assert_eq!(
+            Value::Float(0.0),
+            numeric(&Value::Blob(Cow::from(&[255][..])))
+        );
+        assert_eq!(Value::Null, numeric(&Value::Null));
+        // sqlite reads neither as a number
+        assert_eq!(Value::Float(0.0), numeric(&Value::String(Cow::from("inf"))));
+        assert_eq!(
+            Value::Float(0.0),
+            numeric(&Value::String(Cow::from(" -Infinity")))
+        );
+        assert_eq!(Value::Float(0.0), numeric(&Value::String(Cow::from("nan"))));
+        assert_eq!(Value::Float(2.0), numeric(&Value::String(Cow::from("2.0"))));
+    }
+}
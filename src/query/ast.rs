@@ -0,0 +1,160 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use crate::value::Value;
+
+// a column, optionally qualified by the name of its table
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnRef {
+    pub table: Option<String>,
+    pub name: String,
+}
+
+impl From<&str> for ColumnRef {
+    fn from(name: &str) -> Self {
+        ColumnRef {
+            table: None,
+            name: name.to_string(),
+        }
+    }
+}
+
+impl Display for ColumnRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.table {
+            Some(table) => write!(f, "{}.{}", table, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResultColumn {
+    // every column of the table, in declaration order
+    Star,
+    Column(ColumnRef),
+    Aggregate(Aggregate),
+    // col AS alias, the alias names the column in the results
+    Aliased(Box<ResultColumn>, String),
+}
+
+impl ResultColumn {
+    pub fn is_aggregate(&self) -> bool {
+        match self {
+            ResultColumn::Aggregate(_) => true,
+            ResultColumn::Aliased(column, _) => column.is_aggregate(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Aggregate {
+    // COUNT(*), every row including the ones full of nulls
+    CountRows,
+    // COUNT(DISTINCT col), the number of different non-null values of the column
+    CountDistinct(ColumnRef),
+    // SUM(col), null when every value is null
+    Sum(ColumnRef),
+    // MIN(col) and MAX(col) follow the sort order, nulls are ignored
+    Min(ColumnRef),
+    Max(ColumnRef),
+    // AVG(col), always a float
+    Avg(ColumnRef),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    // col <op> literal, never true when either side is NULL
+    Compare {
+        column: ColumnRef,
+        comparison: Comparison,
+        value: Value<'static>,
+    },
+    // col BETWEEN low AND high, bounds included
+    Between {
+        column: ColumnRef,
+        low: Value<'static>,
+        high: Value<'static>,
+    },
+    IsNull {
+        column: ColumnRef,
+    },
+    // col LIKE 'pattern', only ever true for text values
+    Like {
+        column: ColumnRef,
+        pattern: String,
+    },
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+}
+
+impl Predicate {
+    // the col = literal comparisons which must hold for the whole predicate to be true, i.e.
+    // the ones which aren't under an OR
+    pub fn required_equalities(&self) -> Vec<(&ColumnRef, &Value<'static>)> {
+        match self {
+            Predicate::Compare {
+                column,
+                comparison: Comparison::Eq,
+                value,
+            } => vec![(column, value)],
+            Predicate::And(left, right) => {
+                let mut equalities = left.required_equalities();
+                equalities.extend(right.required_equalities());
+                equalities
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl Comparison {
+    // whether a value ordered this way against the literal satisfies the comparison
+    pub fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Eq => ordering == Ordering::Equal,
+            Comparison::NotEq => ordering != Ordering::Equal,
+            Comparison::Lt => ordering == Ordering::Less,
+            Comparison::LtEq => ordering != Ordering::Greater,
+            Comparison::Gt => ordering == Ordering::Greater,
+            Comparison::GtEq => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub column: ColumnRef,
+    pub descending: bool,
+}
+
+// [INNER] JOIN table ON col = col, only equalities between a column of each table are supported
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub table: String,
+    // the sides of the ON equality, in the order they were written
+    pub on: (ColumnRef, ColumnRef),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Select {
+    pub columns: Vec<ResultColumn>,
+    pub table: String,
+    pub join: Option<Join>,
+    pub filter: Option<Predicate>,
+    pub group_by: Option<ColumnRef>,
+    pub order_by: Option<OrderBy>,
+    // None when there is no limit
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
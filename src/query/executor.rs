@@ -0,0 +1,1702 @@
+use std::cmp::Ordering;
+
+use crate::{
+    cursor::Cursor,
+    db::Db,
+    paging::pager::Pager,
+    scanner::Scanner,
+    schema::{column_def::ColumnDef, index::Index, table::Table},
+    value::{Affinity, Value},
+};
+
+use super::{
+    aggregate::{AggregateColumn, Aggregation},
+    ast::{Aggregate, ColumnRef, Comparison, Join, OrderBy, Predicate, ResultColumn, Select},
+    like::like_match,
+};
+
+// the names the rowid goes by, unless a column is declared with one of them
+const ROWID_NAMES: [&str; 3] = ["rowid", "_rowid_", "oid"];
+
+// where a column is read from in the records of a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnIndex {
+    // a field of the record, the fields of a joined record come after the ones of the first
+    Field(usize),
+    // the rowid of a table which has no INTEGER PRIMARY KEY to alias it, 0 for the record's own
+    // table and 1 for the joined one
+    RowId(usize),
+}
+
+// the tables a query reads from, the columns of the joined table are numbered after the ones of
+// the first table like in the records a join produces
+struct Scope<'t> {
+    tables: Vec<&'t Table>,
+    columns: Vec<&'t ColumnDef>,
+}
+
+impl<'t> Scope<'t> {
+    fn new(tables: Vec<&'t Table>) -> Scope<'t> {
+        let columns = tables.iter().flat_map(|t| &t.columns).collect();
+        Scope { tables, columns }
+    }
+
+    // the index of the table the column belongs to along with where it is in that table's
+    // records, an unqualified column can only belong to one of the tables, like in sqlite the
+    // rowid aliases are only looked up when no table has a column with that name
+    fn locate(&self, column: &ColumnRef) -> anyhow::Result<(usize, ColumnIndex)> {
+        let field = |table: &Table, name: &str| table.column_index(name).map(ColumnIndex::Field);
+        match self.locate_with(column, field)? {
+            Some(located) => Ok(located),
+            None => self
+                .locate_with(column, rowid_column)?
+                .ok_or_else(|| anyhow::anyhow!("no such column: {}", column)),
+        }
+    }
+
+    fn locate_with(
+        &self,
+        column: &ColumnRef,
+        column_index: impl Fn(&Table, &str) -> Option<ColumnIndex>,
+    ) -> anyhow::Result<Option<(usize, ColumnIndex)>> {
+        let mut located = None;
+        for (t, table) in self.tables.iter().enumerate() {
+            let in_table = column
+                .table
+                .as_ref()
+                .is_none_or(|name| name.eq_ignore_ascii_case(&table.name));
+            if let Some(i) = column_index(table, &column.name).filter(|_| in_table) {
+                if located.is_some() {
+                    anyhow::bail!("ambiguous column name: {}", column);
+                }
+                located = Some((t, i));
+            }
+        }
+        Ok(located)
+    }
+
+    // the index of the column in the records the query reads
+    fn resolve(&self, column: &ColumnRef) -> anyhow::Result<ColumnIndex> {
+        match self.locate(column)? {
+            (t, ColumnIndex::Field(i)) => {
+                let first_column = self.tables[..t]
+                    .iter()
+                    .map(|t| t.columns.len())
+                    .sum::<usize>();
+                Ok(ColumnIndex::Field(first_column + i))
+            }
+            (t, ColumnIndex::RowId(_)) => Ok(ColumnIndex::RowId(t)),
+        }
+    }
+
+    // the rowid pseudo-column is named like sqlite does, whichever alias was used
+    fn name(&self, column: ColumnIndex) -> &str {
+        match column {
+            ColumnIndex::Field(i) => &self.columns[i].name,
+            ColumnIndex::RowId(_) => "rowid",
+        }
+    }
+
+    fn affinity(&self, column: ColumnIndex) -> Affinity {
+        match column {
+            ColumnIndex::Field(i) => self.columns[i].affinity,
+            ColumnIndex::RowId(_) => Affinity::Integer,
+        }
+    }
+}
+
+// where the rowid is read from in the table's records when the column name is one of its
+// aliases: the INTEGER PRIMARY KEY if there is one, tables WITHOUT ROWID have none
+fn rowid_column(table: &Table, name: &str) -> Option<ColumnIndex> {
+    if table.without_rowid || !ROWID_NAMES.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+        return None;
+    }
+    Some(
+        table
+            .rowid_alias()
+            .map_or(ColumnIndex::RowId(0), ColumnIndex::Field),
+    )
+}
+
+// a predicate with its column resolved to an index in the record
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Compare(ColumnIndex, Comparison, Value<'static>),
+    Between(ColumnIndex, Value<'static>, Value<'static>),
+    IsNull(ColumnIndex),
+    Like(ColumnIndex, String),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    fn resolve(scope: &Scope, predicate: &Predicate) -> anyhow::Result<Condition> {
+        let condition = match predicate {
+            Predicate::Compare {
+                column,
+                comparison,
+                value,
+            } => {
+                let i = scope.resolve(column)?;
+                Condition::Compare(i, *comparison, coerce_literal(scope.affinity(i), value))
+            }
+            Predicate::Between { column, low, high } => {
+                let i = scope.resolve(column)?;
+                Condition::Between(
+                    i,
+                    coerce_literal(scope.affinity(i), low),
+                    coerce_literal(scope.affinity(i), high),
+                )
+            }
+            Predicate::IsNull { column } => Condition::IsNull(scope.resolve(column)?),
+            Predicate::Like { column, pattern } => {
+                Condition::Like(scope.resolve(column)?, pattern.clone())
+            }
+            Predicate::And(left, right) => Condition::And(
+                Box::new(Self::resolve(scope, left)?),
+                Box::new(Self::resolve(scope, right)?),
+            ),
+            Predicate::Or(left, right) => Condition::Or(
+                Box::new(Self::resolve(scope, left)?),
+                Box::new(Self::resolve(scope, right)?),
+            ),
+        };
+        Ok(condition)
+    }
+
+    fn matches(&self, record: &Cursor) -> anyhow::Result<bool> {
+        let matches = match self {
+            // comparing anything to null yields null, which isn't true
+            Condition::Compare(_, _, Value::Null) => false,
+            Condition::Compare(i, comparison, value) => {
+                let field = column_value(record, *i)?;
+                field != Value::Null && comparison.holds(field.cmp_sqlite(value))
+            }
+            Condition::Between(_, Value::Null, _) | Condition::Between(_, _, Value::Null) => false,
+            Condition::Between(i, low, high) => {
+                let field = column_value(record, *i)?;
+                field != Value::Null && &field >= low && &field <= high
+            }
+            Condition::IsNull(i) => column_value(record, *i)? == Value::Null,
+            Condition::Like(i, pattern) => match column_value(record, *i)? {
+                Value::String(text) => like_match(pattern, &text),
+                _ => false,
+            },
+            // without NOT, a null condition can be treated as false
+            Condition::And(left, right) => left.matches(record)? && right.matches(record)?,
+            Condition::Or(left, right) => left.matches(record)? || right.matches(record)?,
+        };
+        Ok(matches)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SortKey {
+    index: ColumnIndex,
+    descending: bool,
+}
+
+impl SortKey {
+    fn resolve(scope: &Scope, order_by: &OrderBy) -> anyhow::Result<SortKey> {
+        Ok(SortKey {
+            index: scope.resolve(&order_by.column)?,
+            descending: order_by.descending,
+        })
+    }
+}
+
+// how the rows of the joined table matching a row of the first one are found
+#[derive(Debug, Clone, PartialEq)]
+enum JoinLookup {
+    // the joined column is the table's rowid or its INTEGER PRIMARY KEY
+    RowId,
+    Index(Index),
+    // every row of the table is read for each row of the first one
+    Scan,
+}
+
+// an inner join on the equality of a column of each table
+#[derive(Debug, Clone, PartialEq)]
+struct JoinPlan {
+    table: Table,
+    // the column in the records of the first table
+    outer_column: ColumnIndex,
+    // the column in the records of the joined table
+    inner_column: ColumnIndex,
+    lookup: JoinLookup,
+}
+
+impl JoinPlan {
+    fn new<P: Pager>(db: &mut Db<P>, outer: &Table, join: &Join) -> anyhow::Result<JoinPlan> {
+        let table = db.table(&join.table)?;
+        // both sides would need an alias to be told apart
+        if table.name.eq_ignore_ascii_case(&outer.name) {
+            anyhow::bail!("unsupported query: a table can't be joined with itself");
+        }
+        let scope = Scope::new(vec![outer, &table]);
+        let (outer_column, inner_column) =
+            match (scope.locate(&join.on.0)?, scope.locate(&join.on.1)?) {
+                ((0, outer_column), (1, inner_column)) | ((1, inner_column), (0, outer_column)) => {
+                    (outer_column, inner_column)
+                }
+                _ => anyhow::bail!("unsupported query: ON must compare a column of each table"),
+            };
+
+        let lookup = match inner_column {
+            ColumnIndex::RowId(_) => JoinLookup::RowId,
+            ColumnIndex::Field(i) if table.rowid_alias() == Some(i) => JoinLookup::RowId,
+            ColumnIndex::Field(i) => {
+                // like for the filter, an index whose schema can't be read is simply ignored
+                let column = &table.columns[i];
+                db.indexes()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|_| !table.without_rowid)
+                    .find(|i| i.table.eq_ignore_ascii_case(&table.name) && i.can_seek(column))
+                    .map_or(JoinLookup::Scan, JoinLookup::Index)
+            }
+        };
+
+        Ok(JoinPlan {
+            table,
+            outer_column,
+            inner_column,
+            lookup,
+        })
+    }
+
+    // the records of the joined table, in rowid order, whose column equals the one of the record
+    fn matches<P: Pager>(&self, db: &mut Db<P>, record: &Cursor) -> anyhow::Result<Vec<Cursor>> {
+        // nulls are never equal, keys are compared like a literal would be
+        let key = match column_value(record, self.outer_column)? {
+            Value::Null => return Ok(Vec::new()),
+            key => {
+                let affinity = match self.inner_column {
+                    ColumnIndex::Field(i) => self.table.columns[i].affinity,
+                    ColumnIndex::RowId(_) => Affinity::Integer,
+                };
+                coerce_literal(affinity, &key.into_owned())
+            }
+        };
+        let mut records = Vec::new();
+        match &self.lookup {
+            JoinLookup::RowId => {
+                if let Value::Int(row_id) = key {
+                    records.extend(db.seek_row(&self.table, row_id)?);
+                }
+            }
+            JoinLookup::Index(index) => {
+                let mut row_ids = db.index_lookup(index, &key)?;
+                row_ids.sort_unstable();
+                for row_id in row_ids {
+                    records.extend(db.seek_row(&self.table, row_id)?);
+                }
+            }
+            JoinLookup::Scan => {
+                let mut scanner = db.scanner_for(&self.table.name)?;
+                while let Some(joined) = scanner.next_record()? {
+                    let value = column_value(&joined, self.inner_column)?;
+                    if value != Value::Null && value.cmp_sqlite(&key) == Ordering::Equal {
+                        records.push(joined);
+                    }
+                }
+            }
+        }
+        Ok(records)
+    }
+}
+
+// where the records of a plan come from
+enum Rows<'d, P: Pager> {
+    Scan(Scanner<'d>),
+    // row ids found in an index, each one looked up in the table b-tree
+    Lookup(&'d mut Db<P>, std::vec::IntoIter<i64>),
+    Join(Box<JoinedRows<'d, P>>),
+}
+
+// the records of the first table, each one joined with the matching records of the other
+struct JoinedRows<'d, P: Pager> {
+    db: &'d mut Db<P>,
+    join: JoinPlan,
+    records: std::vec::IntoIter<Cursor>,
+    current: Option<Cursor>,
+    matches: std::vec::IntoIter<Cursor>,
+}
+
+impl<P: Pager> Rows<'_, P> {
+    fn next_record(&mut self, table: &Table) -> anyhow::Result<Option<Cursor>> {
+        match self {
+            Rows::Scan(scanner) => scanner.next_record(),
+            Rows::Lookup(db, row_ids) => {
+                for row_id in row_ids.by_ref() {
+                    if let Some(record) = db.seek_row(table, row_id)? {
+                        return Ok(Some(record));
+                    }
+                }
+                Ok(None)
+            }
+            Rows::Join(rows) => loop {
+                if let (Some(record), Some(joined)) = (&rows.current, rows.matches.next()) {
+                    let width = table.columns.len();
+                    return Ok(Some(record.clone().with_joined(width, joined)));
+                }
+                let Some(record) = rows.records.next() else {
+                    return Ok(None);
+                };
+                rows.matches = rows.join.matches(rows.db, &record)?.into_iter();
+                rows.current = Some(record);
+            },
+        }
+    }
+}
+
+// a select resolved against the schema, ready to be run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub table: Table,
+    join: Option<JoinPlan>,
+    // used to find the rows matching the filter instead of scanning the whole table
+    pub index: Option<Index>,
+    // the value looked up in the index
+    seek: Option<Value<'static>>,
+    pub column_names: Vec<String>,
+    projection: Vec<ColumnIndex>,
+    // set when the result columns are aggregates, the records are then folded into one row per
+    // group instead of being projected
+    aggregation: Option<Aggregation>,
+    filter: Option<Condition>,
+    sort_key: Option<SortKey>,
+    limit: Option<usize>,
+    offset: usize,
+}
+
+impl Plan {
+    pub fn new<P: Pager>(db: &mut Db<P>, select: &Select) -> anyhow::Result<Plan> {
+        let table = db.table(&select.table)?;
+        let join = select
+            .join
+            .as_ref()
+            .map(|j| JoinPlan::new(db, &table, j))
+            .transpose()?;
+        let scope = Scope::new(
+            std::iter::once(&table)
+                .chain(join.as_ref().map(|j| &j.table))
+                .collect(),
+        );
+
+        let group_by = select
+            .group_by
+            .as_ref()
+            .map(|c| scope.resolve(c))
+            .transpose()?;
+
+        let mut column_names = Vec::new();
+        let mut projection = Vec::new();
+        let mut aggregate_columns = Vec::new();
+        for column in &select.columns {
+            let (column, alias) = match column {
+                ResultColumn::Aliased(column, alias) => (column.as_ref(), Some(alias)),
+                column => (column, None),
+            };
+            match column {
+                ResultColumn::Star => {
+                    column_names.extend(scope.columns.iter().map(|c| c.name.clone()));
+                    projection.extend((0..scope.columns.len()).map(ColumnIndex::Field));
+                }
+                ResultColumn::Column(column) => {
+                    let index = scope.resolve(column)?;
+                    column_names.push(scope.name(index).to_string());
+                    projection.push(index);
+                    if group_by == Some(index) {
+                        aggregate_columns.push(AggregateColumn::Group);
+                    }
+                }
+                ResultColumn::Aggregate(aggregate) => {
+                    let (name, column) = resolve_aggregate(&scope, aggregate)?;
+                    column_names.push(name);
+                    aggregate_columns.push(column);
+                }
+                ResultColumn::Aliased(..) => anyhow::bail!("unsupported query: nested alias"),
+            }
+            if let (Some(alias), Some(name)) = (alias, column_names.last_mut()) {
+                *name = alias.clone();
+            }
+        }
+        let aggregate = group_by.is_some() || select.columns.iter().any(ResultColumn::is_aggregate);
+        if aggregate && aggregate_columns.len() != column_names.len() {
+            anyhow::bail!(
+                "unsupported query: only the GROUP BY column can be selected along with aggregates"
+            );
+        }
+
+        let filter = select
+            .filter
+            .as_ref()
+            .map(|p| Condition::resolve(&scope, p))
+            .transpose()?;
+        // any equality the filter can't be true without can be looked up in an index, the rows
+        // found are still checked against the whole filter
+        let equalities = match &select.filter {
+            Some(predicate) if !table.without_rowid => predicate.required_equalities(),
+            _ => Vec::new(),
+        };
+        let (index, seek) = if equalities.is_empty() {
+            (None, None)
+        } else {
+            // indexes only speed things up, one whose schema can't be read is simply ignored
+            let indexes = db.indexes().unwrap_or_default();
+            equalities
+                .into_iter()
+                .filter(|(_, value)| **value != Value::Null)
+                .find_map(|(column, value)| {
+                    // only the first table's rows are looked up
+                    let (_, ColumnIndex::Field(column)) =
+                        scope.locate(column).ok().filter(|&(t, _)| t == 0)?
+                    else {
+                        return None;
+                    };
+                    let column = table.columns.get(column)?;
+                    let index = indexes.iter().find(|i| {
+                        i.table.eq_ignore_ascii_case(&table.name) && i.can_seek(column)
+                    })?;
+                    // index keys were stored with the column's affinity applied
+                    let value = coerce_literal(column.affinity, value);
+                    Some((index.clone(), value))
+                })
+                .unzip()
+        };
+        let sort_key = select
+            .order_by
+            .as_ref()
+            .map(|o| SortKey::resolve(&scope, o))
+            .transpose()?;
+        let aggregation = if aggregate {
+            // groups can only be ordered by their key, there is nothing to order without groups
+            let descending = match (group_by, sort_key) {
+                (Some(group), Some(sort_key)) if group != sort_key.index => anyhow::bail!(
+                    "unsupported query: aggregates can only be ordered by the GROUP BY column"
+                ),
+                (_, Some(sort_key)) => sort_key.descending,
+                (_, None) => false,
+            };
+            Some(Aggregation {
+                group_by,
+                columns: aggregate_columns,
+                descending,
+            })
+        } else {
+            None
+        };
+
+        Ok(Plan {
+            table,
+            join,
+            index,
+            seek,
+            column_names,
+            projection,
+            aggregation,
+            filter,
+            sort_key,
+            limit: select.limit,
+            offset: select.offset,
+        })
+    }
+
+    // rows are streamed in rowid order unless there is an ORDER BY, in which case every matching
+    // record is buffered in memory to be sorted before the first row is emitted
+    pub fn execute<P: Pager>(
+        &self,
+        db: &mut Db<P>,
+        mut on_row: impl FnMut(&[Value]) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        for row in self.clone().into_rows(db)? {
+            on_row(&row?)?;
+        }
+        Ok(())
+    }
+
+    pub fn into_rows<P: Pager>(self, db: &mut Db<P>) -> anyhow::Result<RowStream<'_, P>> {
+        let rows = match &self.join {
+            None => self.table_rows(db)?,
+            // the records of the first table are read upfront, the db is then free to look up
+            // the matching ones of the joined table
+            Some(join) => {
+                let mut table_rows = self.table_rows(db)?;
+                let mut records = Vec::new();
+                while let Some(record) = table_rows.next_record(&self.table)? {
+                    records.push(record);
+                }
+                Rows::Join(Box::new(JoinedRows {
+                    db,
+                    join: join.clone(),
+                    records: records.into_iter(),
+                    current: None,
+                    matches: Vec::new().into_iter(),
+                }))
+            }
+        };
+
+        Ok(RowStream {
+            plan: self,
+            rows,
+            sorted: None,
+            aggregated: None,
+            skipped: 0,
+            emitted: 0,
+            done: false,
+        })
+    }
+
+    // the records of the first table, before any join
+    fn table_rows<'d, P: Pager>(&self, db: &'d mut Db<P>) -> anyhow::Result<Rows<'d, P>> {
+        let rows = match (&self.index, &self.seek) {
+            (Some(index), Some(value)) => {
+                let mut row_ids = db.index_lookup(index, value)?;
+                // rows still come out in rowid order, entries of a multi-column index are also
+                // sorted by their other columns
+                row_ids.sort_unstable();
+                Rows::Lookup(db, row_ids.into_iter())
+            }
+            _ => Rows::Scan(db.scanner_for(&self.table.name)?),
+        };
+        Ok(rows)
+    }
+
+    fn next_match<P: Pager>(&self, rows: &mut Rows<P>) -> anyhow::Result<Option<Cursor>> {
+        while let Some(record) = rows.next_record(&self.table)? {
+            match &self.filter {
+                Some(filter) if !filter.matches(&record)? => continue,
+                _ => return Ok(Some(record)),
+            }
+        }
+        Ok(None)
+    }
+
+    fn project<'r>(&self, record: &'r Cursor) -> anyhow::Result<Vec<Value<'r>>> {
+        self.projection
+            .iter()
+            .map(|&i| column_value(record, i))
+            .collect()
+    }
+
+    // returns the indices of the records in sorted order, the sort is stable so ties stay in
+    // rowid order, nulls come first in ascending order like in sqlite
+    fn sort(records: &[Cursor], sort_key: SortKey) -> anyhow::Result<Vec<usize>> {
+        let keys = records
+            .iter()
+            .map(|r| column_value(r, sort_key.index))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let mut order = (0..records.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            let ordering = keys[a].cmp_sqlite(&keys[b]);
+            if sort_key.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        Ok(order)
+    }
+}
+
+// the rows of a plan, read lazily from the table or index unless the plan has an ORDER BY or
+// aggregates
+pub struct RowStream<'d, P: Pager> {
+    plan: Plan,
+    rows: Rows<'d, P>,
+    // with an ORDER BY, every match along with the order in which to emit them, filled on the
+    // first call to next
+    sorted: Option<(Vec<Option<Cursor>>, std::vec::IntoIter<usize>)>,
+    // the rows of an aggregate plan, computed on the first call to next
+    aggregated: Option<std::vec::IntoIter<Vec<Value<'static>>>>,
+    skipped: usize,
+    emitted: usize,
+    // set once exhausted or after an error
+    done: bool,
+}
+
+impl<P: Pager> RowStream<'_, P> {
+    pub fn columns(&self) -> &[String] {
+        &self.plan.column_names
+    }
+
+    fn next_record(&mut self) -> anyhow::Result<Option<Cursor>> {
+        let Some(sort_key) = self.plan.sort_key else {
+            return self.plan.next_match(&mut self.rows);
+        };
+        if self.sorted.is_none() {
+            let mut records = Vec::new();
+            while let Some(record) = self.plan.next_match(&mut self.rows)? {
+                records.push(record);
+            }
+            let order = Plan::sort(&records, sort_key)?;
+            self.sorted = Some((records.into_iter().map(Some).collect(), order.into_iter()));
+        }
+        Ok(self
+            .sorted
+            .as_mut()
+            .and_then(|(records, order)| order.next().and_then(|i| records[i].take())))
+    }
+
+    // every matching record is read before the first row is emitted
+    fn next_aggregate(&mut self) -> anyhow::Result<Option<Vec<Value<'static>>>> {
+        if self.aggregated.is_none() {
+            let (plan, rows) = (&self.plan, &mut self.rows);
+            let aggregated = match &plan.aggregation {
+                Some(aggregation) => aggregation.run(|| plan.next_match(rows))?,
+                None => Vec::new(),
+            };
+            self.aggregated = Some(aggregated.into_iter());
+        }
+        Ok(self.aggregated.as_mut().and_then(Iterator::next))
+    }
+
+    fn next_row(&mut self) -> anyhow::Result<Option<Vec<Value<'static>>>> {
+        while self.emitted < self.plan.limit.unwrap_or(usize::MAX) {
+            // records are only projected once past the offset
+            let row = if self.plan.aggregation.is_some() {
+                let Some(row) = self.next_aggregate()? else {
+                    break;
+                };
+                if self.skip() {
+                    continue;
+                }
+                row
+            } else {
+                let Some(record) = self.next_record()? else {
+                    break;
+                };
+                if self.skip() {
+                    continue;
+                }
+                let row = self.plan.project(&record)?;
+                row.into_iter().map(Value::into_owned).collect()
+            };
+            self.emitted += 1;
+            return Ok(Some(row));
+        }
+        Ok(None)
+    }
+
+    fn skip(&mut self) -> bool {
+        let skip = self.skipped < self.plan.offset;
+        if skip {
+            self.skipped += 1;
+        }
+        skip
+    }
+}
+
+impl<P: Pager> Iterator for RowStream<'_, P> {
+    type Item = anyhow::Result<Vec<Value<'static>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let row = self.next_row().transpose();
+        self.done = !matches!(row, Some(Ok(_)));
+        row
+    }
+}
+
+// like sqlite, a literal compared to a column of numeric affinity is turned into a number when it
+// looks like one and one compared to a text column is turned into text
+fn coerce_literal(affinity: Affinity, value: &Value<'static>) -> Value<'static> {
+    let affinity = match affinity {
+        Affinity::Integer | Affinity::Real | Affinity::Numeric => Affinity::Numeric,
+        affinity => affinity,
+    };
+    value.clone().affinity_coerce(affinity)
+}
+
+// the name of the result column along with what it computes
+fn resolve_aggregate(
+    scope: &Scope,
+    aggregate: &Aggregate,
+) -> anyhow::Result<(String, AggregateColumn)> {
+    let name = |i: ColumnIndex| scope.name(i);
+    let resolved = match aggregate {
+        Aggregate::CountRows => ("COUNT(*)".to_string(), AggregateColumn::CountRows),
+        Aggregate::CountDistinct(column) => {
+            let i = scope.resolve(column)?;
+            (
+                format!("COUNT(DISTINCT {})", name(i)),
+                AggregateColumn::CountDistinct(i),
+            )
+        }
+        Aggregate::Sum(column) => {
+            let i = scope.resolve(column)?;
+            (format!("SUM({})", name(i)), AggregateColumn::Sum(i))
+        }
+        Aggregate::Min(column) => {
+            let i = scope.resolve(column)?;
+            (format!("MIN({})", name(i)), AggregateColumn::Min(i))
+        }
+        Aggregate::Max(column) => {
+            let i = scope.resolve(column)?;
+            (format!("MAX({})", name(i)), AggregateColumn::Max(i))
+        }
+        Aggregate::Avg(column) => {
+            let i = scope.resolve(column)?;
+            (format!("AVG({})", name(i)), AggregateColumn::Avg(i))
+        }
+    };
+    Ok(resolved)
+}
+
+// records written before an ALTER TABLE ADD COLUMN can be shorter than the schema
+pub(super) fn column_value(record: &Cursor, column: ColumnIndex) -> anyhow::Result<Value<'_>> {
+    match (column, &record.joined) {
+        (ColumnIndex::Field(i), _) => record.field(i).map(|v| v.unwrap_or(Value::Null)),
+        (ColumnIndex::RowId(0), _) => Ok(Value::Int(record.row_id)),
+        (ColumnIndex::RowId(1), Some((_, joined))) => Ok(Value::Int(joined.row_id)),
+        (ColumnIndex::RowId(t), _) => anyhow::bail!("no table {} in the record", t),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::query::parser::parse_select;
+
+    use super::*;
+
+    fn run(db: &mut Db, sql: &str) -> anyhow::Result<(Vec<String>, Vec<Vec<String>>)> {
+        let plan = Plan::new(db, &parse_select(sql)?)?;
+        let mut rows = Vec::new();
+        plan.execute(db, |row| {
+            rows.push(row.iter().map(|v| v.to_string()).collect());
+            Ok(())
+        })?;
+        Ok((plan.column_names, rows))
+    }
+
+    #[test]
+    fn execute_select_tests() {
+        let mut db = Db::from_file("test.db").unwrap();
+        assert_eq!(
+            (
+                vec!["two".to_string(), "one".to_string()],
+                vec![
+                    vec!["10".to_string(), "hello!".to_string()],
+                    vec!["20".to_string(), "goodbye".to_string()]
+                ]
+            ),
+            run(&mut db, "SELECT two, one FROM tbl1").unwrap()
+        );
+        let (columns, rows) = run(&mut db, "SELECT * FROM tbl1").unwrap();
+        assert_eq!(vec!["one", "two"], columns);
+        assert_eq!(vec!["hello!", "10"], rows[0]);
+        assert!(run(&mut db, "SELECT three FROM tbl1").is_err());
+        assert!(run(&mut db, "SELECT one FROM tbl2").is_err());
+
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        let (_, rows) = run(&mut db, "SELECT id, age FROM people").unwrap();
+        assert_eq!(
+            vec![
+                vec!["1".to_string(), "30".to_string()],
+                vec!["5".to_string(), "".to_string()],
+                vec!["42".to_string(), "25".to_string()]
+            ],
+            rows
+        );
+    }
+
+    #[test]
+    fn execute_alias_tests() {
+        let mut db = Db::from_file("test.db").unwrap();
+        let (columns, rows) =
+            run(&mut db, "SELECT one AS label, two, one AS again FROM tbl1").unwrap();
+        assert_eq!(vec!["label", "two", "again"], columns);
+        assert_eq!(vec!["hello!", "10", "hello!"], rows[0]);
+        // an alias doesn't rename the column for the rest of the query
+        assert!(run(
+            &mut db,
+            "SELECT one AS label FROM tbl1 WHERE label = 'hello!'"
+        )
+        .is_err());
+
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let (columns, rows) = run(
+            &mut db,
+            "SELECT age AS years, COUNT(*) AS people FROM people GROUP BY age LIMIT 1",
+        )
+        .unwrap();
+        assert_eq!(vec!["years", "people"], columns);
+        assert_eq!(vec![vec!["0".to_string(), "10".to_string()]], rows);
+    }
+
+    #[test]
+    fn execute_where_tests() {
+        let rows = |db: &mut Db, sql: &str| run(db, sql).unwrap().1;
+        let mut db = Db::from_file("test.db").unwrap();
+        assert_eq!(
+            vec![vec!["goodbye".to_string()]],
+            rows(&mut db, "SELECT one FROM tbl1 WHERE two = 20")
+        );
+        assert_eq!(
+            vec![vec!["10".to_string()]],
+            rows(&mut db, "SELECT two FROM tbl1 WHERE one = 'hello!'")
+        );
+        assert_eq!(
+            vec![vec!["10".to_string()]],
+            rows(&mut db, "SELECT two FROM tbl1 WHERE two = 10.0")
+        );
+        assert!(rows(&mut db, "SELECT two FROM tbl1 WHERE two = 11").is_empty());
+        // the int column turns the text into a number, see execute_affinity_tests
+        assert!(rows(&mut db, "SELECT two FROM tbl1 WHERE two = '11'").is_empty());
+        assert!(run(&mut db, "SELECT two FROM tbl1 WHERE three = 1").is_err());
+
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        assert_eq!(
+            vec![vec!["bob".to_string()]],
+            rows(&mut db, "SELECT name FROM people WHERE age IS NULL")
+        );
+        assert_eq!(
+            vec![vec!["carol".to_string()]],
+            rows(&mut db, "SELECT name FROM people WHERE id = 42")
+        );
+        assert!(rows(&mut db, "SELECT name FROM people WHERE age = NULL").is_empty());
+    }
+
+    #[test]
+    fn execute_comparison_tests() {
+        let ids = |db: &mut Db, sql: &str| {
+            run(db, sql)
+                .unwrap()
+                .1
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect::<Vec<_>>()
+        };
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        assert_eq!(
+            vec!["42"],
+            ids(&mut db, "SELECT id FROM people WHERE age < 30")
+        );
+        assert_eq!(
+            vec!["1", "42"],
+            ids(&mut db, "SELECT id FROM people WHERE age <= 30")
+        );
+        assert_eq!(
+            vec!["1"],
+            ids(&mut db, "SELECT id FROM people WHERE age > 25.5")
+        );
+        assert_eq!(
+            vec!["1", "42"],
+            ids(&mut db, "SELECT id FROM people WHERE age >= 25")
+        );
+        // nulls never compare
+        assert_eq!(
+            vec!["1"],
+            ids(&mut db, "SELECT id FROM people WHERE age <> 25")
+        );
+        assert!(ids(&mut db, "SELECT id FROM people WHERE age <> NULL").is_empty());
+        // numbers sort before text
+        assert_eq!(
+            vec!["1", "42"],
+            ids(&mut db, "SELECT id FROM people WHERE age < 'a'")
+        );
+        assert_eq!(
+            vec!["5", "42"],
+            ids(&mut db, "SELECT id FROM people WHERE name > 'alice'")
+        );
+
+        assert_eq!(
+            vec!["1", "42"],
+            ids(&mut db, "SELECT id FROM people WHERE age BETWEEN 25 AND 30")
+        );
+        assert_eq!(
+            vec!["42"],
+            ids(
+                &mut db,
+                "SELECT id FROM people WHERE age BETWEEN 20 AND 29.9"
+            )
+        );
+        assert!(ids(&mut db, "SELECT id FROM people WHERE age BETWEEN 30 AND 25").is_empty());
+        assert!(ids(
+            &mut db,
+            "SELECT id FROM people WHERE age BETWEEN NULL AND 30"
+        )
+        .is_empty());
+
+        let mut db = Db::from_file("test_index.db").unwrap();
+        assert_eq!(
+            vec!["400"],
+            ids(&mut db, "SELECT COUNT(*) FROM people WHERE id > 100")
+        );
+        assert_eq!(
+            vec!["101", "102"],
+            ids(&mut db, "SELECT id FROM people WHERE id > 100 LIMIT 2")
+        );
+    }
+
+    #[test]
+    fn execute_affinity_tests() {
+        let ids = |db: &mut Db, sql: &str| {
+            run(db, sql)
+                .unwrap()
+                .1
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect::<Vec<_>>()
+        };
+        // text literals compared to int columns are turned into numbers
+        let mut db = Db::from_file("test.db").unwrap();
+        assert_eq!(
+            vec!["hello!"],
+            ids(&mut db, "SELECT one FROM tbl1 WHERE two = '10'")
+        );
+        assert_eq!(
+            vec!["goodbye"],
+            ids(&mut db, "SELECT one FROM tbl1 WHERE two > '15'")
+        );
+        assert_eq!(
+            vec!["hello!", "goodbye"],
+            ids(
+                &mut db,
+                "SELECT one FROM tbl1 WHERE two BETWEEN '5' AND '2e1'"
+            )
+        );
+        assert!(ids(&mut db, "SELECT one FROM tbl1 WHERE two = 'ten'").is_empty());
+        assert!(ids(&mut db, "SELECT one FROM tbl1 WHERE two = '10a'").is_empty());
+
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        assert_eq!(
+            vec!["1"],
+            ids(&mut db, "SELECT id FROM people WHERE age = ' 30 '")
+        );
+        assert_eq!(
+            vec!["5"],
+            ids(&mut db, "SELECT id FROM people WHERE id = '5'")
+        );
+
+        // and looked up in indexes as numbers
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let plan = Plan::new(
+            &mut db,
+            &parse_select("SELECT id FROM people WHERE age = '10'").unwrap(),
+        )
+        .unwrap();
+        assert!(plan.index.is_some());
+        assert_eq!(
+            vec!["10"],
+            ids(&mut db, "SELECT COUNT(*) FROM people WHERE age = '10'")
+        );
+    }
+
+    #[test]
+    fn expression_index_tests() {
+        // the index on lower(a) doesn't keep the one on b from being used
+        let mut db = Db::from_file("test_expr_index.db").unwrap();
+        let plan = Plan::new(
+            &mut db,
+            &parse_select("SELECT a FROM t WHERE b = 2").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(Some("i_b"), plan.index.as_ref().map(|i| i.name.as_str()));
+        assert_eq!(
+            vec![vec!["Two".to_string()], vec!["FOUR".to_string()]],
+            run(&mut db, "SELECT a FROM t WHERE b = 2").unwrap().1
+        );
+        assert_eq!(
+            vec![vec!["three".to_string()]],
+            run(&mut db, "SELECT a FROM t WHERE a = 'three'").unwrap().1
+        );
+    }
+
+    #[test]
+    fn execute_like_tests() {
+        let ids = |db: &mut Db, sql: &str| {
+            run(db, sql)
+                .unwrap()
+                .1
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect::<Vec<_>>()
+        };
+        let mut db = Db::from_file("test_index.db").unwrap();
+        assert_eq!(
+            vec!["10"],
+            ids(
+                &mut db,
+                "SELECT COUNT(*) FROM people WHERE name LIKE 'NAME04%'"
+            )
+        );
+        assert_eq!(
+            vec!["1", "2", "3"],
+            ids(
+                &mut db,
+                "SELECT id FROM people WHERE name LIKE 'name_0_' LIMIT 3"
+            )
+        );
+        assert_eq!(
+            vec!["500"],
+            ids(
+                &mut db,
+                "SELECT COUNT(*) FROM people WHERE email LIKE '%@example.com'"
+            )
+        );
+        // never true for numbers
+        assert!(ids(&mut db, "SELECT id FROM people WHERE age LIKE '1'").is_empty());
+
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        assert_eq!(
+            vec!["bob", "carol"],
+            ids(&mut db, "SELECT name FROM people WHERE name LIKE '%o%'")
+        );
+    }
+
+    #[test]
+    fn execute_and_or_tests() {
+        let ids = |db: &mut Db, sql: &str| {
+            run(db, sql)
+                .unwrap()
+                .1
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect::<Vec<_>>()
+        };
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        assert_eq!(
+            vec!["1", "5"],
+            ids(
+                &mut db,
+                "SELECT id FROM people WHERE age = 30 OR name = 'bob'"
+            )
+        );
+        // AND binds tighter than OR
+        assert_eq!(
+            vec!["1", "5"],
+            ids(
+                &mut db,
+                "SELECT id FROM people WHERE id = 1 OR id = 5 AND age IS NULL"
+            )
+        );
+        assert_eq!(
+            vec!["5"],
+            ids(
+                &mut db,
+                "SELECT id FROM people WHERE (id = 1 OR id = 5) AND age IS NULL"
+            )
+        );
+        assert!(ids(&mut db, "SELECT id FROM people WHERE age > 20 AND age < 25").is_empty());
+
+        let mut db = Db::from_file("test_index.db").unwrap();
+        assert_eq!(
+            vec!["12"],
+            ids(
+                &mut db,
+                "SELECT COUNT(*) FROM people WHERE age = 10 OR age = 11 AND id > 400"
+            )
+        );
+        assert_eq!(
+            vec!["4"],
+            ids(
+                &mut db,
+                "SELECT COUNT(*) FROM people WHERE (age = 10 OR age = 11) AND id > 400"
+            )
+        );
+        // the rows found through the index are checked against the rest of the filter
+        assert_eq!(
+            vec!["11", "61", "461"],
+            ids(
+                &mut db,
+                "SELECT id FROM people WHERE age = 10 AND (id < 100 OR name = 'name460')"
+            )
+        );
+    }
+
+    #[test]
+    fn execute_index_lookup_tests() {
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let plan = |db: &mut Db, sql: &str| Plan::new(db, &parse_select(sql).unwrap()).unwrap();
+
+        assert_eq!(
+            Some("idx_people_age"),
+            plan(&mut db, "SELECT name FROM people WHERE age = 10")
+                .index
+                .as_ref()
+                .map(|i| i.name.as_str())
+        );
+        assert_eq!(
+            Some("idx_people_age"),
+            plan(
+                &mut db,
+                "SELECT name FROM people WHERE name = 'name010' AND age = 10"
+            )
+            .index
+            .as_ref()
+            .map(|i| i.name.as_str())
+        );
+        // NOCASE index, index without sql, no equality, null, an equality under OR and no filter
+        for sql in [
+            "SELECT name FROM people WHERE name = 'name042'",
+            "SELECT name FROM people WHERE email = 'p1@example.com'",
+            "SELECT name FROM people WHERE age IS NULL",
+            "SELECT name FROM people WHERE age = NULL",
+            "SELECT name FROM people WHERE age = 10 OR id = 1",
+            "SELECT name FROM people",
+        ] {
+            assert_eq!(None, plan(&mut db, sql).index, "{}", sql);
+        }
+
+        let (_, rows) = run(&mut db, "SELECT id, name FROM people WHERE age = 10").unwrap();
+        assert_eq!(
+            (0..10)
+                .map(|i| vec![(11 + 50 * i).to_string(), format!("name{:03}", 10 + 50 * i)])
+                .collect::<Vec<_>>(),
+            rows
+        );
+        let (_, rows) = run(
+            &mut db,
+            "SELECT id FROM people WHERE age = 49 ORDER BY id DESC LIMIT 2 OFFSET 1",
+        )
+        .unwrap();
+        assert_eq!(vec![vec!["450".to_string()], vec!["400".to_string()]], rows);
+        assert!(run(&mut db, "SELECT id FROM people WHERE age = 50")
+            .unwrap()
+            .1
+            .is_empty());
+        assert_eq!(
+            vec![vec!["name042".to_string()]],
+            run(&mut db, "SELECT name FROM people WHERE name = 'name042'")
+                .unwrap()
+                .1
+        );
+    }
+
+    #[test]
+    fn scope_tests() {
+        use ColumnIndex::{Field, RowId};
+
+        let table = |name: &str, sql: &str| {
+            let table_def = crate::schema::parse_create_table(sql).unwrap();
+            Table {
+                name: name.to_string(),
+                root_page: 0,
+                columns: table_def.columns,
+                primary_key: table_def.primary_key,
+                without_rowid: table_def.without_rowid,
+                sql: sql.to_string(),
+            }
+        };
+        let qualified = |table: &str, name: &str| ColumnRef {
+            table: Some(table.to_string()),
+            name: name.to_string(),
+        };
+        let a = table("a", "CREATE TABLE a(id, x)");
+        let b = table("B", "CREATE TABLE b(id, a_id, y)");
+        let scope = Scope::new(vec![&a, &b]);
+        assert_eq!((0, Field(1)), scope.locate(&"X".into()).unwrap());
+        assert_eq!((1, Field(2)), scope.locate(&qualified("b", "y")).unwrap());
+        assert_eq!((1, Field(0)), scope.locate(&qualified("b", "id")).unwrap());
+        assert_eq!(Field(2), scope.resolve(&qualified("B", "ID")).unwrap());
+        assert_eq!(Field(4), scope.resolve(&"y".into()).unwrap());
+        assert_eq!(
+            "ambiguous column name: id",
+            scope.locate(&"id".into()).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "no such column: a.y",
+            scope.locate(&qualified("a", "y")).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "no such column: c.id",
+            scope.locate(&qualified("c", "id")).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "no such column: z",
+            scope.locate(&"z".into()).unwrap_err().to_string()
+        );
+
+        let scope = Scope::new(vec![&a]);
+        assert_eq!(Field(0), scope.resolve(&"id".into()).unwrap());
+        assert_eq!(Field(1), scope.resolve(&qualified("a", "x")).unwrap());
+
+        // a column declared with the name of one of the rowid aliases shadows it, even in
+        // another table
+        let c = table("c", "CREATE TABLE c(oid, x)");
+        let scope = Scope::new(vec![&a, &c]);
+        assert_eq!(Field(2), scope.resolve(&"OID".into()).unwrap());
+        assert_eq!(RowId(0), scope.resolve(&qualified("a", "oid")).unwrap());
+        assert_eq!(RowId(1), scope.resolve(&qualified("c", "_rowid_")).unwrap());
+        assert_eq!("rowid", scope.name(RowId(1)));
+        assert_eq!(
+            "ambiguous column name: rowid",
+            scope.locate(&"rowid".into()).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn execute_rowid_tests() {
+        let mut db = Db::from_file("test_join.db").unwrap();
+        let (columns, rows) = run(
+            &mut db,
+            "SELECT rowid, oid, title FROM reviews WHERE _rowid_ >= 2 ORDER BY ROWID DESC",
+        )
+        .unwrap();
+        assert_eq!(vec!["rowid", "rowid", "title"], columns);
+        assert_eq!(
+            vec![
+                vec!["4", "4", "missing"],
+                vec!["3", "3", "dune"],
+                vec!["2", "2", "the hobbit"],
+            ],
+            rows
+        );
+        assert_eq!(
+            vec![vec!["2".to_string()]],
+            run(
+                &mut db,
+                "SELECT COUNT(*) FROM reviews WHERE rowid BETWEEN '2' AND 3"
+            )
+            .unwrap()
+            .1
+        );
+
+        // the rowid of either table can be joined on or selected
+        let (_, rows) = run(
+            &mut db,
+            "SELECT books.oid, reviews.rowid, books.title FROM reviews JOIN books ON books.rowid = reviews.rowid",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                vec!["1", "1", "the hobbit"],
+                vec!["2", "2", "dune"],
+                vec!["3", "3", "the silmarillion"],
+                vec!["4", "4", "anonymous"],
+            ],
+            rows
+        );
+        assert_eq!(
+            "ambiguous column name: rowid",
+            run(
+                &mut db,
+                "SELECT rowid FROM books JOIN authors ON authors.id = books.author_id"
+            )
+            .unwrap_err()
+            .to_string()
+        );
+
+        // the INTEGER PRIMARY KEY is the rowid
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        let (columns, rows) = run(&mut db, "SELECT rowid, name FROM people LIMIT 1").unwrap();
+        assert_eq!(vec!["id", "name"], columns);
+        assert_eq!("1", rows[0][0]);
+
+        let mut db = Db::from_file("test_without_rowid.db").unwrap();
+        assert_eq!(
+            "no such column: rowid",
+            run(&mut db, "SELECT rowid FROM words")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn execute_qualified_column_tests() {
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        assert_eq!(
+            (vec!["name".to_string()], vec![vec!["bob".to_string()]]),
+            run(
+                &mut db,
+                "SELECT people.name FROM people WHERE People.age IS NULL ORDER BY people.id"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            "no such column: other.name",
+            run(&mut db, "SELECT other.name FROM people")
+                .unwrap_err()
+                .to_string()
+        );
+        assert!(run(&mut db, "SELECT COUNT(DISTINCT people.age) FROM people").is_ok());
+    }
+
+    #[test]
+    fn execute_join_tests() {
+        let mut db = Db::from_file("test_join.db").unwrap();
+        let rows = |db: &mut Db, sql: &str| run(db, sql).unwrap().1;
+        let pairs = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(a, b)| vec![a.to_string(), b.to_string()])
+                .collect::<Vec<_>>()
+        };
+
+        // books.author_id is indexed, NULL keys match nothing and '3' was stored as 3
+        let (columns, joined) = run(
+            &mut db,
+            "SELECT authors.name, books.title FROM authors JOIN books ON authors.id = books.author_id",
+        )
+        .unwrap();
+        assert_eq!(vec!["name", "title"], columns);
+        assert_eq!(
+            pairs(&[
+                ("tolkien", "the hobbit"),
+                ("tolkien", "the silmarillion"),
+                ("herbert", "dune"),
+                ("le guin", "earthsea")
+            ]),
+            joined
+        );
+        // authors.id is the rowid, the ON sides can be written in either order
+        assert_eq!(
+            pairs(&[
+                ("the hobbit", "tolkien"),
+                ("dune", "herbert"),
+                ("the silmarillion", "tolkien"),
+                ("earthsea", "le guin")
+            ]),
+            rows(
+                &mut db,
+                "SELECT title, name FROM books INNER JOIN authors ON author_id = authors.id"
+            )
+        );
+        // reviews has no index and is scanned for each book
+        assert_eq!(
+            pairs(&[("the hobbit", "4"), ("dune", "5"), ("dune", "3")]),
+            rows(
+                &mut db,
+                "SELECT books.title, stars FROM books JOIN reviews ON books.title = reviews.title"
+            )
+        );
+
+        // the columns of both tables can be filtered, sorted and aggregated
+        assert_eq!(
+            pairs(&[("the silmarillion", "tolkien"), ("the hobbit", "tolkien")]),
+            rows(
+                &mut db,
+                "SELECT title, name FROM books JOIN authors ON author_id = authors.id \
+                 WHERE authors.name = 'tolkien' ORDER BY books.id DESC"
+            )
+        );
+        assert_eq!(
+            pairs(&[("herbert", "1"), ("le guin", "1"), ("tolkien", "2")]),
+            rows(
+                &mut db,
+                "SELECT name, COUNT(*) FROM authors JOIN books ON authors.id = author_id \
+                 GROUP BY name"
+            )
+        );
+        assert_eq!(
+            vec!["id", "name", "id", "author_id", "title"],
+            run(
+                &mut db,
+                "SELECT * FROM authors JOIN books ON authors.id = author_id LIMIT 1"
+            )
+            .unwrap()
+            .0
+        );
+        // the first table's rows can still be looked up in an index
+        let plan = Plan::new(
+            &mut db,
+            &parse_select(
+                "SELECT title FROM books JOIN authors ON author_id = authors.id \
+                 WHERE books.author_id = 1",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            Some("idx_books_author"),
+            plan.index.as_ref().map(|i| i.name.as_str())
+        );
+        assert_eq!(
+            vec![vec!["the hobbit"], vec!["the silmarillion"]],
+            rows(
+                &mut db,
+                "SELECT title FROM books JOIN authors ON author_id = authors.id \
+                 WHERE books.author_id = 1"
+            )
+        );
+
+        let err = |sql: &str| run(&mut Db::from_file("test_join.db").unwrap(), sql).unwrap_err();
+        assert_eq!(
+            "ambiguous column name: id",
+            err("SELECT id FROM authors JOIN books ON authors.id = author_id").to_string()
+        );
+        assert_eq!(
+            "no such column: books.name",
+            err("SELECT books.name FROM authors JOIN books ON authors.id = author_id").to_string()
+        );
+        assert!(
+            err("SELECT * FROM authors JOIN missing ON authors.id = missing.id")
+                .to_string()
+                .contains("no such table")
+        );
+        assert!(
+            err("SELECT * FROM authors JOIN books ON authors.id = authors.name")
+                .to_string()
+                .contains("a column of each table")
+        );
+        assert!(err("SELECT * FROM authors JOIN authors ON id = id")
+            .to_string()
+            .contains("joined with itself"));
+    }
+
+    #[test]
+    fn execute_schema_table_tests() {
+        let mut db = Db::from_file("test_index.db").unwrap();
+        let (columns, rows) = run(
+            &mut db,
+            "SELECT name, rootpage FROM sqlite_schema WHERE tbl_name = 'people' LIMIT 2",
+        )
+        .unwrap();
+        assert_eq!(vec!["name", "rootpage"], columns);
+        assert_eq!(
+            vec![
+                vec!["people".to_string(), "2".to_string()],
+                vec!["sqlite_autoindex_people_1".to_string(), "3".to_string()]
+            ],
+            rows
+        );
+    }
+
+    #[test]
+    fn execute_count_distinct_tests() {
+        let mut db = Db::from_file("test_index.db").unwrap();
+        assert_eq!(
+            (
+                vec![
+                    "COUNT(DISTINCT age)".to_string(),
+                    "COUNT(DISTINCT name)".to_string()
+                ],
+                vec![vec!["50".to_string(), "500".to_string()]]
+            ),
+            run(
+                &mut db,
+                "SELECT COUNT(DISTINCT age), count(distinct NAME) FROM people"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            vec![vec!["10".to_string()]],
+            run(
+                &mut db,
+                "SELECT COUNT(DISTINCT name) FROM people WHERE age = 10"
+            )
+            .unwrap()
+            .1
+        );
+        assert!(
+            run(&mut db, "SELECT COUNT(DISTINCT name) FROM people LIMIT 0")
+                .unwrap()
+                .1
+                .is_empty()
+        );
+        assert!(run(&mut db, "SELECT COUNT(DISTINCT name), age FROM people").is_err());
+        assert!(run(&mut db, "SELECT COUNT(DISTINCT missing) FROM people").is_err());
+
+        // nulls aren't counted
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        assert_eq!(
+            vec![vec!["2".to_string()]],
+            run(&mut db, "SELECT COUNT(DISTINCT age) FROM people")
+                .unwrap()
+                .1
+        );
+    }
+
+    #[test]
+    fn execute_group_by_tests() {
+        let rows = |db: &mut Db, sql: &str| run(db, sql).unwrap().1;
+        let mut db = Db::from_file("test_index.db").unwrap();
+        assert_eq!(
+            (
+                vec![
+                    "age".to_string(),
+                    "COUNT(*)".to_string(),
+                    "SUM(id)".to_string()
+                ],
+                vec![
+                    vec!["49".to_string(), "10".to_string(), "2750".to_string()],
+                    vec!["48".to_string(), "10".to_string(), "2740".to_string()]
+                ]
+            ),
+            run(
+                &mut db,
+                "SELECT age, COUNT(*), SUM(id) FROM people GROUP BY age ORDER BY age DESC LIMIT 2"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            50,
+            rows(&mut db, "SELECT age FROM people GROUP BY age").len()
+        );
+        assert_eq!(
+            vec![vec!["500".to_string(), "12250".to_string()]],
+            rows(&mut db, "SELECT COUNT(*), SUM(age) FROM people")
+        );
+        // no rows, a single row without GROUP BY and no groups with it
+        assert_eq!(
+            vec![vec!["0".to_string(), "".to_string()]],
+            rows(
+                &mut db,
+                "SELECT COUNT(*), SUM(age) FROM people WHERE age = 99"
+            )
+        );
+        assert!(rows(
+            &mut db,
+            "SELECT age, COUNT(*) FROM people WHERE age = 99 GROUP BY age"
+        )
+        .is_empty());
+        assert!(run(&mut db, "SELECT name, COUNT(*) FROM people GROUP BY age").is_err());
+        assert!(run(&mut db, "SELECT name, COUNT(*) FROM people").is_err());
+        assert!(run(&mut db, "SELECT COUNT(*) FROM people GROUP BY missing").is_err());
+        assert!(run(
+            &mut db,
+            "SELECT COUNT(*) FROM people GROUP BY age ORDER BY id"
+        )
+        .is_err());
+
+        // nulls are grouped together and come first
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        assert_eq!(
+            vec![
+                vec!["".to_string(), "1".to_string(), "5".to_string()],
+                vec!["25".to_string(), "1".to_string(), "42".to_string()],
+                vec!["30".to_string(), "1".to_string(), "1".to_string()]
+            ],
+            rows(
+                &mut db,
+                "SELECT age, COUNT(*), SUM(id) FROM people GROUP BY age"
+            )
+        );
+        assert_eq!(
+            vec![vec!["0.0".to_string()]],
+            rows(&mut db, "SELECT SUM(name) FROM people")
+        );
+    }
+
+    #[test]
+    fn execute_min_max_avg_tests() {
+        let rows = |db: &mut Db, sql: &str| run(db, sql).unwrap().1;
+        let mut db = Db::from_file("test_index.db").unwrap();
+        assert_eq!(
+            (
+                vec![
+                    "MIN(age)".to_string(),
+                    "MAX(age)".to_string(),
+                    "AVG(age)".to_string(),
+                    "MIN(name)".to_string(),
+                    "MAX(email)".to_string()
+                ],
+                vec![vec![
+                    "0".to_string(),
+                    "49".to_string(),
+                    "24.5".to_string(),
+                    "name000".to_string(),
+                    "p9@example.com".to_string()
+                ]]
+            ),
+            run(
+                &mut db,
+                "SELECT MIN(age), MAX(age), AVG(age), MIN(name), MAX(email) FROM people"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            vec![
+                vec![
+                    "0".to_string(),
+                    "1".to_string(),
+                    "451".to_string(),
+                    "226.0".to_string()
+                ],
+                vec![
+                    "1".to_string(),
+                    "2".to_string(),
+                    "452".to_string(),
+                    "227.0".to_string()
+                ]
+            ],
+            rows(
+                &mut db,
+                "SELECT age, MIN(id), MAX(id), AVG(id) FROM people GROUP BY age LIMIT 2"
+            )
+        );
+
+        // nulls are ignored, an all-null column gives null
+        let mut db = Db::from_file("test_rowid.db").unwrap();
+        assert_eq!(
+            vec![vec!["25".to_string(), "30".to_string(), "27.5".to_string()]],
+            rows(&mut db, "SELECT MIN(age), MAX(age), AVG(age) FROM people")
+        );
+        assert_eq!(
+            vec![vec!["".to_string(), "".to_string(), "".to_string()]],
+            rows(
+                &mut db,
+                "SELECT MIN(age), MAX(age), AVG(age) FROM people WHERE age IS NULL"
+            )
+        );
+    }
+
+    #[test]
+    fn execute_order_by_tests() {
+        let names = |sql: &str| {
+            let mut db = Db::from_file("test_rowid.db").unwrap();
+            run(&mut db, sql)
+                .unwrap()
+                .1
+                .into_iter()
+                .map(|mut row| row.remove(0))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec!["bob", "carol", "alice"],
+            names("SELECT name FROM people ORDER BY age")
+        );
+        assert_eq!(
+            vec!["alice", "carol", "bob"],
+            names("SELECT name FROM people ORDER BY age DESC")
+        );
+        assert_eq!(
+            vec!["carol", "bob", "alice"],
+            names("SELECT name FROM people ORDER BY name DESC")
+        );
+        assert_eq!(
+            vec!["carol"],
+            names("SELECT name FROM people ORDER BY age LIMIT 1 OFFSET 1")
+        );
+        assert_eq!(
+            vec!["bob"],
+            names("SELECT name FROM people WHERE age IS NULL ORDER BY id DESC")
+        );
+    }
+
+    #[test]
+    fn execute_limit_tests() {
+        let names = |sql: &str| {
+            let mut db = Db::from_file("test_rowid.db").unwrap();
+            run(&mut db, sql)
+                .unwrap()
+                .1
+                .into_iter()
+                .map(|mut row| row.remove(0))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec!["alice", "bob"],
+            names("SELECT name FROM people LIMIT 2")
+        );
+        assert_eq!(
+            vec!["bob", "carol"],
+            names("SELECT name FROM people LIMIT 5 OFFSET 1")
+        );
+        assert_eq!(vec!["bob"], names("SELECT name FROM people LIMIT 1, 1"));
+        assert!(names("SELECT name FROM people LIMIT 0").is_empty());
+        assert!(names("SELECT name FROM people LIMIT 1 OFFSET 3").is_empty());
+        assert_eq!(
+            vec!["carol"],
+            names("SELECT name FROM people WHERE age = 25 LIMIT 1")
+        );
+        assert_eq!(
+            vec!["alice", "bob", "carol"],
+            names("SELECT name FROM people LIMIT -1")
+        );
+    }
+}
@@ -0,0 +1,70 @@
+// sqlite's default LIKE: % matches any sequence of characters including none, _ matches exactly
+// one character, and ascii letters match regardless of their case
+pub fn like_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    let (mut p, mut t) = (0, 0);
+    // position of the last % seen and of the text it was matched against, to backtrack to when
+    // the rest of the pattern doesn't match
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('%') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(&c) if c == '_' || c.eq_ignore_ascii_case(&text[t]) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // the last % swallows one more character
+                Some((bp, bt)) => {
+                    p = bp;
+                    t = bt + 1;
+                    backtrack = Some((bp, bt + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '%')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn like_match_tests() {
+        assert!(like_match("abc", "abc"));
+        assert!(like_match("ABC", "abc"));
+        assert!(!like_match("abc", "abcd"));
+        assert!(!like_match("abcd", "abc"));
+        assert!(like_match("", ""));
+        assert!(!like_match("", "a"));
+
+        assert!(like_match("foo%", "foo"));
+        assert!(like_match("foo%", "Foobar"));
+        assert!(!like_match("foo%", "afoo"));
+        assert!(like_match("%bar", "foobar"));
+        assert!(like_match("%o%a%", "foobar"));
+        assert!(like_match("%", ""));
+        assert!(like_match("%%", "abc"));
+        assert!(like_match("a%b%c", "aXbYbZc"));
+        assert!(!like_match("a%b%c", "aXbYbZ"));
+
+        assert!(like_match("_", "a"));
+        assert!(!like_match("_", ""));
+        assert!(!like_match("_", "ab"));
+        assert!(like_match("f_o", "FOO"));
+        assert!(like_match("%_", "a"));
+        assert!(!like_match("%__", "a"));
+
+        // one character, not one byte, and only ascii is case insensitive
+        assert!(like_match("_", "é"));
+        assert!(like_match("caf_", "café"));
+        assert!(!like_match("É", "é"));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod aggregate;
+pub mod ast;
+pub mod executor;
+pub mod like;
+pub mod parser;
+pub mod result_set;
@@ -0,0 +1,680 @@
+use std::borrow::Cow;
+
+use crate::{
+    sql::{token::Token, token_stream::TokenStream},
+    value::Value,
+};
+
+use super::ast::{
+    Aggregate, ColumnRef, Comparison, Join, OrderBy, Predicate, ResultColumn, Select,
+};
+
+// SELECT col [, col]* FROM table [[INNER] JOIN table ON column = column] [WHERE predicate]
+// [GROUP BY column] [ORDER BY column [ASC|DESC]] [LIMIT n [OFFSET m]] [;]
+// where col is *, a column, COUNT(*), COUNT(DISTINCT column), SUM(column), MIN(column),
+// MAX(column) or AVG(column), the last ones optionally followed by AS alias, and columns can be
+// qualified by their table as in table.column
+pub fn parse_select(sql: &str) -> anyhow::Result<Select> {
+    let mut stream = TokenStream::parse(sql)?;
+
+    if !stream.next_if_keyword("SELECT") {
+        anyhow::bail!("unsupported query: only SELECT statements are supported");
+    }
+
+    let mut columns = Vec::new();
+    loop {
+        columns.push(parse_result_column(&mut stream)?);
+        if !stream.next_if_token(&Token::Comma) {
+            break;
+        }
+    }
+
+    stream.expect_keyword("FROM")?;
+    let table = stream.expect_identifier()?;
+
+    let join = if stream.next_if_keyword("INNER") {
+        stream.expect_keyword("JOIN")?;
+        Some(parse_join(&mut stream)?)
+    } else if stream.next_if_keyword("JOIN") {
+        Some(parse_join(&mut stream)?)
+    } else {
+        None
+    };
+
+    let filter = if stream.next_if_keyword("WHERE") {
+        Some(parse_predicate(&mut stream)?)
+    } else {
+        None
+    };
+
+    let group_by = if stream.next_if_keyword("GROUP") {
+        stream.expect_keyword("BY")?;
+        let column = parse_column_ref(&mut stream)?;
+        if stream.next_if_token(&Token::Comma) {
+            anyhow::bail!("unsupported query: only a single GROUP BY column is supported");
+        }
+        Some(column)
+    } else {
+        None
+    };
+
+    let order_by = if stream.next_if_keyword("ORDER") {
+        stream.expect_keyword("BY")?;
+        Some(parse_order_by(&mut stream)?)
+    } else {
+        None
+    };
+
+    let (limit, offset) = if stream.next_if_keyword("LIMIT") {
+        parse_limit(&mut stream)?
+    } else {
+        (None, 0)
+    };
+
+    stream.next_if_token(&Token::Semicolon);
+    if let Some(token) = stream.next() {
+        anyhow::bail!("unsupported query: unexpected {:?}", token);
+    }
+
+    Ok(Select {
+        columns,
+        table,
+        join,
+        filter,
+        group_by,
+        order_by,
+        limit,
+        offset,
+    })
+}
+
+// table ON column = column
+fn parse_join(stream: &mut TokenStream) -> anyhow::Result<Join> {
+    let table = stream.expect_identifier()?;
+    stream.expect_keyword("ON")?;
+    let left = parse_column_ref(stream)?;
+    match stream.next() {
+        Some(Token::Eq) => (),
+        other => anyhow::bail!(
+            "unsupported query: only equi-joins are supported, got {:?}",
+            other
+        ),
+    }
+    let right = parse_column_ref(stream)?;
+    Ok(Join {
+        table,
+        on: (left, right),
+    })
+}
+
+// column | table.column
+fn parse_column_ref(stream: &mut TokenStream) -> anyhow::Result<ColumnRef> {
+    let name = stream.expect_identifier()?;
+    if stream.next_if_token(&Token::Dot) {
+        return Ok(ColumnRef {
+            table: Some(name),
+            name: stream.expect_identifier()?,
+        });
+    }
+    Ok(ColumnRef { table: None, name })
+}
+
+fn parse_order_by(stream: &mut TokenStream) -> anyhow::Result<OrderBy> {
+    let column = parse_column_ref(stream)?;
+    let descending = if stream.next_if_keyword("DESC") {
+        true
+    } else {
+        stream.next_if_keyword("ASC");
+        false
+    };
+    if stream.next_if_token(&Token::Comma) {
+        anyhow::bail!("unsupported query: only a single ORDER BY column is supported");
+    }
+    Ok(OrderBy { column, descending })
+}
+
+// LIMIT n [OFFSET m] | LIMIT m, n
+fn parse_limit(stream: &mut TokenStream) -> anyhow::Result<(Option<usize>, usize)> {
+    let first = parse_integer(stream)?;
+    let (limit, offset) = if stream.next_if_keyword("OFFSET") {
+        (first, parse_integer(stream)?)
+    } else if stream.next_if_token(&Token::Comma) {
+        (parse_integer(stream)?, first)
+    } else {
+        (first, 0)
+    };
+    // a negative limit means no limit and a negative offset is treated as 0
+    Ok((usize::try_from(limit).ok(), offset.max(0) as usize))
+}
+
+fn parse_integer(stream: &mut TokenStream) -> anyhow::Result<i64> {
+    match parse_literal(stream)? {
+        Value::Int(i) => Ok(i),
+        other => anyhow::bail!("expected integer, got {:?}", other),
+    }
+}
+
+// conditions combined with AND and OR, AND binding tighter, and grouped with parentheses
+fn parse_predicate(stream: &mut TokenStream) -> anyhow::Result<Predicate> {
+    let mut predicate = parse_conjunction(stream)?;
+    while stream.next_if_keyword("OR") {
+        let right = parse_conjunction(stream)?;
+        predicate = Predicate::Or(Box::new(predicate), Box::new(right));
+    }
+    Ok(predicate)
+}
+
+fn parse_conjunction(stream: &mut TokenStream) -> anyhow::Result<Predicate> {
+    let mut predicate = parse_condition(stream)?;
+    while stream.next_if_keyword("AND") {
+        let right = parse_condition(stream)?;
+        predicate = Predicate::And(Box::new(predicate), Box::new(right));
+    }
+    Ok(predicate)
+}
+
+// ( predicate ) | col <op> literal | col BETWEEN literal AND literal | col IS NULL
+// | col LIKE 'pattern'
+// where op is one of =, ==, <>, !=, <, <=, >, >=
+fn parse_condition(stream: &mut TokenStream) -> anyhow::Result<Predicate> {
+    if stream.next_if_token(&Token::LeftParen) {
+        let predicate = parse_predicate(stream)?;
+        stream.expect_token(&Token::RightParen)?;
+        return Ok(predicate);
+    }
+    let column = parse_column_ref(stream)?;
+    if stream.next_if_keyword("IS") {
+        stream.expect_keyword("NULL")?;
+        return Ok(Predicate::IsNull { column });
+    }
+    if stream.next_if_keyword("LIKE") {
+        return match stream.next() {
+            Some(Token::String(pattern)) => Ok(Predicate::Like { column, pattern }),
+            other => anyhow::bail!("expected a string pattern after LIKE, got {:?}", other),
+        };
+    }
+    if stream.next_if_keyword("BETWEEN") {
+        let low = parse_literal(stream)?;
+        stream.expect_keyword("AND")?;
+        let high = parse_literal(stream)?;
+        return Ok(Predicate::Between { column, low, high });
+    }
+    let comparison = match stream.next() {
+        Some(Token::Eq) => Comparison::Eq,
+        Some(Token::NotEq) => Comparison::NotEq,
+        Some(Token::Lt) => Comparison::Lt,
+        Some(Token::LtEq) => Comparison::LtEq,
+        Some(Token::Gt) => Comparison::Gt,
+        Some(Token::GtEq) => Comparison::GtEq,
+        other => anyhow::bail!("unsupported query: expected a comparison, got {:?}", other),
+    };
+    let value = parse_literal(stream)?;
+    Ok(Predicate::Compare {
+        column,
+        comparison,
+        value,
+    })
+}
+
+pub fn parse_literal(stream: &mut TokenStream) -> anyhow::Result<Value<'static>> {
+    let negate = stream.next_if_token(&Token::Minus);
+    let value = match stream.next() {
+        // 0x8000000000000000 is already i64::MIN and can't be negated
+        Some(Token::Integer(i)) if negate => i
+            .checked_neg()
+            .map_or(Value::Float(-(i as f64)), Value::Int),
+        // 9223372036854775808 only fits in an i64 once negated
+        Some(Token::Float(f)) if negate && f == -(i64::MIN as f64) => Value::Int(i64::MIN),
+        Some(Token::Float(f)) if negate => Value::Float(-f),
+        Some(Token::Integer(i)) => Value::Int(i),
+        Some(Token::Float(f)) => Value::Float(f),
+        Some(Token::String(s)) if !negate => Value::String(Cow::Owned(s)),
+        Some(Token::Blob(b)) if !negate => Value::Blob(Cow::Owned(b)),
+        Some(t) if !negate && t.is_keyword("NULL") => Value::Null,
+        other => anyhow::bail!("expected literal, got {:?}", other),
+    };
+    Ok(value)
+}
+
+fn parse_result_column(stream: &mut TokenStream) -> anyhow::Result<ResultColumn> {
+    if stream.next_if_token(&Token::Star) {
+        return Ok(ResultColumn::Star);
+    }
+    let column = parse_expression_column(stream)?;
+    if stream.next_if_keyword("AS") {
+        let alias = stream.expect_identifier()?;
+        return Ok(ResultColumn::Aliased(Box::new(column), alias));
+    }
+    Ok(column)
+}
+
+fn parse_expression_column(stream: &mut TokenStream) -> anyhow::Result<ResultColumn> {
+    if stream
+        .peek()
+        .is_some_and(|t| AGGREGATES.iter().any(|a| t.is_keyword(a)))
+        && stream.peek_nth(1) == Some(&Token::LeftParen)
+    {
+        return parse_aggregate(stream).map(ResultColumn::Aggregate);
+    }
+    match stream.peek() {
+        Some(t) if t.is_keyword("FROM") => anyhow::bail!("expected column, got FROM"),
+        Some(t) if t.as_identifier().is_none() => {
+            anyhow::bail!("unsupported query: unexpected {:?} in columns", t)
+        }
+        Some(_) => parse_column_ref(stream).map(ResultColumn::Column),
+        None => anyhow::bail!("expected column, got end of input"),
+    }
+}
+
+const AGGREGATES: [&str; 5] = ["COUNT", "SUM", "MIN", "MAX", "AVG"];
+
+// COUNT(*) | COUNT(DISTINCT col) | SUM(col) | MIN(col) | MAX(col) | AVG(col)
+fn parse_aggregate(stream: &mut TokenStream) -> anyhow::Result<Aggregate> {
+    let function = stream.expect_identifier()?.to_ascii_uppercase();
+    stream.expect_token(&Token::LeftParen)?;
+    let aggregate = match function.as_str() {
+        "COUNT" if stream.next_if_token(&Token::Star) => Aggregate::CountRows,
+        "COUNT" if stream.next_if_keyword("DISTINCT") => {
+            Aggregate::CountDistinct(parse_column_ref(stream)?)
+        }
+        "COUNT" => anyhow::bail!(
+            "unsupported query: only COUNT(*) and COUNT(DISTINCT column) are supported"
+        ),
+        "SUM" => Aggregate::Sum(parse_column_ref(stream)?),
+        "MIN" => Aggregate::Min(parse_column_ref(stream)?),
+        "MAX" => Aggregate::Max(parse_column_ref(stream)?),
+        _ => Aggregate::Avg(parse_column_ref(stream)?),
+    };
+    stream.expect_token(&Token::RightParen)?;
+    Ok(aggregate)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_select_tests() {
+        assert_eq!(
+            Select {
+                columns: vec![
+                    ResultColumn::Column("one".into()),
+                    ResultColumn::Column("two".into())
+                ],
+                table: "tbl1".to_string(),
+                join: None,
+                filter: None,
+                group_by: None,
+                order_by: None,
+                limit: None,
+                offset: 0,
+            },
+            parse_select("SELECT one, two FROM tbl1;").unwrap()
+        );
+        assert_eq!(
+            Select {
+                columns: vec![ResultColumn::Star, ResultColumn::Column("a b".into())],
+                table: "t".to_string(),
+                join: None,
+                filter: None,
+                group_by: None,
+                order_by: None,
+                limit: None,
+                offset: 0,
+            },
+            parse_select("select *, \"a b\" from [t]").unwrap()
+        );
+        assert!(parse_select("INSERT INTO t VALUES (1)").is_err());
+        assert!(parse_select("SELECT FROM t").is_err());
+        assert!(parse_select("SELECT a FROM").is_err());
+        assert!(parse_select("SELECT a, FROM t").is_err());
+        assert!(parse_select("SELECT a + 1 FROM t").is_err());
+        assert!(parse_select("SELECT a FROM t GROUP BY a, b").is_err());
+        assert!(parse_select("SELECT a FROM t ORDER BY a GROUP BY a").is_err());
+    }
+
+    #[test]
+    fn parse_aggregate_tests() {
+        let columns = |sql: &str| parse_select(sql).unwrap().columns;
+        assert_eq!(
+            vec![ResultColumn::Aggregate(Aggregate::CountDistinct(
+                "age".into()
+            ))],
+            columns("SELECT count(distinct age) FROM people")
+        );
+        assert_eq!(
+            vec![
+                ResultColumn::Aggregate(Aggregate::CountRows),
+                ResultColumn::Aggregate(Aggregate::CountDistinct("b c".into())),
+                ResultColumn::Aggregate(Aggregate::Sum("a".into()))
+            ],
+            columns("SELECT COUNT(*), COUNT(DISTINCT \"b c\"), sum(a) FROM t")
+        );
+        // columns named like aggregates
+        assert_eq!(
+            vec![
+                ResultColumn::Column("count".into()),
+                ResultColumn::Column("sum".into())
+            ],
+            columns("SELECT count, sum FROM t")
+        );
+        assert!(parse_select("SELECT COUNT(a) FROM t").is_err());
+        assert!(parse_select("SELECT COUNT(DISTINCT a FROM t").is_err());
+        assert!(parse_select("SELECT COUNT(DISTINCT) FROM t").is_err());
+        assert!(parse_select("SELECT SUM(*) FROM t").is_err());
+        assert_eq!(
+            vec![
+                ResultColumn::Aggregate(Aggregate::Min("a".into())),
+                ResultColumn::Aggregate(Aggregate::Max("a".into())),
+                ResultColumn::Aggregate(Aggregate::Avg("b".into()))
+            ],
+            columns("SELECT min(a), MAX(a), Avg(b) FROM t")
+        );
+    }
+
+    #[test]
+    fn parse_group_by_tests() {
+        let select =
+            parse_select("SELECT age, COUNT(*) FROM people GROUP BY age ORDER BY age DESC")
+                .unwrap();
+        assert_eq!(Some("age".into()), select.group_by);
+        assert_eq!(
+            Some(OrderBy {
+                column: "age".into(),
+                descending: true
+            }),
+            select.order_by
+        );
+        assert_eq!(None, parse_select("SELECT a FROM t").unwrap().group_by);
+        assert!(parse_select("SELECT a FROM t GROUP a").is_err());
+    }
+
+    #[test]
+    fn parse_join_tests() {
+        let qualified = |table: &str, name: &str| ColumnRef {
+            table: Some(table.to_string()),
+            name: name.to_string(),
+        };
+        let select =
+            parse_select("SELECT a.x, y FROM a JOIN b ON a.id = b.a_id WHERE b.y > 1 ORDER BY a.x")
+                .unwrap();
+        assert_eq!(
+            vec![
+                ResultColumn::Column(qualified("a", "x")),
+                ResultColumn::Column("y".into())
+            ],
+            select.columns
+        );
+        assert_eq!(
+            Some(Join {
+                table: "b".to_string(),
+                on: (qualified("a", "id"), qualified("b", "a_id"))
+            }),
+            select.join
+        );
+        assert!(matches!(
+            select.filter,
+            Some(Predicate::Compare { column, .. }) if column == qualified("b", "y")
+        ));
+        assert_eq!(Some(qualified("a", "x")), select.order_by.map(|o| o.column));
+        assert_eq!(
+            Some(Join {
+                table: "b".to_string(),
+                on: ("id".into(), "a_id".into())
+            }),
+            parse_select("SELECT * FROM a INNER JOIN b ON id = a_id")
+                .unwrap()
+                .join
+        );
+        assert_eq!(
+            vec![ResultColumn::Aggregate(Aggregate::Sum(qualified("t", "a")))],
+            parse_select("SELECT SUM(t.a) FROM t").unwrap().columns
+        );
+        assert!(parse_select("SELECT * FROM a JOIN b").is_err());
+        assert!(parse_select("SELECT * FROM a JOIN b ON a.id < b.id").is_err());
+        assert!(parse_select("SELECT * FROM a JOIN b ON a.id = 1").is_err());
+        assert!(parse_select("SELECT * FROM a INNER b ON a.id = b.id").is_err());
+        assert!(parse_select("SELECT * FROM a LEFT JOIN b ON a.id = b.id").is_err());
+        assert!(parse_select("SELECT a. FROM a").is_err());
+    }
+
+    #[test]
+    fn parse_alias_tests() {
+        let columns = |sql: &str| parse_select(sql).unwrap().columns;
+        assert_eq!(
+            vec![
+                ResultColumn::Aliased(
+                    Box::new(ResultColumn::Column("one".into())),
+                    "label".to_string()
+                ),
+                ResultColumn::Column("two".into()),
+                ResultColumn::Aliased(
+                    Box::new(ResultColumn::Aggregate(Aggregate::CountRows)),
+                    "total rows".to_string()
+                )
+            ],
+            columns("SELECT one AS label, two, COUNT(*) as \"total rows\" FROM t")
+        );
+        assert!(parse_select("SELECT * AS a FROM t").is_err());
+        assert!(parse_select("SELECT a AS FROM t").is_err());
+        assert!(parse_select("SELECT a AS 1 FROM t").is_err());
+    }
+
+    #[test]
+    fn parse_where_tests() {
+        let filter = |sql: &str| parse_select(sql).unwrap().filter;
+        assert_eq!(
+            Some(Predicate::Compare {
+                column: "two".into(),
+                comparison: Comparison::Eq,
+                value: Value::Int(3)
+            }),
+            filter("SELECT one FROM tbl WHERE two = 3;")
+        );
+        assert_eq!(
+            Some(Predicate::Compare {
+                column: "two".into(),
+                comparison: Comparison::Eq,
+                value: Value::Float(-1.5)
+            }),
+            filter("SELECT one FROM tbl WHERE two == -1.5")
+        );
+        assert_eq!(
+            Some(Predicate::Compare {
+                column: "one".into(),
+                comparison: Comparison::Eq,
+                value: Value::String(Cow::from("it's"))
+            }),
+            filter("SELECT one FROM tbl WHERE one = 'it''s'")
+        );
+        assert_eq!(
+            Some(Predicate::IsNull {
+                column: "one".into()
+            }),
+            filter("SELECT one FROM tbl WHERE one IS NULL")
+        );
+        assert!(parse_select("SELECT one FROM tbl WHERE").is_err());
+        assert!(parse_select("SELECT one FROM tbl WHERE one = -'a'").is_err());
+        assert!(parse_select("SELECT one FROM tbl WHERE one IS 3").is_err());
+        assert!(parse_select("SELECT one FROM tbl WHERE one = two").is_err());
+        assert_eq!(
+            Some(Predicate::Like {
+                column: "one".into(),
+                pattern: "a%".to_string()
+            }),
+            filter("SELECT one FROM tbl WHERE one LIKE 'a%'")
+        );
+        assert!(parse_select("SELECT one FROM tbl WHERE one LIKE 3").is_err());
+        assert!(parse_select("SELECT one FROM tbl WHERE one LIKE").is_err());
+    }
+
+    #[test]
+    fn parse_and_or_tests() {
+        let filter = |sql: &str| parse_select(sql).unwrap().filter.unwrap();
+        let eq = |column: &str, i: i64| {
+            Box::new(Predicate::Compare {
+                column: column.into(),
+                comparison: Comparison::Eq,
+                value: Value::Int(i),
+            })
+        };
+        // AND binds tighter than OR
+        assert_eq!(
+            Predicate::Or(eq("a", 1), Box::new(Predicate::And(eq("b", 2), eq("c", 3)))),
+            filter("SELECT * FROM t WHERE a = 1 OR b = 2 AND c = 3")
+        );
+        assert_eq!(
+            Predicate::Or(Box::new(Predicate::And(eq("a", 1), eq("b", 2))), eq("c", 3)),
+            filter("SELECT * FROM t WHERE a = 1 and b = 2 or c = 3")
+        );
+        assert_eq!(
+            Predicate::And(Box::new(Predicate::Or(eq("a", 1), eq("b", 2))), eq("c", 3)),
+            filter("SELECT * FROM t WHERE (a = 1 OR (b = 2)) AND c = 3")
+        );
+        // left associative
+        assert_eq!(
+            Predicate::And(Box::new(Predicate::And(eq("a", 1), eq("b", 2))), eq("c", 3)),
+            filter("SELECT * FROM t WHERE a = 1 AND b = 2 AND c = 3")
+        );
+        // the AND of a BETWEEN belongs to it
+        assert!(matches!(
+            filter("SELECT * FROM t WHERE a BETWEEN 1 AND 2 AND b = 3"),
+            Predicate::And(left, _) if matches!(*left, Predicate::Between { .. })
+        ));
+        assert!(parse_select("SELECT * FROM t WHERE (a = 1").is_err());
+        assert!(parse_select("SELECT * FROM t WHERE a = 1)").is_err());
+        assert!(parse_select("SELECT * FROM t WHERE a = 1 AND").is_err());
+        assert!(parse_select("SELECT * FROM t WHERE OR a = 1").is_err());
+    }
+
+    #[test]
+    fn required_equalities_tests() {
+        let equalities = |sql: &str| {
+            parse_select(sql)
+                .unwrap()
+                .filter
+                .unwrap()
+                .required_equalities()
+                .into_iter()
+                .map(|(c, v)| (c.to_string(), v.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![
+                ("a".to_string(), Value::Int(1)),
+                ("c".to_string(), Value::Int(3))
+            ],
+            equalities("SELECT * FROM t WHERE a = 1 AND b > 2 AND (c = 3 AND d IS NULL)")
+        );
+        assert!(equalities("SELECT * FROM t WHERE a = 1 OR b = 2").is_empty());
+        assert!(equalities("SELECT * FROM t WHERE a <> 1").is_empty());
+    }
+
+    #[test]
+    fn parse_comparison_tests() {
+        let comparison = |sql: &str| match parse_select(sql).unwrap().filter {
+            Some(Predicate::Compare { comparison, .. }) => comparison,
+            other => panic!("not a comparison: {:?}", other),
+        };
+        for (op, expected) in [
+            ("=", Comparison::Eq),
+            ("==", Comparison::Eq),
+            ("<>", Comparison::NotEq),
+            ("!=", Comparison::NotEq),
+            ("<", Comparison::Lt),
+            ("<=", Comparison::LtEq),
+            (">", Comparison::Gt),
+            (">=", Comparison::GtEq),
+        ] {
+            assert_eq!(
+                expected,
+                comparison(&format!("SELECT a FROM t WHERE a {} 1", op)),
+                "{}",
+                op
+            );
+        }
+        assert_eq!(
+            Some(Predicate::Between {
+                column: "a".into(),
+                low: Value::Int(-1),
+                high: Value::String(Cow::from("z"))
+            }),
+            parse_select("SELECT a FROM t WHERE a BETWEEN -1 AND 'z'")
+                .unwrap()
+                .filter
+        );
+        assert!(parse_select("SELECT a FROM t WHERE a BETWEEN 1").is_err());
+        assert!(parse_select("SELECT a FROM t WHERE a BETWEEN 1 OR 2").is_err());
+        assert!(parse_select("SELECT a FROM t WHERE a + 1").is_err());
+    }
+
+    #[test]
+    fn parse_literal_tests() {
+        let literal = |sql: &str| match parse_select(sql).unwrap().filter {
+            Some(Predicate::Compare { value, .. }) => value,
+            other => panic!("not a comparison: {:?}", other),
+        };
+        assert_eq!(Value::Int(-1), literal("SELECT a FROM t WHERE a = -1"));
+        assert_eq!(
+            Value::Float(-1.5),
+            literal("SELECT a FROM t WHERE a = -1.5")
+        );
+        assert_eq!(
+            Value::Int(i64::MIN),
+            literal("SELECT a FROM t WHERE a = -9223372036854775808")
+        );
+        assert_eq!(
+            Value::Float(9223372036854775808.0),
+            literal("SELECT a FROM t WHERE a = 9223372036854775808")
+        );
+        assert_eq!(
+            Value::Int(i64::MIN),
+            literal("SELECT a FROM t WHERE a = 0x8000000000000000")
+        );
+        assert_eq!(
+            Value::Float(9223372036854775808.0),
+            literal("SELECT a FROM t WHERE a = -0x8000000000000000")
+        );
+        assert!(parse_select("SELECT a FROM t WHERE a = -'a'").is_err());
+    }
+
+    #[test]
+    fn parse_order_by_tests() {
+        let order_by = |sql: &str| parse_select(sql).unwrap().order_by;
+        let two = |descending| {
+            Some(OrderBy {
+                column: "two".into(),
+                descending,
+            })
+        };
+        assert_eq!(two(false), order_by("SELECT * FROM t ORDER BY two"));
+        assert_eq!(two(false), order_by("SELECT * FROM t ORDER BY two ASC"));
+        assert_eq!(
+            two(true),
+            order_by("SELECT * FROM t WHERE a = 1 ORDER BY two DESC LIMIT 1;")
+        );
+        assert!(parse_select("SELECT * FROM t ORDER two").is_err());
+        assert!(parse_select("SELECT * FROM t ORDER BY a, b").is_err());
+        assert!(parse_select("SELECT * FROM t LIMIT 1 ORDER BY a").is_err());
+    }
+
+    #[test]
+    fn parse_limit_tests() {
+        let limit = |sql: &str| {
+            let select = parse_select(sql).unwrap();
+            (select.limit, select.offset)
+        };
+        assert_eq!((Some(10), 0), limit("SELECT * FROM t LIMIT 10"));
+        assert_eq!((Some(10), 5), limit("SELECT * FROM t LIMIT 10 OFFSET 5;"));
+        assert_eq!((Some(10), 5), limit("SELECT * FROM t LIMIT 5, 10"));
+        assert_eq!((None, 0), limit("SELECT * FROM t LIMIT -1 OFFSET -2"));
+        assert_eq!(
+            (Some(1), 0),
+            limit("SELECT * FROM t WHERE a IS NULL LIMIT 1")
+        );
+        assert!(parse_select("SELECT * FROM t LIMIT").is_err());
+        assert!(parse_select("SELECT * FROM t LIMIT 'a'").is_err());
+        assert!(parse_select("SELECT * FROM t LIMIT 1.5").is_err());
+        assert!(parse_select("SELECT * FROM t OFFSET 1").is_err());
+    }
+}
@@ -0,0 +1,51 @@
+use crate::value::Value;
+
+// every row of a query, owned so that it outlives the db it was read from
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ResultSet {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value<'static>>>,
+}
+
+impl ResultSet {
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    // the values of a column across every row, None if there is no such column
+    pub fn column(&self, name: &str) -> Option<Vec<&Value<'static>>> {
+        let i = self
+            .columns
+            .iter()
+            .position(|c| c.eq_ignore_ascii_case(name))?;
+        Some(self.rows.iter().map(|row| &row[i]).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn result_set_tests() {
+        let result_set = ResultSet {
+            columns: vec!["a".to_string(), "b".to_string()],
+            rows: vec![
+                vec![Value::Int(1), Value::Null],
+                vec![Value::Int(2), Value::Float(0.5)],
+            ],
+        };
+        assert_eq!(2, result_set.len());
+        assert!(!result_set.is_empty());
+        assert_eq!(
+            Some(vec![&Value::Null, &Value::Float(0.5)]),
+            result_set.column("B")
+        );
+        assert_eq!(None, result_set.column("c"));
+        assert!(ResultSet::default().is_empty());
+    }
+}
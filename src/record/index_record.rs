@@ -0,0 +1,122 @@
+use std::cmp::Ordering;
+
+use anyhow::Context;
+
+use crate::{cursor::Cursor, value::Value};
+
+use super::record_header::RecordHeader;
+
+// an entry of an index b-tree, the indexed columns followed by the row id of the table row they
+// were taken from
+#[derive(Debug, Clone)]
+pub struct IndexRecord {
+    record: Cursor,
+    key_len: usize,
+}
+
+impl IndexRecord {
+    // key_len is the number of indexed columns
+    pub fn parse(payload: &[u8], key_len: usize) -> anyhow::Result<IndexRecord> {
+        let header = RecordHeader::parse(payload)?;
+        if header.len() <= key_len {
+            anyhow::bail!(
+                "index record has {} fields, expected {} columns and a row id",
+                header.len(),
+                key_len
+            );
+        }
+        Ok(IndexRecord {
+            record: Cursor::new(header, payload.to_vec(), 0),
+            key_len,
+        })
+    }
+
+    pub fn key(&self, n: usize) -> anyhow::Result<Option<Value<'_>>> {
+        if n >= self.key_len {
+            return Ok(None);
+        }
+        self.record.field(n)
+    }
+
+    pub fn keys(&self) -> anyhow::Result<Vec<Value<'_>>> {
+        (0..self.key_len)
+            .map(|n| self.record.field(n).map(|v| v.unwrap_or(Value::Null)))
+            .collect()
+    }
+
+    pub fn row_id(&self) -> anyhow::Result<i64> {
+        self.record
+            .field(self.record.header.len() - 1)?
+            .and_then(|v| v.as_int())
+            .context("index record doesn't end with a row id")
+    }
+
+    // orders the entry against a search key, column by column like the index does: a column
+    // declared DESC sorts the other way around, and an entry starting with all the columns of a
+    // shorter key is equal to it
+    pub fn cmp_key(&self, key: &[Value], descending: &[bool]) -> anyhow::Result<Ordering> {
+        for (n, value) in key.iter().enumerate() {
+            let ordering = self.key(n)?.unwrap_or(Value::Null).cmp_sqlite(value);
+            let ordering = if descending.get(n).copied().unwrap_or(false) {
+                ordering.reverse()
+            } else {
+                ordering
+            };
+            if ordering != Ordering::Equal {
+                return Ok(ordering);
+            }
+        }
+        Ok(Ordering::Equal)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use crate::record::serialize;
+
+    use super::*;
+
+    #[test]
+    fn index_record_tests() {
+        let payload = serialize(&[Value::String(Cow::from("b")), Value::Int(3), Value::Int(42)]);
+        let record = IndexRecord::parse(&payload, 2).unwrap();
+        assert_eq!(
+            vec![Value::String(Cow::from("b")), Value::Int(3)],
+            record.keys().unwrap()
+        );
+        assert_eq!(Some(Value::Int(3)), record.key(1).unwrap());
+        assert_eq!(None, record.key(2).unwrap());
+        assert_eq!(42, record.row_id().unwrap());
+
+        // the row id can't be missing
+        assert!(IndexRecord::parse(&payload, 3).is_err());
+        let payload = serialize(&[Value::Int(1), Value::Null]);
+        assert!(IndexRecord::parse(&payload, 1).unwrap().row_id().is_err());
+    }
+
+    #[test]
+    fn cmp_key_tests() {
+        let payload = serialize(&[Value::Int(5), Value::String(Cow::from("m")), Value::Int(1)]);
+        let record = IndexRecord::parse(&payload, 2).unwrap();
+        let cmp = |key: &[Value], descending: &[bool]| record.cmp_key(key, descending).unwrap();
+
+        let m = || Value::String(Cow::from("m"));
+        let z = || Value::String(Cow::from("z"));
+        assert_eq!(Ordering::Equal, cmp(&[Value::Int(5), m()], &[]));
+        assert_eq!(Ordering::Less, cmp(&[Value::Int(5), z()], &[]));
+        assert_eq!(Ordering::Greater, cmp(&[Value::Int(4), z()], &[]));
+        // a prefix of the key
+        assert_eq!(Ordering::Equal, cmp(&[Value::Int(5)], &[]));
+        assert_eq!(Ordering::Equal, cmp(&[], &[]));
+        // only the DESC column is reversed
+        assert_eq!(
+            Ordering::Greater,
+            cmp(&[Value::Int(5), z()], &[false, true])
+        );
+        assert_eq!(Ordering::Less, cmp(&[Value::Int(4), z()], &[true, false]));
+        // nulls sort first
+        assert_eq!(Ordering::Greater, cmp(&[Value::Null], &[]));
+    }
+}
@@ -1,3 +1,7 @@
+pub mod index_record;
 pub mod record_field;
 pub mod record_field_type;
 pub mod record_header;
+pub mod serializer;
+
+pub use serializer::serialize;
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 
-use crate::{utils, value::Value};
+use crate::{error::SqlrError, utils, value::Value};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum RecordFieldType {
@@ -19,7 +19,7 @@ pub enum RecordFieldType {
 }
 
 impl RecordFieldType {
-    pub fn parse(discriminant: i64) -> anyhow::Result<RecordFieldType> {
+    pub fn parse(discriminant: i64) -> crate::error::Result<RecordFieldType> {
         match discriminant {
             0 => Ok(RecordFieldType::Null),
             1 => Ok(RecordFieldType::I8),
@@ -39,7 +39,45 @@ impl RecordFieldType {
                 let size = ((n - 13) / 2) as usize;
                 Ok(RecordFieldType::String(size))
             }
-            n => Err(anyhow::anyhow!("unsupported field type: {}", n)),
+            n => Err(SqlrError::UnsupportedSerialType(n)),
+        }
+    }
+
+    // inverse of parse
+    pub fn serial_type(self) -> i64 {
+        match self {
+            Self::Null => 0,
+            Self::I8 => 1,
+            Self::I16 => 2,
+            Self::I24 => 3,
+            Self::I32 => 4,
+            Self::I48 => 5,
+            Self::I64 => 6,
+            Self::Float => 7,
+            Self::Zero => 8,
+            Self::One => 9,
+            Self::Blob(s) => 2 * s as i64 + 12,
+            Self::String(s) => 2 * s as i64 + 13,
+        }
+    }
+
+    // the smallest type able to hold the value
+    pub fn for_value(value: &Value) -> RecordFieldType {
+        match value {
+            Value::Null => Self::Null,
+            Value::Int(0) => Self::Zero,
+            Value::Int(1) => Self::One,
+            Value::Int(n) => match *n {
+                -0x80..=0x7f => Self::I8,
+                -0x8000..=0x7fff => Self::I16,
+                -0x80_0000..=0x7f_ffff => Self::I24,
+                -0x8000_0000..=0x7fff_ffff => Self::I32,
+                -0x8000_0000_0000..=0x7fff_ffff_ffff => Self::I48,
+                _ => Self::I64,
+            },
+            Value::Float(_) => Self::Float,
+            Value::String(s) => Self::String(s.len()),
+            Value::Blob(b) => Self::Blob(b.len()),
         }
     }
 
@@ -60,8 +98,8 @@ impl RecordFieldType {
         }
     }
 
-    pub fn value(self, payload: &[u8], offset: usize) -> Option<Value> {
-        match self {
+    pub fn value(self, payload: &[u8], offset: usize) -> anyhow::Result<Option<Value<'_>>> {
+        let value = match self {
             Self::Null => Some(Value::Null),
             Self::I8 => Some(Value::Int(utils::read_i8_at(payload, offset))),
             Self::I16 => Some(Value::Int(utils::read_i16_at(payload, offset))),
@@ -69,18 +107,44 @@ impl RecordFieldType {
             Self::I32 => Some(Value::Int(utils::read_i32_at(payload, offset))),
             Self::I48 => Some(Value::Int(utils::read_i48_at(payload, offset))),
             Self::I64 => Some(Value::Int(utils::read_i64_at(payload, offset))),
-            Self::Float => Some(Value::Float(utils::read_f64_at(payload, offset))),
+            Self::Float => {
+                let value = utils::try_read_f64_at(payload, offset).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "float at offset {} exceeds payload of length {}",
+                        offset,
+                        payload.len()
+                    )
+                })?;
+                Some(Value::Float(value))
+            }
+            Self::Zero => Some(Value::Int(0)),
+            Self::One => Some(Value::Int(1)),
             Self::Blob(length) => {
-                let value = &payload[offset..offset + length];
+                let value = Self::slice_at(payload, offset, length)?;
                 Some(Value::Blob(Cow::Borrowed(value)))
             }
             Self::String(length) => {
-                let value =
-                    std::str::from_utf8(&payload[offset..offset + length]).expect("invalid utf8");
+                let value = std::str::from_utf8(Self::slice_at(payload, offset, length)?)
+                    .map_err(|e| anyhow::anyhow!("invalid utf8 string: {}", e))?;
                 Some(Value::String(Cow::Borrowed(value)))
             }
-            _ => None,
-        }
+        };
+        Ok(value)
+    }
+
+    // blob and string lengths come straight from the record header, they can't be trusted
+    fn slice_at(payload: &[u8], offset: usize, length: usize) -> anyhow::Result<&[u8]> {
+        offset
+            .checked_add(length)
+            .and_then(|end| payload.get(offset..end))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "field of length {} at offset {} exceeds payload of length {}",
+                    length,
+                    offset,
+                    payload.len()
+                )
+            })
     }
 }
 
@@ -89,33 +153,82 @@ mod test {
     use super::*;
 
     #[test]
-    fn record_field_type_value_tests() -> () {
-        assert_eq!(Some(Value::Null), RecordFieldType::Null.value(&[], 0));
-        assert_eq!(Some(Value::Int(1)), RecordFieldType::I8.value(&[1], 0));
+    fn record_field_type_value_tests() {
+        assert_eq!(
+            Some(Value::Null),
+            RecordFieldType::Null.value(&[], 0).unwrap()
+        );
+        assert_eq!(
+            Some(Value::Int(1)),
+            RecordFieldType::I8.value(&[1], 0).unwrap()
+        );
         assert_eq!(
             Some(Value::Int(257)),
-            RecordFieldType::I16.value(&[1, 1], 0)
+            RecordFieldType::I16.value(&[1, 1], 0).unwrap()
         );
         assert_eq!(
             Some(Value::Int(65793)),
-            RecordFieldType::I24.value(&[1, 1, 1], 0)
+            RecordFieldType::I24.value(&[1, 1, 1], 0).unwrap()
         );
         assert_eq!(
             Some(Value::Int(16843009)),
-            RecordFieldType::I32.value(&[1, 1, 1, 1], 0)
+            RecordFieldType::I32.value(&[1, 1, 1, 1], 0).unwrap()
         );
         assert_eq!(
             Some(Value::Int(1099511627777)),
-            RecordFieldType::I48.value(&[1, 0, 0, 0, 0, 1], 0)
+            RecordFieldType::I48.value(&[1, 0, 0, 0, 0, 1], 0).unwrap()
         );
         assert_eq!(
             Some(Value::Int(72057594037927936)),
-            RecordFieldType::I64.value(&[1, 0, 0, 0, 0, 0, 0, 0], 0)
+            RecordFieldType::I64
+                .value(&[1, 0, 0, 0, 0, 0, 0, 0], 0)
+                .unwrap()
+        );
+        assert_eq!(
+            Some(Value::Int(0)),
+            RecordFieldType::Zero.value(&[], 0).unwrap()
+        );
+        assert_eq!(
+            Some(Value::Int(1)),
+            RecordFieldType::One.value(&[], 0).unwrap()
+        );
+        assert_eq!(
+            Some(Value::String(Cow::from("ab"))),
+            RecordFieldType::String(2).value(&[97, 98], 0).unwrap()
+        );
+        // 0xff is never valid in utf8
+        assert!(RecordFieldType::String(2).value(&[97, 255], 0).is_err());
+    }
+
+    #[test]
+    fn record_field_type_value_truncated_tests() {
+        assert_eq!(
+            Some(Value::Blob(Cow::from(&[2, 3][..]))),
+            RecordFieldType::Blob(2).value(&[1, 2, 3], 1).unwrap()
         );
+        assert!(RecordFieldType::Blob(3).value(&[1, 2, 3], 1).is_err());
+        assert!(RecordFieldType::Blob(1).value(&[1, 2, 3], 4).is_err());
+        assert!(RecordFieldType::Blob(usize::MAX)
+            .value(&[1, 2, 3], 1)
+            .is_err());
+        assert!(RecordFieldType::String(4).value(&[97, 98, 99], 0).is_err());
+        assert_eq!(
+            Some(Value::String(Cow::from(""))),
+            RecordFieldType::String(0).value(&[97, 98, 99], 3).unwrap()
+        );
+        assert_eq!(
+            Some(Value::Float(1.0)),
+            RecordFieldType::Float
+                .value(&[0b00111111, 0b11110000, 0, 0, 0, 0, 0, 0], 0)
+                .unwrap()
+        );
+        assert!(RecordFieldType::Float
+            .value(&[0b00111110, 0b00100000, 0, 0], 0)
+            .is_err());
     }
 
     #[test]
-    fn parse_record_field_type_tests() -> () {
+    fn parse_record_field_type_tests() {
         assert_eq!(RecordFieldType::Null, RecordFieldType::parse(0).unwrap());
         assert_eq!(RecordFieldType::I8, RecordFieldType::parse(1).unwrap());
         assert_eq!(RecordFieldType::I16, RecordFieldType::parse(2).unwrap());
@@ -126,7 +239,10 @@ mod test {
         assert_eq!(RecordFieldType::Float, RecordFieldType::parse(7).unwrap());
         assert_eq!(RecordFieldType::Zero, RecordFieldType::parse(8).unwrap());
         assert_eq!(RecordFieldType::One, RecordFieldType::parse(9).unwrap());
-        assert!(RecordFieldType::parse(10).is_err());
+        assert_eq!(
+            Err(SqlrError::UnsupportedSerialType(10)),
+            RecordFieldType::parse(10)
+        );
         assert!(RecordFieldType::parse(11).is_err());
         assert_eq!(
             RecordFieldType::Blob(0),
@@ -147,7 +263,74 @@ mod test {
     }
 
     #[test]
-    fn record_field_type_size_tests() -> () {
+    fn serial_type_tests() {
+        for n in (0..10).chain(12..20) {
+            assert_eq!(n, RecordFieldType::parse(n).unwrap().serial_type());
+        }
+    }
+
+    #[test]
+    fn for_value_tests() {
+        assert_eq!(
+            RecordFieldType::Null,
+            RecordFieldType::for_value(&Value::Null)
+        );
+        assert_eq!(
+            RecordFieldType::Zero,
+            RecordFieldType::for_value(&Value::Int(0))
+        );
+        assert_eq!(
+            RecordFieldType::One,
+            RecordFieldType::for_value(&Value::Int(1))
+        );
+        assert_eq!(
+            RecordFieldType::I8,
+            RecordFieldType::for_value(&Value::Int(2))
+        );
+        assert_eq!(
+            RecordFieldType::I8,
+            RecordFieldType::for_value(&Value::Int(-128))
+        );
+        assert_eq!(
+            RecordFieldType::I16,
+            RecordFieldType::for_value(&Value::Int(128))
+        );
+        assert_eq!(
+            RecordFieldType::I24,
+            RecordFieldType::for_value(&Value::Int(-32769))
+        );
+        assert_eq!(
+            RecordFieldType::I32,
+            RecordFieldType::for_value(&Value::Int(1 << 23))
+        );
+        assert_eq!(
+            RecordFieldType::I48,
+            RecordFieldType::for_value(&Value::Int(1 << 31))
+        );
+        assert_eq!(
+            RecordFieldType::I64,
+            RecordFieldType::for_value(&Value::Int(1 << 47))
+        );
+        assert_eq!(
+            RecordFieldType::I64,
+            RecordFieldType::for_value(&Value::Int(i64::MIN))
+        );
+        assert_eq!(
+            RecordFieldType::Float,
+            RecordFieldType::for_value(&Value::Float(0.0))
+        );
+        assert_eq!(
+            RecordFieldType::String(3),
+            RecordFieldType::for_value(&Value::String(Cow::from("abc")))
+        );
+        assert_eq!(
+            RecordFieldType::Blob(2),
+            RecordFieldType::for_value(&Value::Blob(Cow::from(&[1, 2][..])))
+        );
+    }
+
+    #[test]
+    fn record_field_type_size_tests() {
         assert_eq!(RecordFieldType::Null.size(), 0);
         assert_eq!(RecordFieldType::I8.size(), 1);
         assert_eq!(RecordFieldType::I16.size(), 2);
@@ -1,4 +1,4 @@
-use crate::utils;
+use crate::{error::SqlrError, utils};
 
 use super::{record_field::RecordField, record_field_type::RecordFieldType};
 
@@ -8,11 +8,16 @@ pub struct RecordHeader {
 }
 
 impl RecordHeader {
-    pub fn parse(mut buffer: &[u8]) -> anyhow::Result<RecordHeader> {
+    // the buffer is expected to be the whole payload so that field bounds can be checked
+    pub fn parse(mut buffer: &[u8]) -> crate::error::Result<RecordHeader> {
+        let payload_length = buffer.len();
         let (varint_size, header_length) = utils::read_varint_at(buffer, 0);
 
         if header_length < varint_size as i64 || buffer.len() < header_length as usize {
-            anyhow::bail!("header length too large")
+            return Err(SqlrError::InvalidRecordHeader {
+                header_length,
+                payload_length: buffer.len(),
+            });
         }
 
         buffer = &buffer[varint_size as usize..header_length as usize];
@@ -27,6 +32,19 @@ impl RecordHeader {
             let field_type = RecordFieldType::parse(discriminant)?;
             let field_size = field_type.size();
 
+            // stops as soon as a field goes past the payload instead of piling up fields that
+            // would fail when read
+            if current_offset
+                .checked_add(field_size)
+                .is_none_or(|end| end > payload_length)
+            {
+                return Err(SqlrError::FieldOutOfBounds {
+                    offset: current_offset,
+                    size: field_size,
+                    payload_length,
+                });
+            }
+
             fields.push(RecordField {
                 offset: current_offset,
                 field_type,
@@ -37,6 +55,19 @@ impl RecordHeader {
 
         Ok(RecordHeader { fields })
     }
+
+    // number of fields in the record, which can be fewer than the table's columns
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn field_type(&self, i: usize) -> Option<RecordFieldType> {
+        self.fields.get(i).map(|f| f.field_type)
+    }
 }
 
 #[cfg(test)]
@@ -44,10 +75,19 @@ mod test {
     use super::*;
 
     #[test]
-    fn record_header_parse_tests() -> () {
-        assert!(RecordHeader::parse(&vec![0b10000001, 0b01111111]).is_err());
+    fn record_header_parse_tests() {
+        assert_eq!(
+            Err(SqlrError::InvalidRecordHeader {
+                header_length: 255,
+                payload_length: 2
+            }),
+            RecordHeader::parse(&[0b10000001, 0b01111111])
+        );
         // 10 record field type unsupported
-        assert!(RecordHeader::parse(&[2, 10]).is_err());
+        assert_eq!(
+            Err(SqlrError::UnsupportedSerialType(10)),
+            RecordHeader::parse(&[2, 10])
+        );
         assert_eq!(
             RecordHeader {
                 fields: vec![RecordField {
@@ -58,4 +98,49 @@ mod test {
             RecordHeader::parse(&[2, 8]).unwrap()
         );
     }
+
+    #[test]
+    fn record_header_shape_tests() {
+        // a null, a 1-byte int and the string "a"
+        let header = RecordHeader::parse(&[4, 0, 1, 15, 7, 97]).unwrap();
+        assert_eq!(3, header.len());
+        assert!(!header.is_empty());
+        assert_eq!(Some(RecordFieldType::Null), header.field_type(0));
+        assert_eq!(Some(RecordFieldType::I8), header.field_type(1));
+        assert_eq!(Some(RecordFieldType::String(1)), header.field_type(2));
+        assert_eq!(None, header.field_type(3));
+
+        let header = RecordHeader::parse(&[1]).unwrap();
+        assert_eq!(0, header.len());
+        assert!(header.is_empty());
+    }
+
+    #[test]
+    fn record_header_parse_out_of_bounds_tests() {
+        // an 8-byte integer in a 3-byte payload
+        assert_eq!(
+            Err(SqlrError::FieldOutOfBounds {
+                offset: 2,
+                size: 8,
+                payload_length: 3
+            }),
+            RecordHeader::parse(&[2, 6, 1])
+        );
+        // a string whose length doesn't fit in usize arithmetic
+        let mut payload = vec![10];
+        payload.extend(crate::utils::write_varint(i64::MAX));
+        assert!(RecordHeader::parse(&payload).is_err());
+
+        // a long header of tiny fields where only the last one overflows
+        let mut payload = vec![101];
+        payload.extend([1; 99]);
+        payload.push(2);
+        payload.extend([0; 100]);
+        assert!(matches!(
+            RecordHeader::parse(&payload),
+            Err(SqlrError::FieldOutOfBounds { offset: 200, .. })
+        ));
+        payload.push(0);
+        assert_eq!(100, RecordHeader::parse(&payload).unwrap().fields.len());
+    }
 }
@@ -0,0 +1,119 @@
+use crate::{utils, value::Value};
+
+use super::record_field_type::RecordFieldType;
+
+// inverse of RecordHeader::parse and RecordFieldType::value, every value is stored with the
+// smallest serial type that can hold it
+pub fn serialize(values: &[Value]) -> Vec<u8> {
+    let field_types = values
+        .iter()
+        .map(RecordFieldType::for_value)
+        .collect::<Vec<_>>();
+
+    let mut serial_types = Vec::new();
+    for field_type in &field_types {
+        utils::write_varint_into(&mut serial_types, field_type.serial_type());
+    }
+
+    // the header length counts its own varint, which can push it over a varint size boundary
+    let mut header_length = serial_types.len() + 1;
+    while utils::write_varint(header_length as i64).len() + serial_types.len() != header_length {
+        header_length += 1;
+    }
+
+    let body_length = field_types.iter().map(|t| t.size()).sum::<usize>();
+    let mut buffer = Vec::with_capacity(header_length + body_length);
+    utils::write_varint_into(&mut buffer, header_length as i64);
+    buffer.extend_from_slice(&serial_types);
+
+    for (value, field_type) in values.iter().zip(field_types) {
+        match value {
+            Value::Null => (),
+            Value::Int(n) => {
+                let size = field_type.size();
+                buffer.extend_from_slice(&n.to_be_bytes()[8 - size..]);
+            }
+            Value::Float(f) => buffer.extend_from_slice(&f.to_be_bytes()),
+            Value::String(s) => buffer.extend_from_slice(s.as_bytes()),
+            Value::Blob(b) => buffer.extend_from_slice(b),
+        }
+    }
+
+    buffer
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use crate::record::record_header::RecordHeader;
+
+    use super::*;
+
+    fn assert_round_trip(values: &[Value]) {
+        let payload = serialize(values);
+        let header = RecordHeader::parse(&payload).unwrap();
+        let parsed = header
+            .fields
+            .iter()
+            .map(|f| f.field_type.value(&payload, f.offset).unwrap().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(values, parsed);
+        assert_eq!(
+            payload.len(),
+            header
+                .fields
+                .last()
+                .map_or(payload.len(), |f| f.offset + f.field_type.size())
+        );
+    }
+
+    #[test]
+    fn serialize_tests() {
+        assert_eq!(vec![1], serialize(&[]));
+        // matches the first row of tbl1 in test.db
+        assert_eq!(
+            vec![3, 25, 1, 104, 101, 108, 108, 111, 33, 10],
+            serialize(&[Value::String(Cow::from("hello!")), Value::Int(10)])
+        );
+        assert_eq!(
+            vec![4, 0, 8, 9],
+            serialize(&[Value::Null, Value::Int(0), Value::Int(1)])
+        );
+        assert_eq!(vec![2, 2, 0xff, 0x7f], serialize(&[Value::Int(-129)]));
+    }
+
+    #[test]
+    fn serialize_round_trip_tests() {
+        let values = vec![
+            Value::Null,
+            Value::Int(0),
+            Value::Int(1),
+            Value::Int(-1),
+            Value::Int(200),
+            Value::Int(-40000),
+            Value::Int(1 << 30),
+            Value::Int(-(1 << 40)),
+            Value::Int(i64::MAX),
+            Value::Int(i64::MIN),
+            Value::Float(1.5),
+            Value::Float(-0.0),
+            Value::String(Cow::from("")),
+            Value::String(Cow::from("héllo")),
+            Value::Blob(Cow::from(&[0xde, 0xad, 0xbe, 0xef][..])),
+        ];
+        assert_round_trip(&values);
+    }
+
+    #[test]
+    fn serialize_long_header_tests() {
+        // 127 bytes of serial types need a 2-byte header length varint
+        let values = vec![Value::Int(2); 127];
+        assert_eq!(vec![0x81, 0x01], serialize(&values)[..2].to_vec());
+        assert_round_trip(&values);
+
+        let values = vec![Value::Int(2); 126];
+        assert_eq!(127, serialize(&values)[0]);
+        assert_round_trip(&values);
+    }
+}
@@ -0,0 +1,124 @@
+use std::marker::PhantomData;
+
+use anyhow::Context;
+
+use crate::{
+    cursor::Cursor,
+    scanner::Scanner,
+    value::{FromValue, Value},
+};
+
+// a rust type the first columns of a record can be decoded into, implemented for tuples of up to
+// 8 values, e.g. (i64, String, Option<f64>)
+pub trait FromRow: Sized {
+    fn from_row(record: &Cursor) -> anyhow::Result<Self>;
+}
+
+macro_rules! tuple_from_row {
+    ($($t:ident $n:tt),+) => {
+        impl<$($t: FromValue),+> FromRow for ($($t,)+) {
+            fn from_row(record: &Cursor) -> anyhow::Result<Self> {
+                Ok(($(column::<$t>(record, $n)?,)+))
+            }
+        }
+    };
+}
+
+tuple_from_row!(A 0);
+tuple_from_row!(A 0, B 1);
+tuple_from_row!(A 0, B 1, C 2);
+tuple_from_row!(A 0, B 1, C 2, D 3);
+tuple_from_row!(A 0, B 1, C 2, D 3, E 4);
+tuple_from_row!(A 0, B 1, C 2, D 3, E 4, F 5);
+tuple_from_row!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+tuple_from_row!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+// like in queries, a column missing from the record is null
+fn column<T: FromValue>(record: &Cursor, n: usize) -> anyhow::Result<T> {
+    let value = record.field(n)?.unwrap_or(Value::Null);
+    T::from_value(value).with_context(|| format!("record {}: column {}", record.row_id, n))
+}
+
+// the records of a scan decoded into T, see Scanner::rows, the iteration stops after an error
+pub struct Rows<'s, 'p, T> {
+    scanner: &'s mut Scanner<'p>,
+    done: bool,
+    row: PhantomData<T>,
+}
+
+impl<'s, 'p, T: FromRow> Rows<'s, 'p, T> {
+    pub fn new(scanner: &'s mut Scanner<'p>) -> Self {
+        Rows {
+            scanner,
+            done: false,
+            row: PhantomData,
+        }
+    }
+}
+
+impl<T: FromRow> Iterator for Rows<'_, '_, T> {
+    type Item = anyhow::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let row = match self.scanner.next_record() {
+            Ok(record) => record.map(|r| T::from_row(&r)),
+            Err(e) => Some(Err(e)),
+        };
+        self.done = !matches!(row, Some(Ok(_)));
+        row
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use crate::record::{record_header::RecordHeader, serialize};
+
+    use super::*;
+
+    fn record(values: &[Value]) -> Cursor {
+        let payload = serialize(values);
+        Cursor::new(RecordHeader::parse(&payload).unwrap(), payload, 7)
+    }
+
+    #[test]
+    fn from_row_tests() {
+        let record = record(&[
+            Value::Int(1),
+            Value::String(Cow::from("a")),
+            Value::Float(2.5),
+            Value::Null,
+        ]);
+        assert_eq!((1,), <(i64,)>::from_row(&record).unwrap());
+        assert_eq!(
+            (1, "a".to_string(), 2.5, None),
+            <(i64, String, f64, Option<i64>)>::from_row(&record).unwrap()
+        );
+        // columns past the end of the record are null
+        assert_eq!(
+            (1, "a".to_string(), 2.5, None, None, None, None, None),
+            <(
+                i64,
+                String,
+                f64,
+                Option<i64>,
+                Option<i64>,
+                Option<i64>,
+                Option<i64>,
+                Option<String>
+            )>::from_row(&record)
+            .unwrap()
+        );
+
+        let err = <(i64, i64)>::from_row(&record).unwrap_err();
+        assert_eq!(
+            "record 7: column 1: expected an integer, got text",
+            format!("{:#}", err)
+        );
+        assert!(<(i64, String, f64, i64)>::from_row(&record).is_err());
+    }
+}
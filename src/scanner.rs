@@ -1,7 +1,13 @@
+use std::rc::Rc;
+
+use anyhow::Context;
+
 use crate::{
     cursor::Cursor,
     paging::{cell::Cell, pager::Pager, positioned_page::PositionedPage},
     record::record_header::RecordHeader,
+    row::{FromRow, Rows},
+    value::Value,
 };
 
 #[derive(Debug)]
@@ -14,7 +20,9 @@ pub struct Scanner<'p> {
     pager: &'p mut dyn Pager,
     initial_page_num: usize,
     page_stack: Vec<PositionedPage>,
-    current_page_pointer: Option<u32>,
+    rowid_alias: Option<usize>,
+    column_order: Option<Rc<[usize]>>,
+    defaults: Option<Rc<[Value<'static>]>>,
 }
 
 impl<'p> Scanner<'p> {
@@ -23,40 +31,72 @@ impl<'p> Scanner<'p> {
             pager,
             initial_page_num,
             page_stack: Vec::new(),
-            current_page_pointer: None,
+            rowid_alias: None,
+            column_order: None,
+            defaults: None,
         }
     }
 
+    // resolves the given column to the row id in every cursor produced
+    pub fn with_rowid_alias(mut self, rowid_alias: Option<usize>) -> Scanner<'p> {
+        self.rowid_alias = rowid_alias;
+        self
+    }
+
+    // maps columns to record fields in every cursor produced
+    pub fn with_column_order(mut self, column_order: Option<Vec<usize>>) -> Scanner<'p> {
+        self.column_order = column_order.map(Rc::from);
+        self
+    }
+
+    // pads records missing trailing columns with the given values, one per column, and rejects
+    // records with more fields than there are columns
+    pub fn with_defaults(mut self, defaults: Option<Vec<Value<'static>>>) -> Scanner<'p> {
+        self.defaults = defaults.map(Rc::from);
+        self
+    }
+
+    // starts the scan over from the root page, pages already read stay in the pager's cache
+    pub fn reset(&mut self) {
+        self.page_stack.clear();
+    }
+
     pub fn next_record(&mut self) -> anyhow::Result<Option<Cursor>> {
         loop {
-            match self.next_elem() {
-                Ok(Some(ScannerElem::Cursor(cursor))) => return Ok(Some(cursor)),
-                Ok(Some(ScannerElem::PagePointer(page_pointer))) => {
-                    // TODO: remove clone
-                    let new_page = self.pager.read_page(page_pointer as usize)?.clone();
+            match self.next_elem()? {
+                Some(ScannerElem::Cursor(cursor)) => return Ok(Some(cursor)),
+                Some(ScannerElem::PagePointer(page_pointer)) => {
+                    let page_num = page_pointer as usize;
+                    let page = self.pager.read_page(page_num)?;
                     self.page_stack.push(PositionedPage {
-                        page: new_page,
+                        page,
+                        page_num,
                         cell_num: 0,
                     });
-                    if self.current_page_pointer == Some(page_pointer) {
-                        return Ok(None);
-                    } else {
-                        self.current_page_pointer = Some(page_pointer);
-                    }
                 }
-                Ok(None) if self.page_stack.len() > 1 => {
+                None if self.page_stack.len() > 1 => {
                     self.page_stack.pop();
+                    if let Some(cursor) = self.parent_record()? {
+                        return Ok(Some(cursor));
+                    }
                 }
-                Ok(None) => return Ok(None),
-                Err(e) => return Err(e),
+                None => return Ok(None),
             }
         }
     }
 
+    // decodes the first columns of every record into T, e.g. scanner.rows::<(i64, String)>(),
+    // next_record is still there for records whose columns aren't known upfront
+    pub fn rows<T: FromRow>(&mut self) -> Rows<'_, 'p, T> {
+        Rows::new(self)
+    }
+
     fn next_elem(&mut self) -> anyhow::Result<Option<ScannerElem>> {
+        let page_size = self.pager.page_size();
         let Some(pos_page) = self.current_page()? else {
             return Ok(None);
         };
+        let location = cell_location(pos_page, pos_page.cell_num, page_size);
 
         if let Some(page_pointer) = pos_page.next_page_pointer() {
             return Ok(Some(ScannerElem::PagePointer(page_pointer)));
@@ -68,37 +108,86 @@ impl<'p> Scanner<'p> {
 
         match cell {
             Cell::TableLeaf(leaf) => {
-                let header = RecordHeader::parse(&leaf.payload)?;
                 // TODO: remove clone
-                Ok(Some(ScannerElem::Cursor(Cursor::new(
-                    header,
-                    leaf.payload.clone(),
-                ))))
+                let (payload, row_id) = (leaf.payload.clone(), leaf.row_id);
+                let cursor = self.cursor(payload, row_id).with_context(location)?;
+                Ok(Some(ScannerElem::Cursor(cursor)))
+            }
+            Cell::IndexLeaf(leaf) => {
+                let payload = leaf.payload.clone();
+                let cursor = self.cursor(payload, 0).with_context(location)?;
+                Ok(Some(ScannerElem::Cursor(cursor)))
             }
             Cell::TableInterior(interior) => {
                 Ok(Some(ScannerElem::PagePointer(interior.left_child_page)))
             }
+            Cell::IndexInterior(interior) => {
+                Ok(Some(ScannerElem::PagePointer(interior.left_child_page)))
+            }
         }
     }
 
+    // index interior cells hold a record which comes right after their left subtree
+    fn parent_record(&self) -> anyhow::Result<Option<Cursor>> {
+        let Some(parent) = self.page_stack.last() else {
+            return Ok(None);
+        };
+        match parent.previous_cell() {
+            Some(Cell::IndexInterior(interior)) => {
+                let cell_num = parent.cell_num - 1;
+                let location = cell_location(parent, cell_num, self.pager.page_size());
+                let cursor = self
+                    .cursor(interior.payload.clone(), 0)
+                    .with_context(location)?;
+                Ok(Some(cursor))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn cursor(&self, payload: Vec<u8>, row_id: i64) -> anyhow::Result<Cursor> {
+        let header = RecordHeader::parse(&payload)?;
+        let cursor = Cursor::new(header, payload, row_id)
+            .with_rowid_alias(self.rowid_alias)
+            .with_column_order(self.column_order.clone())
+            .with_defaults(self.defaults.clone());
+        cursor.check_field_count()?;
+        Ok(cursor)
+    }
+
     fn current_page(&mut self) -> anyhow::Result<Option<&mut PositionedPage>> {
         if self.page_stack.is_empty() {
-            let page = match self.pager.read_page(self.initial_page_num) {
-                Ok(page) => page.clone(),
-                Err(e) => return Err(e),
-            };
-
-            self.page_stack.push(PositionedPage { page, cell_num: 0 });
+            let page = self.pager.read_page(self.initial_page_num)?;
+            self.page_stack.push(PositionedPage {
+                page,
+                page_num: self.initial_page_num,
+                cell_num: 0,
+            });
         }
         Ok(self.page_stack.last_mut())
     }
 }
 
+// describes where the record of a cell lies, for errors about records which can't be parsed
+fn cell_location(
+    pos_page: &PositionedPage,
+    cell_num: usize,
+    page_size: usize,
+) -> impl FnOnce() -> String {
+    let page_num = pos_page.page_num;
+    let offset = pos_page.cell_file_offset(cell_num, page_size);
+    move || match offset {
+        Some(offset) => format!("record in page {} at file offset {}", page_num, offset),
+        None => format!("record in page {}", page_num),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::collections::{hash_map::Entry, HashMap};
 
     use crate::{
+        error::SqlrError,
         paging::{
             cell::{TableInteriorCell, TableLeafCell},
             page::Page,
@@ -111,7 +200,7 @@ mod test {
     use super::*;
 
     #[test]
-    fn next_elem_tests() -> () {
+    fn next_elem_tests() {
         // points to the rightmost pointer
         let int_header = PageHeader::TableInteriorPageHeader {
             first_freeblock: 0,
@@ -163,6 +252,8 @@ mod test {
                 payload: vec![2, 8],
             }
             .into()],
+            free_blocks: vec![],
+            header_offset: 0,
         };
         let mut pager = MockPager {
             reader: |_| Ok(leaf_page.clone()),
@@ -172,7 +263,9 @@ mod test {
         let next_elem = scanner.next_elem();
         assert!(next_elem.is_ok());
         match next_elem.unwrap() {
-            Some(ScannerElem::Cursor(Cursor { header, payload })) => {
+            Some(ScannerElem::Cursor(Cursor {
+                header, payload, ..
+            })) => {
                 assert_eq!(
                     RecordHeader {
                         fields: vec![RecordField {
@@ -196,6 +289,8 @@ mod test {
                 key: 0,
             }
             .into()],
+            free_blocks: vec![],
+            header_offset: 0,
         };
         let mut pager = MockPager {
             reader: |_| Ok(leaf_page.clone()),
@@ -210,11 +305,55 @@ mod test {
         }
     }
 
+    #[test]
+    fn rows_tests() {
+        let mut db = crate::db::Db::from_file("test_rowid.db").unwrap();
+        let mut scanner = db.scanner_for("people").unwrap();
+        let rows = scanner
+            .rows::<(i64, String, Option<i64>)>()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            vec![
+                (1, "alice".to_string(), Some(30)),
+                (5, "bob".to_string(), None),
+                (42, "carol".to_string(), Some(25)),
+            ],
+            rows
+        );
+
+        // bob has no age, nothing is decoded past the error
+        scanner.reset();
+        let rows = scanner.rows::<(i64, String, i64)>().collect::<Vec<_>>();
+        assert_eq!(2, rows.len());
+        assert_eq!(
+            "record 5: column 2: expected an integer, got null",
+            format!("{:#}", rows[1].as_ref().unwrap_err())
+        );
+    }
+
+    #[test]
+    fn next_record_error_offset_tests() {
+        let mut bytes = std::fs::read("test.db").unwrap();
+        // the header of the record of the cell at 4084 on page 2 claims more than its payload
+        bytes[4096 + 4086] = 50;
+        let mut pager = FilePager::new(std::io::Cursor::new(bytes), 4096);
+        let mut scanner = Scanner::new(&mut pager, 2);
+        let err = scanner.next_record().unwrap_err();
+        assert_eq!("record in page 2 at file offset 8180", err.to_string());
+        assert!(matches!(
+            err.downcast_ref::<SqlrError>(),
+            Some(SqlrError::InvalidRecordHeader { .. })
+        ));
+    }
+
     fn empty_page(header: PageHeader) -> Page {
         Page {
-            header: header,
+            header,
             cell_pointers: vec![],
             cells: vec![],
+            free_blocks: vec![],
+            header_offset: 0,
         }
     }
 
@@ -223,7 +362,7 @@ mod test {
         F: Fn(usize) -> anyhow::Result<Page>,
     {
         reader: F,
-        pages: HashMap<usize, Page>,
+        pages: HashMap<usize, Rc<Page>>,
     }
 
     impl<F> Pager for MockPager<F>
@@ -234,38 +373,74 @@ mod test {
             (self.reader)(page_num)
         }
 
-        fn read_page<'a>(&'a mut self, page_num: usize) -> anyhow::Result<&'a Page> {
+        fn read_page(&mut self, page_num: usize) -> anyhow::Result<Rc<Page>> {
             if let Entry::Vacant(_) = self.pages.entry(page_num) {
-                let page = self.load_page(page_num)?;
+                let page = Rc::new(self.load_page(page_num)?);
                 self.pages.insert(page_num, page);
             }
-            Ok(self.pages.get(&page_num).unwrap())
+            Ok(Rc::clone(self.pages.get(&page_num).unwrap()))
+        }
+
+        fn load_raw_page(&mut self, _page_num: usize) -> anyhow::Result<Vec<u8>> {
+            anyhow::bail!("raw pages are not supported by the mock pager")
+        }
+
+        fn page_size(&self) -> usize {
+            4096
+        }
+
+        fn input_size(&mut self) -> anyhow::Result<u64> {
+            Ok(0)
+        }
+
+        fn clear_cache(&mut self) {
+            self.pages.clear();
+        }
+
+        fn invalidate(&mut self, page_num: usize) {
+            self.pages.remove(&page_num);
         }
     }
 
     #[test]
-    fn current_page_tests() -> () {
+    fn current_page_tests() {
         let file = std::fs::File::open("test.db").unwrap();
         let mut pager = FilePager::new(file, 4096);
 
         let mut scanner1 = Scanner::new(&mut pager, 10);
         assert!(scanner1.current_page().is_err());
 
-        let mut scanner2 = Scanner::new(&mut pager, 0);
+        let mut scanner2 = Scanner::new(&mut pager, 2);
         let current_page = scanner2.current_page();
         assert!(current_page.is_ok());
         assert_eq!(
             Some(PositionedPage {
-                page: Page {
-                    header: crate::paging::page_header::PageHeader::TableLeafPageHeader {
+                page: Rc::new(Page {
+                    header: PageHeader::TableLeafPageHeader {
                         first_freeblock: 0,
-                        cell_count: 0,
-                        cell_content_offset: 0,
+                        cell_count: 2,
+                        cell_content_offset: 4071,
                         fragmented_bytes_count: 0
                     },
-                    cell_pointers: vec![],
-                    cells: vec![],
-                },
+                    cell_pointers: vec![4084, 4071],
+                    cells: vec![
+                        TableLeafCell {
+                            size: 10,
+                            row_id: 1,
+                            payload: vec![3, 25, 1, 104, 101, 108, 108, 111, 33, 10],
+                        }
+                        .into(),
+                        TableLeafCell {
+                            size: 11,
+                            row_id: 2,
+                            payload: vec![3, 27, 1, 103, 111, 111, 100, 98, 121, 101, 20],
+                        }
+                        .into(),
+                    ],
+                    free_blocks: vec![],
+                    header_offset: 0,
+                }),
+                page_num: 2,
                 cell_num: 0,
             }),
             current_page.unwrap().cloned()
@@ -0,0 +1,99 @@
+use crate::value::{Affinity, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnDef {
+    pub name: String,
+    // declared type as written, e.g. VARCHAR(10), None if omitted
+    pub type_name: Option<String>,
+    // derived from the declared type
+    pub affinity: Affinity,
+    // only literal defaults are kept, None for expressions and for columns without one
+    pub default: Option<Value<'static>>,
+    pub primary_key: bool,
+    // COLLATE given in the definition, None when not given, i.e. BINARY
+    pub collation: Option<String>,
+}
+
+// https://www.sqlite.org/datatype3.html#determination_of_column_affinity, the rules apply in order
+// so that e.g. FLOATING POINT has integer affinity
+pub fn affinity_of(declared_type: &str) -> Affinity {
+    let declared_type = declared_type.to_ascii_uppercase();
+    let contains = |words: &[&str]| words.iter().any(|w| declared_type.contains(w));
+    if contains(&["INT"]) {
+        Affinity::Integer
+    } else if contains(&["CHAR", "CLOB", "TEXT"]) {
+        Affinity::Text
+    } else if contains(&["BLOB"]) || declared_type.is_empty() {
+        Affinity::Blob
+    } else if contains(&["REAL", "FLOA", "DOUB"]) {
+        Affinity::Real
+    } else {
+        Affinity::Numeric
+    }
+}
+
+impl ColumnDef {
+    // an INTEGER PRIMARY KEY column is stored as null in records, its value is the row id
+    pub fn is_integer_primary_key(&self) -> bool {
+        self.primary_key
+            && self
+                .type_name
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case("INTEGER"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn is_integer_primary_key_tests() {
+        let mut column = ColumnDef {
+            name: "id".to_string(),
+            type_name: Some("integer".to_string()),
+            affinity: Affinity::Integer,
+            default: None,
+            primary_key: true,
+            collation: None,
+        };
+        assert!(column.is_integer_primary_key());
+        column.type_name = Some("INT".to_string());
+        assert!(!column.is_integer_primary_key());
+        column.type_name = None;
+        assert!(!column.is_integer_primary_key());
+        column.type_name = Some("INTEGER".to_string());
+        column.primary_key = false;
+        assert!(!column.is_integer_primary_key());
+    }
+
+    #[test]
+    fn affinity_of_tests() {
+        for (declared_type, affinity) in [
+            ("INTEGER", Affinity::Integer),
+            ("unsigned big int", Affinity::Integer),
+            ("TINYINT", Affinity::Integer),
+            ("VARCHAR(10)", Affinity::Text),
+            ("native character(70)", Affinity::Text),
+            ("CLOB", Affinity::Text),
+            ("text", Affinity::Text),
+            ("BLOB", Affinity::Blob),
+            ("", Affinity::Blob),
+            ("REAL", Affinity::Real),
+            ("double precision", Affinity::Real),
+            ("FLOAT", Affinity::Real),
+            ("NUMERIC", Affinity::Numeric),
+            ("DECIMAL(10,5)", Affinity::Numeric),
+            ("BOOLEAN", Affinity::Numeric),
+            ("DATETIME", Affinity::Numeric),
+            // the first matching rule wins
+            ("FLOATING POINT", Affinity::Integer),
+            ("CHARINT", Affinity::Integer),
+            ("BLOBTEXT", Affinity::Text),
+            // STRING matches no rule
+            ("STRING", Affinity::Numeric),
+        ] {
+            assert_eq!(affinity, affinity_of(declared_type), "{}", declared_type);
+        }
+    }
+}
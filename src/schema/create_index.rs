@@ -0,0 +1,158 @@
+use crate::sql::{token::Token, token_stream::TokenStream};
+
+use super::{create_table::parse_name, index_def::IndexDef};
+
+// https://www.sqlite.org/lang_createindex.html
+pub fn parse_create_index(sql: &str) -> anyhow::Result<IndexDef> {
+    let mut stream = TokenStream::parse(sql)?;
+
+    stream.expect_keyword("CREATE")?;
+    let unique = stream.next_if_keyword("UNIQUE");
+    stream.expect_keyword("INDEX")?;
+    if stream.next_if_keyword("IF") {
+        stream.expect_keyword("NOT")?;
+        stream.expect_keyword("EXISTS")?;
+    }
+    let mut name = parse_name(&mut stream)?;
+    if stream.next_if_token(&Token::Dot) {
+        name = parse_name(&mut stream)?;
+    }
+    stream.expect_keyword("ON")?;
+    let table = parse_name(&mut stream)?;
+    stream.expect_token(&Token::LeftParen)?;
+
+    let mut columns = Vec::new();
+    let mut collations = Vec::new();
+    let mut descending = Vec::new();
+    loop {
+        let (column, collation, desc) = parse_indexed_column(&mut stream)?;
+        columns.push(column);
+        collations.push(collation);
+        descending.push(desc);
+        match stream.next() {
+            Some(Token::Comma) => continue,
+            Some(Token::RightParen) => break,
+            other => anyhow::bail!("expected ',' or ')' in column list, got {:?}", other),
+        }
+    }
+
+    let partial = stream.next_if_keyword("WHERE");
+    if !partial && !stream.is_empty() {
+        anyhow::bail!("unexpected {:?} after column list", stream.peek());
+    }
+
+    Ok(IndexDef {
+        name,
+        table,
+        columns,
+        collations,
+        descending,
+        unique,
+        partial,
+    })
+}
+
+// (column | expr) [COLLATE name] [ASC|DESC], the column is None for an expression
+fn parse_indexed_column(
+    stream: &mut TokenStream,
+) -> anyhow::Result<(Option<String>, Option<String>, bool)> {
+    let name = if stream.peek_nth(1).is_some_and(ends_indexed_expr) {
+        Some(parse_name(stream)?)
+    } else {
+        skip_expr(stream)?;
+        None
+    };
+    let collation = if stream.next_if_keyword("COLLATE") {
+        Some(parse_name(stream)?)
+    } else {
+        None
+    };
+    let descending = stream.next_if_keyword("DESC");
+    if !descending {
+        stream.next_if_keyword("ASC");
+    }
+    Ok((name, collation, descending))
+}
+
+fn ends_indexed_expr(token: &Token) -> bool {
+    matches!(token, Token::Comma | Token::RightParen)
+        || ["COLLATE", "ASC", "DESC"]
+            .iter()
+            .any(|k| token.is_keyword(k))
+}
+
+// expressions are only listed, never evaluated, so their tokens are skipped up to the end of the
+// indexed column
+fn skip_expr(stream: &mut TokenStream) -> anyhow::Result<()> {
+    let mut depth = 0usize;
+    let mut empty = true;
+    loop {
+        match stream.peek() {
+            None => anyhow::bail!("expected ',' or ')' in column list, got end of input"),
+            Some(t) if depth == 0 && ends_indexed_expr(t) => {
+                if empty {
+                    anyhow::bail!("expected column or expression, got {:?}", t);
+                }
+                return Ok(());
+            }
+            Some(Token::LeftParen) => depth += 1,
+            Some(Token::RightParen) => depth -= 1,
+            Some(_) => {}
+        }
+        stream.next();
+        empty = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_create_index_tests() {
+        assert_eq!(
+            IndexDef {
+                name: "idx".to_string(),
+                table: "t".to_string(),
+                columns: vec![Some("a".to_string())],
+                collations: vec![None],
+                descending: vec![false],
+                unique: false,
+                partial: false,
+            },
+            parse_create_index("CREATE INDEX idx ON t(a)").unwrap()
+        );
+        assert_eq!(
+            IndexDef {
+                name: "my idx".to_string(),
+                table: "t".to_string(),
+                columns: vec![Some("a".to_string()), Some("b c".to_string())],
+                collations: vec![Some("NOCASE".to_string()), None],
+                descending: vec![true, false],
+                unique: true,
+                partial: true,
+            },
+            parse_create_index(
+                "create unique index if not exists main.\"my idx\"
+                    on [t] (a COLLATE NOCASE DESC, `b c` asc) WHERE a > 0"
+            )
+            .unwrap()
+        );
+        // expressions have no column name
+        let index_def =
+            parse_create_index("CREATE INDEX idx ON t(lower(a) COLLATE NOCASE, a + (1), b DESC)")
+                .unwrap();
+        assert_eq!(vec![None, None, Some("b".to_string())], index_def.columns);
+        assert_eq!(
+            vec![Some("NOCASE".to_string()), None, None],
+            index_def.collations
+        );
+        assert_eq!(vec![false, false, true], index_def.descending);
+
+        assert!(parse_create_index("CREATE TABLE t(a)").is_err());
+        assert!(parse_create_index("CREATE INDEX idx ON t()").is_err());
+        assert!(parse_create_index("CREATE INDEX idx ON t(lower(a)").is_err());
+        assert!(parse_create_index("CREATE INDEX idx ON t(a").is_err());
+        assert!(parse_create_index("CREATE INDEX idx ON t(a) b").is_err());
+    }
+}
@@ -0,0 +1,419 @@
+use crate::{
+    query::parser::parse_literal,
+    sql::{token::Token, token_stream::TokenStream},
+    value::Value,
+};
+
+use super::{
+    column_def::{affinity_of, ColumnDef},
+    table_def::TableDef,
+};
+
+// keywords that end a column's type name and start its constraints
+const COLUMN_CONSTRAINT_KEYWORDS: &[&str] = &[
+    "CONSTRAINT",
+    "PRIMARY",
+    "NOT",
+    "NULL",
+    "UNIQUE",
+    "CHECK",
+    "DEFAULT",
+    "COLLATE",
+    "REFERENCES",
+    "GENERATED",
+    "AS",
+];
+
+const TABLE_CONSTRAINT_KEYWORDS: &[&str] = &["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"];
+
+// https://www.sqlite.org/lang_createtable.html
+pub fn parse_create_table(sql: &str) -> anyhow::Result<TableDef> {
+    let mut stream = TokenStream::parse(sql)?;
+
+    stream.expect_keyword("CREATE")?;
+    let _ = stream.next_if_keyword("TEMP") || stream.next_if_keyword("TEMPORARY");
+    stream.expect_keyword("TABLE")?;
+    if stream.next_if_keyword("IF") {
+        stream.expect_keyword("NOT")?;
+        stream.expect_keyword("EXISTS")?;
+    }
+    parse_name(&mut stream)?;
+    if stream.next_if_token(&Token::Dot) {
+        parse_name(&mut stream)?;
+    }
+    if stream.peek().is_some_and(|t| t.is_keyword("AS")) {
+        anyhow::bail!("CREATE TABLE ... AS SELECT is not supported");
+    }
+    stream.expect_token(&Token::LeftParen)?;
+
+    let mut columns = Vec::new();
+    let mut primary_key = Vec::new();
+    loop {
+        if is_table_constraint(&stream) {
+            parse_table_constraint(&mut stream, &columns, &mut primary_key)?;
+        } else {
+            let column = parse_column_def(&mut stream)?;
+            if column.primary_key {
+                primary_key.push(columns.len());
+            }
+            columns.push(column);
+        }
+
+        match stream.next() {
+            Some(Token::Comma) => continue,
+            Some(Token::RightParen) => break,
+            other => anyhow::bail!("expected ',' or ')' in column list, got {:?}", other),
+        }
+    }
+    for &i in &primary_key {
+        columns[i].primary_key = true;
+    }
+
+    // table options, e.g. WITHOUT ROWID, STRICT
+    let mut without_rowid = false;
+    while !stream.is_empty() {
+        if stream.next_if_keyword("WITHOUT") {
+            stream.expect_keyword("ROWID")?;
+            without_rowid = true;
+        } else {
+            stream.next();
+        }
+    }
+
+    Ok(TableDef {
+        columns,
+        primary_key,
+        without_rowid,
+    })
+}
+
+// the column aliasing the row id, only when it is the sole primary key column
+pub fn rowid_alias_index(columns: &[ColumnDef]) -> Option<usize> {
+    let mut primary_keys = columns.iter().enumerate().filter(|(_, c)| c.primary_key);
+    match (primary_keys.next(), primary_keys.next()) {
+        (Some((i, column)), None) if column.is_integer_primary_key() => Some(i),
+        _ => None,
+    }
+}
+
+// sqlite also accepts string literals where a name is expected
+pub(super) fn parse_name(stream: &mut TokenStream) -> anyhow::Result<String> {
+    match stream.next() {
+        Some(Token::String(s)) => Ok(s),
+        Some(t) => t
+            .as_identifier()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow::anyhow!("expected name, got {:?}", t)),
+        None => Err(anyhow::anyhow!("expected name, got end of input")),
+    }
+}
+
+fn is_table_constraint(stream: &TokenStream) -> bool {
+    stream
+        .peek()
+        .is_some_and(|t| TABLE_CONSTRAINT_KEYWORDS.iter().any(|k| t.is_keyword(k)))
+}
+
+fn is_column_constraint(token: &Token) -> bool {
+    COLUMN_CONSTRAINT_KEYWORDS
+        .iter()
+        .any(|k| token.is_keyword(k))
+}
+
+fn is_end_of_definition(token: Option<&Token>) -> bool {
+    matches!(token, None | Some(Token::Comma) | Some(Token::RightParen))
+}
+
+fn parse_column_def(stream: &mut TokenStream) -> anyhow::Result<ColumnDef> {
+    let name = parse_name(stream)?;
+
+    let mut type_words = Vec::new();
+    while let Some(token @ Token::Word(w)) = stream.peek() {
+        if is_column_constraint(token) {
+            break;
+        }
+        type_words.push(w.clone());
+        stream.next();
+    }
+    let mut type_name = (!type_words.is_empty()).then(|| type_words.join(" "));
+    if stream.next_if_token(&Token::LeftParen) {
+        let args = parse_type_args(stream)?;
+        type_name = Some(format!("{}({})", type_name.unwrap_or_default(), args));
+    }
+
+    let mut primary_key = false;
+    let mut default = None;
+    let mut collation = None;
+    while !is_end_of_definition(stream.peek()) {
+        if stream.next_if_keyword("PRIMARY") {
+            stream.expect_keyword("KEY")?;
+            primary_key = true;
+        } else if stream.next_if_keyword("DEFAULT") {
+            default = parse_default(stream)?;
+        } else if stream.next_if_keyword("COLLATE") {
+            collation = Some(parse_name(stream)?);
+        } else {
+            skip_token(stream)?;
+        }
+    }
+
+    let affinity = affinity_of(type_name.as_deref().unwrap_or_default());
+    Ok(ColumnDef {
+        name,
+        type_name,
+        affinity,
+        default,
+        primary_key,
+        collation,
+    })
+}
+
+// DEFAULT literal | DEFAULT (literal) | DEFAULT TRUE | DEFAULT FALSE, anything else such as
+// CURRENT_TIMESTAMP or an expression is skipped
+fn parse_default(stream: &mut TokenStream) -> anyhow::Result<Option<Value<'static>>> {
+    let mut attempt = stream.clone();
+    let parenthesized = attempt.next_if_token(&Token::LeftParen);
+    let literal = if attempt.next_if_keyword("TRUE") {
+        Some(Value::Int(1))
+    } else if attempt.next_if_keyword("FALSE") {
+        Some(Value::Int(0))
+    } else {
+        parse_literal(&mut attempt).ok()
+    };
+    if literal.is_some() && (!parenthesized || attempt.next_if_token(&Token::RightParen)) {
+        *stream = attempt;
+        return Ok(literal);
+    }
+    skip_token(stream)?;
+    Ok(None)
+}
+
+// e.g. the "10, 2" in DECIMAL(10, 2), the opening parenthesis is already consumed
+fn parse_type_args(stream: &mut TokenStream) -> anyhow::Result<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    loop {
+        match stream.next() {
+            Some(Token::RightParen) => break,
+            Some(Token::Comma) => args.push(std::mem::take(&mut current)),
+            Some(Token::Minus) => current.push('-'),
+            Some(Token::Plus) => current.push('+'),
+            Some(Token::Integer(i)) => current.push_str(&i.to_string()),
+            Some(Token::Float(f)) => current.push_str(&f.to_string()),
+            other => anyhow::bail!("unexpected {:?} in type arguments", other),
+        }
+    }
+    args.push(current);
+    Ok(args.join(","))
+}
+
+fn parse_table_constraint(
+    stream: &mut TokenStream,
+    columns: &[ColumnDef],
+    primary_key: &mut Vec<usize>,
+) -> anyhow::Result<()> {
+    if stream.next_if_keyword("CONSTRAINT") {
+        parse_name(stream)?;
+    }
+    if stream.next_if_keyword("PRIMARY") {
+        stream.expect_keyword("KEY")?;
+        stream.expect_token(&Token::LeftParen)?;
+        loop {
+            let name = parse_name(stream)?;
+            let column = columns
+                .iter()
+                .position(|c| c.name.eq_ignore_ascii_case(&name))
+                .ok_or_else(|| anyhow::anyhow!("unknown primary key column {}", name))?;
+            primary_key.push(column);
+            while !is_end_of_definition(stream.peek()) {
+                skip_token(stream)?;
+            }
+            if !stream.next_if_token(&Token::Comma) {
+                break;
+            }
+        }
+        stream.expect_token(&Token::RightParen)?;
+    }
+    while !is_end_of_definition(stream.peek()) {
+        skip_token(stream)?;
+    }
+    Ok(())
+}
+
+// skips a token, or a whole parenthesized group such as a CHECK expression
+fn skip_token(stream: &mut TokenStream) -> anyhow::Result<()> {
+    let mut depth = 0;
+    loop {
+        match stream.next() {
+            Some(Token::LeftParen) => depth += 1,
+            Some(Token::RightParen) if depth > 0 => depth -= 1,
+            Some(Token::RightParen) => anyhow::bail!("unbalanced parentheses"),
+            Some(_) => (),
+            None => anyhow::bail!("unexpected end of input"),
+        }
+        if depth == 0 {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::value::Affinity;
+
+    use super::*;
+
+    fn column(name: &str, type_name: Option<&str>, primary_key: bool) -> ColumnDef {
+        ColumnDef {
+            name: name.to_string(),
+            type_name: type_name.map(|t| t.to_string()),
+            affinity: affinity_of(type_name.unwrap_or_default()),
+            default: None,
+            primary_key,
+            collation: None,
+        }
+    }
+
+    #[test]
+    fn parse_create_table_tests() {
+        assert_eq!(
+            vec![
+                column("one", Some("text"), false),
+                column("two", Some("int"), false)
+            ],
+            parse_create_table("CREATE TABLE tbl1(one text, two int)")
+                .unwrap()
+                .columns
+        );
+        assert_eq!(
+            vec![
+                column("id", Some("INTEGER"), true),
+                ColumnDef {
+                    default: Some(Value::String("x".into())),
+                    collation: Some("NOCASE".to_string()),
+                    ..column("first name", Some("VARCHAR(10)"), false)
+                },
+                column("price", Some("DECIMAL(10,-2)"), false),
+                column("data", None, false),
+                column("kind", Some("UNSIGNED BIG INT"), false),
+            ],
+            parse_create_table(
+                "create temp table if not exists main.\"t\" (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    [first name] VARCHAR(10) NOT NULL DEFAULT 'x' COLLATE NOCASE,
+                    `price` DECIMAL(10, -2) CHECK (price > (0)),
+                    'data',
+                    kind UNSIGNED BIG INT REFERENCES other(id) ON DELETE CASCADE
+                ) WITHOUT ROWID"
+            )
+            .unwrap()
+            .columns
+        );
+        assert_eq!(
+            vec![
+                column("a", Some("INTEGER"), true),
+                column("b", Some("TEXT"), true),
+                column("c", None, false),
+            ],
+            parse_create_table(
+                "CREATE TABLE t(a INTEGER, b TEXT, c,
+                    CONSTRAINT pk PRIMARY KEY (a, \"B\" DESC),
+                    UNIQUE (c) ON CONFLICT REPLACE,
+                    FOREIGN KEY (c) REFERENCES other(id))"
+            )
+            .unwrap()
+            .columns
+        );
+        assert!(parse_create_table("CREATE INDEX i ON t(a)").is_err());
+        assert!(parse_create_table("CREATE TABLE t AS SELECT 1").is_err());
+        assert!(parse_create_table("CREATE TABLE t(a, PRIMARY KEY (b))").is_err());
+        assert!(parse_create_table("CREATE TABLE t(a").is_err());
+    }
+
+    #[test]
+    fn parse_create_table_affinity_tests() {
+        let affinities =
+            parse_create_table("CREATE TABLE t(a INT, b VARCHAR(10), c, d DECIMAL(10, 2))")
+                .unwrap()
+                .columns
+                .into_iter()
+                .map(|c| c.affinity)
+                .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                Affinity::Integer,
+                Affinity::Text,
+                Affinity::Blob,
+                Affinity::Numeric
+            ],
+            affinities
+        );
+    }
+
+    #[test]
+    fn parse_create_table_default_tests() {
+        let defaults = parse_create_table(
+            "CREATE TABLE t(a DEFAULT 1, b TEXT DEFAULT 'x' NOT NULL, c DEFAULT (-1.5), d DEFAULT NULL,
+                e DEFAULT true, f DEFAULT CURRENT_TIMESTAMP, g DEFAULT (1 + 1), h, i DEFAULT x'01')",
+        )
+        .unwrap()
+        .columns
+        .into_iter()
+        .map(|c| c.default)
+        .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                Some(Value::Int(1)),
+                Some(Value::String("x".into())),
+                Some(Value::Float(-1.5)),
+                Some(Value::Null),
+                Some(Value::Int(1)),
+                None,
+                None,
+                None,
+                Some(Value::Blob(vec![1].into())),
+            ],
+            defaults
+        );
+    }
+
+    #[test]
+    fn parse_create_table_options_tests() {
+        let table_def = parse_create_table("CREATE TABLE t(a, b)").unwrap();
+        assert!(table_def.primary_key.is_empty());
+        assert!(!table_def.without_rowid);
+
+        let table_def =
+            parse_create_table("CREATE TABLE t(a, b, c, PRIMARY KEY (c, a)) STRICT, WITHOUT ROWID")
+                .unwrap();
+        assert_eq!(vec![2, 0], table_def.primary_key);
+        assert!(table_def.without_rowid);
+
+        let table_def =
+            parse_create_table("CREATE TABLE t(a TEXT, b TEXT PRIMARY KEY) without rowid").unwrap();
+        assert_eq!(vec![1], table_def.primary_key);
+        assert!(table_def.without_rowid);
+
+        assert!(parse_create_table("CREATE TABLE t(a) WITHOUT").is_err());
+    }
+
+    #[test]
+    fn rowid_alias_index_tests() {
+        assert_eq!(
+            Some(1),
+            rowid_alias_index(&[
+                column("a", Some("TEXT"), false),
+                column("id", Some("INTEGER"), true)
+            ])
+        );
+        assert_eq!(None, rowid_alias_index(&[column("id", Some("INT"), true)]));
+        assert_eq!(
+            None,
+            rowid_alias_index(&[
+                column("id", Some("INTEGER"), true),
+                column("b", Some("TEXT"), true)
+            ])
+        );
+        assert_eq!(None, rowid_alias_index(&[]));
+    }
+}
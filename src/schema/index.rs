@@ -0,0 +1,132 @@
+use anyhow::Context;
+
+use crate::cursor::Cursor;
+
+use super::{column_def::ColumnDef, create_index::parse_create_index};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Index {
+    pub name: String,
+    pub table: String,
+    pub root_page: usize,
+    // indexed columns, in the order of the index key, None for an expression
+    pub columns: Vec<Option<String>>,
+    // collation of each column, None when not given, i.e. BINARY
+    pub collations: Vec<Option<String>>,
+    pub descending: Vec<bool>,
+    pub unique: bool,
+    pub partial: bool,
+    pub sql: String,
+}
+
+impl Index {
+    // sqlite_master columns are: type, name, tbl_name, rootpage, sql
+    // returns None for anything which isn't an explicitly created index, the ones backing UNIQUE
+    // and PRIMARY KEY constraints have no sql to parse
+    pub fn from_schema_record(record: &Cursor) -> anyhow::Result<Option<Index>> {
+        let type_value = record
+            .field(0)
+            .context("invalid type field")?
+            .context("missing type field")?;
+        if type_value.as_str() != Some("index") {
+            return Ok(None);
+        }
+
+        let Some(sql) = record
+            .field(4)
+            .context("invalid sql field")?
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+        else {
+            return Ok(None);
+        };
+
+        let root_page = record
+            .field(3)
+            .context("invalid rootpage field")?
+            .and_then(|v| v.as_int())
+            .context("missing rootpage field")?;
+        let name = record
+            .field(1)
+            .context("invalid name field")?
+            .context("missing name field")?
+            .to_string();
+        let index_def =
+            parse_create_index(&sql).with_context(|| format!("parse schema of index {}", name))?;
+
+        Ok(Some(Index {
+            name,
+            table: index_def.table,
+            root_page: root_page as usize,
+            columns: index_def.columns,
+            collations: index_def.collations,
+            descending: index_def.descending,
+            unique: index_def.unique,
+            partial: index_def.partial,
+            sql,
+        }))
+    }
+
+    // equality on the leading column can be answered by seeking the index when its entries are
+    // ordered like Value::cmp_sqlite orders them, and when every row of the table is indexed, an
+    // index without a COLLATE of its own uses the one of the column
+    pub fn can_seek(&self, column: &ColumnDef) -> bool {
+        !self.partial
+            && self
+                .columns
+                .first()
+                .and_then(|c| c.as_deref())
+                .is_some_and(|c| c.eq_ignore_ascii_case(&column.name))
+            && self.collations[0]
+                .as_deref()
+                .or(column.collation.as_deref())
+                .is_none_or(|c| c.eq_ignore_ascii_case("BINARY"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn index(sql: &str) -> Index {
+        let index_def = parse_create_index(sql).unwrap();
+        Index {
+            name: index_def.name,
+            table: index_def.table,
+            root_page: 2,
+            columns: index_def.columns,
+            collations: index_def.collations,
+            descending: index_def.descending,
+            unique: index_def.unique,
+            partial: index_def.partial,
+            sql: sql.to_string(),
+        }
+    }
+
+    fn columns(sql: &str) -> Vec<ColumnDef> {
+        crate::schema::parse_create_table(sql).unwrap().columns
+    }
+
+    #[test]
+    fn can_seek_tests() {
+        let [a, b] = columns("CREATE TABLE t(A, b)").try_into().unwrap();
+        assert!(index("CREATE INDEX i ON t(a, b)").can_seek(&a));
+        assert!(!index("CREATE INDEX i ON t(a, b)").can_seek(&b));
+        assert!(index("CREATE INDEX i ON t(a DESC)").can_seek(&a));
+        assert!(index("CREATE INDEX i ON t(a COLLATE binary)").can_seek(&a));
+        assert!(!index("CREATE INDEX i ON t(a COLLATE NOCASE)").can_seek(&a));
+        assert!(!index("CREATE INDEX i ON t(a) WHERE a > 0").can_seek(&a));
+        assert!(!index("CREATE INDEX i ON t(lower(a))").can_seek(&a));
+        assert!(!index("CREATE INDEX i ON t((a))").can_seek(&a));
+
+        // the column's collation applies unless the index has one
+        let [name] = columns("CREATE TABLE t(name TEXT COLLATE NOCASE)")
+            .try_into()
+            .unwrap();
+        assert!(!index("CREATE INDEX i ON t(name)").can_seek(&name));
+        assert!(index("CREATE INDEX i ON t(name COLLATE BINARY)").can_seek(&name));
+        let [name] = columns("CREATE TABLE t(name TEXT COLLATE \"binary\")")
+            .try_into()
+            .unwrap();
+        assert!(index("CREATE INDEX i ON t(name)").can_seek(&name));
+    }
+}
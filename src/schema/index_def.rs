@@ -0,0 +1,15 @@
+// what a CREATE INDEX statement declares
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexDef {
+    pub name: String,
+    pub table: String,
+    // indexed columns, in the order of the index key, None for an expression
+    pub columns: Vec<Option<String>>,
+    // collation of each column, None when not given, i.e. BINARY
+    pub collations: Vec<Option<String>>,
+    // sort order of each column
+    pub descending: Vec<bool>,
+    pub unique: bool,
+    // a WHERE clause restricts the rows the index holds
+    pub partial: bool,
+}
@@ -0,0 +1,15 @@
+pub mod column_def;
+pub mod create_index;
+pub mod create_table;
+pub mod index;
+pub mod index_def;
+pub mod schema_object;
+pub mod sqlite_master;
+pub mod table;
+pub mod table_def;
+pub mod view_def;
+
+pub use create_index::parse_create_index;
+pub use create_table::{parse_create_table, rowid_alias_index};
+pub use schema_object::{ObjectKind, SchemaObject};
+pub use sqlite_master::Schema;
@@ -0,0 +1,89 @@
+use anyhow::Context;
+
+use crate::cursor::Cursor;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ObjectKind {
+    Table,
+    Index,
+    View,
+    Trigger,
+}
+
+impl ObjectKind {
+    pub fn parse(kind: &str) -> anyhow::Result<ObjectKind> {
+        match kind {
+            "table" => Ok(ObjectKind::Table),
+            "index" => Ok(ObjectKind::Index),
+            "view" => Ok(ObjectKind::View),
+            "trigger" => Ok(ObjectKind::Trigger),
+            other => anyhow::bail!("unknown schema object type {}", other),
+        }
+    }
+}
+
+// a row of sqlite_master, whatever the kind of object it describes
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaObject {
+    pub kind: ObjectKind,
+    pub name: String,
+    // table the object belongs to, its own name for tables and views
+    pub tbl_name: String,
+    // 0 for views and triggers which have no b-tree
+    pub root_page: usize,
+    // None for the indexes backing UNIQUE and PRIMARY KEY constraints
+    pub sql: Option<String>,
+}
+
+impl SchemaObject {
+    // sqlite_master columns are: type, name, tbl_name, rootpage, sql
+    pub fn from_schema_record(record: &Cursor) -> anyhow::Result<SchemaObject> {
+        let kind = record
+            .field(0)
+            .context("invalid type field")?
+            .and_then(|v| v.as_str().map(ObjectKind::parse))
+            .context("missing type field")??;
+        let name = record
+            .field(1)
+            .context("invalid name field")?
+            .context("missing name field")?
+            .to_string();
+        let tbl_name = record
+            .field(2)
+            .context("invalid tbl_name field")?
+            .context("missing tbl_name field")?
+            .to_string();
+        let root_page = record
+            .field(3)
+            .context("invalid rootpage field")?
+            .and_then(|v| v.as_int())
+            .unwrap_or(0);
+        let sql = record
+            .field(4)
+            .context("invalid sql field")?
+            .and_then(|v| v.as_str().map(|s| s.to_string()));
+
+        Ok(SchemaObject {
+            kind,
+            name,
+            tbl_name,
+            root_page: root_page as usize,
+            sql,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_object_kind_tests() {
+        assert_eq!(ObjectKind::Table, ObjectKind::parse("table").unwrap());
+        assert_eq!(ObjectKind::Index, ObjectKind::parse("index").unwrap());
+        assert_eq!(ObjectKind::View, ObjectKind::parse("view").unwrap());
+        assert_eq!(ObjectKind::Trigger, ObjectKind::parse("trigger").unwrap());
+        assert!(ObjectKind::parse("TABLE").is_err());
+        assert!(ObjectKind::parse("sequence").is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use super::{
+    create_table::parse_create_table,
+    schema_object::{ObjectKind, SchemaObject},
+    table::Table,
+};
+
+// sqlite_schema is the newer name of sqlite_master, both name the same table
+pub const SCHEMA_TABLE_NAMES: &[&str] = &["sqlite_master", "sqlite_schema"];
+
+const SCHEMA_TABLE_SQL: &str =
+    "CREATE TABLE sqlite_master(type text, name text, tbl_name text, rootpage integer, sql text)";
+
+// sqlite_master's b-tree is always rooted at page 1
+pub const SCHEMA_PAGE: usize = 1;
+
+// every object listed in sqlite_master, in the order they are stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    pub objects: Vec<SchemaObject>,
+}
+
+impl Schema {
+    // names are case insensitive like everywhere else in sqlite
+    pub fn find(&self, name: &str) -> Option<&SchemaObject> {
+        self.objects
+            .iter()
+            .find(|o| o.name.eq_ignore_ascii_case(name))
+    }
+
+    // every index, including the ones backing constraints, optionally only those on a table
+    pub fn indexes<'s>(&'s self, table: Option<&'s str>) -> impl Iterator<Item = &'s SchemaObject> {
+        self.objects.iter().filter(move |o| {
+            o.kind == ObjectKind::Index && table.is_none_or(|t| o.tbl_name.eq_ignore_ascii_case(t))
+        })
+    }
+}
+
+pub fn is_schema_table(name: &str) -> bool {
+    SCHEMA_TABLE_NAMES
+        .iter()
+        .any(|n| n.eq_ignore_ascii_case(name))
+}
+
+// sqlite_master doesn't describe itself, its definition is fixed
+pub fn schema_table() -> Table {
+    let table_def = parse_create_table(SCHEMA_TABLE_SQL).expect("valid sqlite_master schema");
+    Table {
+        name: "sqlite_master".to_string(),
+        root_page: SCHEMA_PAGE,
+        columns: table_def.columns,
+        primary_key: table_def.primary_key,
+        without_rowid: table_def.without_rowid,
+        sql: SCHEMA_TABLE_SQL.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn object(kind: ObjectKind, name: &str, tbl_name: &str) -> SchemaObject {
+        SchemaObject {
+            kind,
+            name: name.to_string(),
+            tbl_name: tbl_name.to_string(),
+            root_page: 0,
+            sql: None,
+        }
+    }
+
+    #[test]
+    fn indexes_tests() {
+        let schema = Schema {
+            objects: vec![
+                object(ObjectKind::Table, "a", "a"),
+                object(ObjectKind::Index, "a_1", "a"),
+                object(ObjectKind::Table, "b", "b"),
+                object(ObjectKind::Index, "b_1", "b"),
+                object(ObjectKind::Trigger, "b_2", "b"),
+                object(ObjectKind::Index, "a_2", "a"),
+            ],
+        };
+        let names = |table| {
+            schema
+                .indexes(table)
+                .map(|o| o.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["a_1", "b_1", "a_2"], names(None));
+        assert_eq!(vec!["a_1", "a_2"], names(Some("A")));
+        assert!(names(Some("c")).is_empty());
+    }
+
+    #[test]
+    fn is_schema_table_tests() {
+        assert!(is_schema_table("sqlite_master"));
+        assert!(is_schema_table("SQLITE_SCHEMA"));
+        assert!(!is_schema_table("sqlite_sequence"));
+    }
+
+    #[test]
+    fn schema_table_tests() {
+        let table = schema_table();
+        assert_eq!(1, table.root_page);
+        assert_eq!(Some(3), table.column_index("rootpage"));
+        assert_eq!(None, table.rowid_alias());
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::Context;
+
+use crate::{cursor::Cursor, value::Value};
+
+use super::{
+    column_def::ColumnDef,
+    create_table::{parse_create_table, rowid_alias_index},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub name: String,
+    pub root_page: usize,
+    pub columns: Vec<ColumnDef>,
+    // indices of the primary key columns, in the order of the primary key
+    pub primary_key: Vec<usize>,
+    pub without_rowid: bool,
+    pub sql: String,
+}
+
+impl Table {
+    // sqlite_master columns are: type, name, tbl_name, rootpage, sql
+    // returns None for anything which isn't a table backed by a b-tree, e.g. indexes or virtual tables
+    pub fn from_schema_record(record: &Cursor) -> anyhow::Result<Option<Table>> {
+        let type_value = record
+            .field(0)
+            .context("invalid type field")?
+            .context("missing type field")?;
+        if type_value.as_str() != Some("table") {
+            return Ok(None);
+        }
+
+        let root_page = record
+            .field(3)
+            .context("invalid rootpage field")?
+            .and_then(|v| v.as_int())
+            .context("missing rootpage field")?;
+        if root_page == 0 {
+            return Ok(None);
+        }
+
+        let name = record
+            .field(1)
+            .context("invalid name field")?
+            .context("missing name field")?
+            .to_string();
+        let sql = record
+            .field(4)
+            .context("invalid sql field")?
+            .context("missing sql field")?
+            .to_string();
+        let table_def =
+            parse_create_table(&sql).with_context(|| format!("parse schema of table {}", name))?;
+
+        Ok(Some(Table {
+            name,
+            root_page: root_page as usize,
+            columns: table_def.columns,
+            primary_key: table_def.primary_key,
+            without_rowid: table_def.without_rowid,
+            sql,
+        }))
+    }
+
+    // WITHOUT ROWID tables don't have a row id to alias
+    pub fn rowid_alias(&self) -> Option<usize> {
+        if self.without_rowid {
+            None
+        } else {
+            rowid_alias_index(&self.columns)
+        }
+    }
+
+    // WITHOUT ROWID tables are stored in an index b-tree keyed by the primary key, their records
+    // hold the primary key columns first followed by the remaining ones in declaration order
+    pub fn column_order(&self) -> Option<Vec<usize>> {
+        if !self.without_rowid {
+            return None;
+        }
+        let mut record_order = self.primary_key.clone();
+        record_order.extend((0..self.columns.len()).filter(|i| !self.primary_key.contains(i)));
+
+        let mut column_order = vec![0; self.columns.len()];
+        for (field, &column) in record_order.iter().enumerate() {
+            column_order[column] = field;
+        }
+        Some(column_order)
+    }
+
+    // the value of each column in records written before it was added with ALTER TABLE, which
+    // takes the column's affinity when stored
+    pub fn defaults(&self) -> Vec<Value<'static>> {
+        self.columns
+            .iter()
+            .map(|c| {
+                c.default
+                    .clone()
+                    .map_or(Value::Null, |v| v.affinity_coerce(c.affinity))
+            })
+            .collect()
+    }
+
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn table(sql: &str) -> Table {
+        let table_def = parse_create_table(sql).unwrap();
+        Table {
+            name: "t".to_string(),
+            root_page: 2,
+            columns: table_def.columns,
+            primary_key: table_def.primary_key,
+            without_rowid: table_def.without_rowid,
+            sql: sql.to_string(),
+        }
+    }
+
+    #[test]
+    fn column_index_tests() {
+        let table = table("CREATE TABLE tbl1(one text, two int)");
+        assert_eq!(Some(0), table.column_index("one"));
+        assert_eq!(Some(1), table.column_index("TWO"));
+        assert_eq!(None, table.column_index("three"));
+    }
+
+    #[test]
+    fn rowid_alias_tests() {
+        assert_eq!(
+            Some(0),
+            table("CREATE TABLE t(id INTEGER PRIMARY KEY, a)").rowid_alias()
+        );
+        assert_eq!(
+            None,
+            table("CREATE TABLE t(id INTEGER PRIMARY KEY, a) WITHOUT ROWID").rowid_alias()
+        );
+    }
+
+    #[test]
+    fn column_order_tests() {
+        assert_eq!(
+            None,
+            table("CREATE TABLE t(a, b, c PRIMARY KEY)").column_order()
+        );
+        assert_eq!(
+            Some(vec![1, 2, 0]),
+            table("CREATE TABLE t(a, b, c PRIMARY KEY) WITHOUT ROWID").column_order()
+        );
+        assert_eq!(
+            Some(vec![2, 0, 3, 1]),
+            table("CREATE TABLE t(a, b, c, d, PRIMARY KEY (b, d)) WITHOUT ROWID").column_order()
+        );
+        assert_eq!(
+            Some(vec![1, 0]),
+            table("CREATE TABLE t(a, b, PRIMARY KEY (b, a)) WITHOUT ROWID").column_order()
+        );
+    }
+}
@@ -0,0 +1,10 @@
+use super::column_def::ColumnDef;
+
+// what a CREATE TABLE statement declares
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableDef {
+    pub columns: Vec<ColumnDef>,
+    // indices of the primary key columns, in the order of the primary key
+    pub primary_key: Vec<usize>,
+    pub without_rowid: bool,
+}
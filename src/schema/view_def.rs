@@ -0,0 +1,47 @@
+use super::schema_object::{ObjectKind, SchemaObject};
+
+// a view listed in sqlite_master, views can't be queried yet so only their definition is kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewDef {
+    pub name: String,
+    // the whole CREATE VIEW ... AS SELECT ... statement
+    pub sql: String,
+}
+
+impl ViewDef {
+    // None for anything which isn't a view
+    pub fn from_schema_object(object: &SchemaObject) -> Option<ViewDef> {
+        if object.kind != ObjectKind::View {
+            return None;
+        }
+        Some(ViewDef {
+            name: object.name.clone(),
+            sql: object.sql.clone().unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_schema_object_tests() {
+        let mut object = SchemaObject {
+            kind: ObjectKind::View,
+            name: "v".to_string(),
+            tbl_name: "v".to_string(),
+            root_page: 0,
+            sql: Some("CREATE VIEW v AS SELECT 1".to_string()),
+        };
+        assert_eq!(
+            Some(ViewDef {
+                name: "v".to_string(),
+                sql: "CREATE VIEW v AS SELECT 1".to_string(),
+            }),
+            ViewDef::from_schema_object(&object)
+        );
+        object.kind = ObjectKind::Table;
+        assert_eq!(None, ViewDef::from_schema_object(&object));
+    }
+}
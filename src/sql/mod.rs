@@ -0,0 +1,3 @@
+pub mod token;
+pub mod token_stream;
+pub mod tokenizer;
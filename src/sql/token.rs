@@ -0,0 +1,58 @@
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    // bare word, either a keyword or an identifier depending on where it appears
+    Word(String),
+    // "name", [name] or `name`, never a keyword
+    QuotedIdentifier(String),
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Blob(Vec<u8>),
+    LeftParen,
+    RightParen,
+    Comma,
+    Semicolon,
+    Dot,
+    Star,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
+    Concat,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl Token {
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    // words and quoted identifiers can both name a column or a table
+    pub fn as_identifier(&self) -> Option<&str> {
+        match self {
+            Token::Word(w) | Token::QuotedIdentifier(w) => Some(w),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn token_tests() {
+        let word = Token::Word("select".to_string());
+        let quoted = Token::QuotedIdentifier("select".to_string());
+        assert!(word.is_keyword("SELECT"));
+        assert!(!quoted.is_keyword("SELECT"));
+        assert_eq!(Some("select"), word.as_identifier());
+        assert_eq!(Some("select"), quoted.as_identifier());
+        assert_eq!(None, Token::String("a".to_string()).as_identifier());
+    }
+}
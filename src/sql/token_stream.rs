@@ -0,0 +1,99 @@
+use super::{token::Token, tokenizer};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenStream {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl TokenStream {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        tokenizer::tokenize(input).map(Self::new)
+    }
+
+    pub fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    pub fn peek_nth(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + n)
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    // consumes the next token if it is the given keyword
+    pub fn next_if_keyword(&mut self, keyword: &str) -> bool {
+        self.next_if(|t| t.is_keyword(keyword))
+    }
+
+    pub fn next_if_token(&mut self, token: &Token) -> bool {
+        self.next_if(|t| t == token)
+    }
+
+    pub fn expect_keyword(&mut self, keyword: &str) -> anyhow::Result<()> {
+        match self.next() {
+            Some(t) if t.is_keyword(keyword) => Ok(()),
+            other => Err(anyhow::anyhow!("expected {}, got {:?}", keyword, other)),
+        }
+    }
+
+    pub fn expect_token(&mut self, token: &Token) -> anyhow::Result<()> {
+        match self.next() {
+            Some(t) if &t == token => Ok(()),
+            other => Err(anyhow::anyhow!("expected {:?}, got {:?}", token, other)),
+        }
+    }
+
+    pub fn expect_identifier(&mut self) -> anyhow::Result<String> {
+        match self.next() {
+            Some(t) => t
+                .as_identifier()
+                .map(|s| s.to_string())
+                .ok_or_else(|| anyhow::anyhow!("expected identifier, got {:?}", t)),
+            None => Err(anyhow::anyhow!("expected identifier, got end of input")),
+        }
+    }
+
+    fn next_if(&mut self, predicate: impl Fn(&Token) -> bool) -> bool {
+        if self.peek().is_some_and(predicate) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn token_stream_tests() {
+        let mut stream = TokenStream::parse("create table \"t\" (").unwrap();
+        assert!(!stream.next_if_keyword("table"));
+        assert!(stream.next_if_keyword("CREATE"));
+        assert!(stream.expect_identifier().is_ok());
+        assert_eq!(Some(&Token::LeftParen), stream.peek_nth(1));
+        assert_eq!("t", stream.expect_identifier().unwrap());
+        assert!(stream.expect_keyword("AS").is_err());
+        assert!(stream.is_empty());
+        assert_eq!(None, stream.next());
+        assert!(stream.expect_token(&Token::RightParen).is_err());
+    }
+}
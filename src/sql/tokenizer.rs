@@ -0,0 +1,245 @@
+use super::token::Token;
+
+// https://www.sqlite.org/lang_keywords.html
+pub fn tokenize(input: &str) -> anyhow::Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+        match c {
+            c if c.is_whitespace() => pos += 1,
+            '-' if chars.get(pos + 1) == Some(&'-') => {
+                while pos < chars.len() && chars[pos] != '\n' {
+                    pos += 1;
+                }
+            }
+            '/' if chars.get(pos + 1) == Some(&'*') => {
+                pos += 2;
+                while pos < chars.len() && !(chars[pos] == '*' && chars.get(pos + 1) == Some(&'/'))
+                {
+                    pos += 1;
+                }
+                pos += 2;
+            }
+            '\'' => {
+                let (len, s) = read_quoted(&chars[pos..], '\'')?;
+                tokens.push(Token::String(s));
+                pos += len;
+            }
+            '"' | '`' => {
+                let (len, s) = read_quoted(&chars[pos..], c)?;
+                tokens.push(Token::QuotedIdentifier(s));
+                pos += len;
+            }
+            '[' => {
+                let end = chars[pos..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .ok_or_else(|| anyhow::anyhow!("unterminated identifier at {}", pos))?;
+                tokens.push(Token::QuotedIdentifier(
+                    chars[pos + 1..pos + end].iter().collect(),
+                ));
+                pos += end + 1;
+            }
+            'x' | 'X' if chars.get(pos + 1) == Some(&'\'') => {
+                let (len, s) = read_quoted(&chars[pos + 1..], '\'')?;
+                tokens.push(Token::Blob(parse_hex(&s)?));
+                pos += len + 1;
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && chars.get(pos + 1).is_some_and(|c| c.is_ascii_digit())) =>
+            {
+                let (len, token) = read_number(&chars[pos..])?;
+                tokens.push(token);
+                pos += len;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let len = chars[pos..]
+                    .iter()
+                    .position(|&c| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                    .unwrap_or(chars.len() - pos);
+                tokens.push(Token::Word(chars[pos..pos + len].iter().collect()));
+                pos += len;
+            }
+            _ => {
+                let next = chars.get(pos + 1).copied();
+                let (len, token) = match (c, next) {
+                    ('(', _) => (1, Token::LeftParen),
+                    (')', _) => (1, Token::RightParen),
+                    (',', _) => (1, Token::Comma),
+                    (';', _) => (1, Token::Semicolon),
+                    ('.', _) => (1, Token::Dot),
+                    ('*', _) => (1, Token::Star),
+                    ('+', _) => (1, Token::Plus),
+                    ('-', _) => (1, Token::Minus),
+                    ('/', _) => (1, Token::Slash),
+                    ('%', _) => (1, Token::Percent),
+                    ('|', Some('|')) => (2, Token::Concat),
+                    ('=', Some('=')) => (2, Token::Eq),
+                    ('=', _) => (1, Token::Eq),
+                    ('!', Some('=')) => (2, Token::NotEq),
+                    ('<', Some('>')) => (2, Token::NotEq),
+                    ('<', Some('=')) => (2, Token::LtEq),
+                    ('<', _) => (1, Token::Lt),
+                    ('>', Some('=')) => (2, Token::GtEq),
+                    ('>', _) => (1, Token::Gt),
+                    _ => anyhow::bail!("unexpected character '{}' at {}", c, pos),
+                };
+                tokens.push(token);
+                pos += len;
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+// the quote character is escaped by doubling it
+fn read_quoted(chars: &[char], quote: char) -> anyhow::Result<(usize, String)> {
+    let mut res = String::new();
+    let mut pos = 1;
+    loop {
+        match chars.get(pos) {
+            None => anyhow::bail!("unterminated quoted string"),
+            Some(&c) if c == quote => {
+                if chars.get(pos + 1) == Some(&quote) {
+                    res.push(quote);
+                    pos += 2;
+                } else {
+                    return Ok((pos + 1, res));
+                }
+            }
+            Some(&c) => {
+                res.push(c);
+                pos += 1;
+            }
+        }
+    }
+}
+
+fn read_number(chars: &[char]) -> anyhow::Result<(usize, Token)> {
+    if chars[0] == '0' && matches!(chars.get(1), Some('x' | 'X')) {
+        let len = chars[2..]
+            .iter()
+            .position(|c| !c.is_ascii_hexdigit())
+            .unwrap_or(chars.len() - 2);
+        let digits: String = chars[2..2 + len].iter().collect();
+        let value = u64::from_str_radix(&digits, 16)
+            .map_err(|e| anyhow::anyhow!("invalid hex literal 0x{}: {}", digits, e))?;
+        return Ok((len + 2, Token::Integer(value as i64)));
+    }
+
+    let mut len = 0;
+    let mut is_float = false;
+    while len < chars.len() {
+        match chars[len] {
+            c if c.is_ascii_digit() => len += 1,
+            '.' if !is_float => {
+                is_float = true;
+                len += 1;
+            }
+            'e' | 'E' => {
+                is_float = true;
+                len += 1;
+                if matches!(chars.get(len), Some('+' | '-')) {
+                    len += 1;
+                }
+                while chars.get(len).is_some_and(|c| c.is_ascii_digit()) {
+                    len += 1;
+                }
+                break;
+            }
+            _ => break,
+        }
+    }
+
+    let literal: String = chars[..len].iter().collect();
+    // integers too large for an i64 are treated as floats like sqlite does
+    let token = match literal.parse::<i64>() {
+        Ok(i) if !is_float => Token::Integer(i),
+        _ => Token::Float(
+            literal
+                .parse::<f64>()
+                .map_err(|e| anyhow::anyhow!("invalid numeric literal {}: {}", literal, e))?,
+        ),
+    };
+    Ok((len, token))
+}
+
+fn parse_hex(s: &str) -> anyhow::Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("invalid blob literal x'{}'", s);
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(anyhow::Error::from))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn word(w: &str) -> Token {
+        Token::Word(w.to_string())
+    }
+
+    #[test]
+    fn tokenize_tests() {
+        assert_eq!(
+            vec![
+                word("SELECT"),
+                Token::Star,
+                word("FROM"),
+                word("tbl1"),
+                Token::Semicolon
+            ],
+            tokenize("SELECT * FROM tbl1;").unwrap()
+        );
+        assert_eq!(
+            vec![
+                Token::QuotedIdentifier("a b".to_string()),
+                Token::QuotedIdentifier("c\"d".to_string()),
+                Token::QuotedIdentifier("e".to_string()),
+                Token::String("it's".to_string()),
+                Token::Blob(vec![0xde, 0xad]),
+            ],
+            tokenize("[a b] \"c\"\"d\" `e` 'it''s' x'DEad'").unwrap()
+        );
+        assert_eq!(
+            vec![
+                Token::Integer(12),
+                Token::Float(1.5),
+                Token::Float(0.5),
+                Token::Float(1e3),
+                Token::Integer(255),
+                Token::Float(1e19),
+            ],
+            tokenize("12 1.5 .5 1e3 0xff 10000000000000000000").unwrap()
+        );
+        assert_eq!(
+            vec![
+                Token::Eq,
+                Token::Eq,
+                Token::NotEq,
+                Token::NotEq,
+                Token::Lt,
+                Token::LtEq,
+                Token::Gt,
+                Token::GtEq,
+                Token::Concat,
+                Token::Minus,
+            ],
+            tokenize("= == != <> < <= > >= || -").unwrap()
+        );
+        assert_eq!(
+            vec![word("a"), Token::Comma, word("b")],
+            tokenize("a -- comment\n, /* block */ b").unwrap()
+        );
+        assert!(tokenize("'unterminated").is_err());
+        assert!(tokenize("x'abc'").is_err());
+        assert!(tokenize("a ? b").is_err());
+    }
+}
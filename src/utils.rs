@@ -45,6 +45,47 @@ pub fn read_varint_at(buffer: &[u8], offset: usize) -> (u8, i64) {
     (bytes, res)
 }
 
+// like read_varint_at but returns None if the buffer ends before the varint does, i.e. when the
+// last available byte still has its continuation bit set
+pub fn try_read_varint_at(buffer: &[u8], offset: usize) -> Option<(u8, i64)> {
+    let (bytes, res) = read_varint_at(buffer, offset);
+    if bytes == 0 || (bytes < 9 && buffer[offset + bytes as usize - 1] >= 0b1000_0000) {
+        None
+    } else {
+        Some((bytes, res))
+    }
+}
+
+// inverse of read_varint_at, values needing more than 56 bits use the 9-byte form where the
+// last byte holds 8 bits instead of 7
+pub fn write_varint_into(buffer: &mut Vec<u8>, value: i64) {
+    let value = value as u64;
+
+    if value > 0x00ff_ffff_ffff_ffff {
+        for i in (1..=8).rev() {
+            buffer.push(((value >> (8 + 7 * (i - 1))) as u8 & 0b0111_1111) | 0b1000_0000);
+        }
+        buffer.push(value as u8);
+        return;
+    }
+
+    let groups = (64 - value.leading_zeros()).div_ceil(7).max(1);
+    for i in (0..groups).rev() {
+        let byte = (value >> (7 * i)) as u8 & 0b0111_1111;
+        if i == 0 {
+            buffer.push(byte);
+        } else {
+            buffer.push(byte | 0b1000_0000);
+        }
+    }
+}
+
+pub fn write_varint(value: i64) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(9);
+    write_varint_into(&mut buffer, value);
+    buffer
+}
+
 #[allow(dead_code)]
 fn read_varint_rec(buffer: &[u8], offset: usize) -> (u8, i64) {
     fn go(buffer: &[u8], offset: usize, res: i64, bytes: u8) -> (u8, i64) {
@@ -97,6 +138,30 @@ pub fn read_be_double_word_at(input: &[u8], offset: usize) -> (u8, u32) {
     }
 }
 
+pub fn read_be_u24_at(input: &[u8], offset: usize) -> (u8, u32) {
+    if input.len() >= offset + 3 {
+        (
+            3,
+            u32::from_be_bytes([0, input[offset], input[offset + 1], input[offset + 2]]),
+        )
+    } else {
+        let (s, r) = read_be_word_at(input, offset);
+        (s, r as u32)
+    }
+}
+
+pub fn read_be_u64_at(input: &[u8], offset: usize) -> (u8, u64) {
+    if input.len() >= offset + 8 {
+        (
+            8,
+            u64::from_be_bytes(input[offset..offset + 8].try_into().unwrap()),
+        )
+    } else {
+        let (s, r) = read_be_double_word_at(input, offset);
+        (s, r as u64)
+    }
+}
+
 pub fn read_i8_at(input: &[u8], offset: usize) -> i64 {
     if offset >= input.len() {
         0
@@ -187,10 +252,51 @@ pub fn read_f64_at(input: &[u8], offset: usize) -> f64 {
     }
 }
 
+// sqlite floats are always 8 bytes, anything shorter is a malformed record rather than a float
+pub fn try_read_f64_at(input: &[u8], offset: usize) -> Option<f64> {
+    offset
+        .checked_add(8)
+        .and_then(|end| input.get(offset..end))
+        .map(|bytes| f64::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// standard alphabet, padded
+pub fn encode_base64(input: &[u8]) -> String {
+    let mut res = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = chunk.get(1).copied().unwrap_or(0) as u32;
+        let b2 = chunk.get(2).copied().unwrap_or(0) as u32;
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (triple >> (18 - 6 * i)) & 0b0011_1111;
+                res.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                res.push('=');
+            }
+        }
+    }
+    res
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn encode_base64_tests() {
+        assert_eq!("", encode_base64(b""));
+        assert_eq!("Zg==", encode_base64(b"f"));
+        assert_eq!("Zm8=", encode_base64(b"fo"));
+        assert_eq!("Zm9v", encode_base64(b"foo"));
+        assert_eq!("Zm9vYmFy", encode_base64(b"foobar"));
+        assert_eq!("3q2+7w==", encode_base64(&[0xde, 0xad, 0xbe, 0xef]));
+    }
+
     #[test]
     fn read_f64_at_tests() {
         assert_eq!(0., read_f64_at(&[], 0));
@@ -221,6 +327,22 @@ mod test {
         );
     }
 
+    #[test]
+    fn try_read_f64_at_tests() {
+        assert_eq!(None, try_read_f64_at(&[], 0));
+        assert_eq!(None, try_read_f64_at(&[0b00111110, 0b00100000, 0, 0], 0));
+        assert_eq!(None, try_read_f64_at(&[0; 8], 1));
+        assert_eq!(None, try_read_f64_at(&[0; 8], usize::MAX));
+        assert_eq!(
+            Some(1.0000000000000004),
+            try_read_f64_at(&[0b00111111, 0b11110000, 0, 0, 0, 0, 0, 2], 0)
+        );
+        assert_eq!(
+            Some(-1.0000000000000004),
+            try_read_f64_at(&[1, 0b10111111, 0b11110000, 0, 0, 0, 0, 0, 2], 1)
+        );
+    }
+
     #[test]
     fn read_i64_at_tests() {
         assert_eq!(0, read_i64_at(&[], 0));
@@ -298,65 +420,125 @@ mod test {
 
     #[test]
     fn read_single_byte_varint() {
-        assert_eq!((1, 1), read_varint_at(&vec![0b00000001], 0));
-        assert_eq!((1, 3), read_varint_at(&vec![0b00000011], 0));
-        assert_eq!((1, 7), read_varint_at(&vec![0b00000111], 0));
-        assert_eq!((1, 15), read_varint_at(&vec![0b00001111], 0));
-        assert_eq!((1, 127), read_varint_at(&vec![0b11111111], 0));
-        assert_eq!((1, 1), read_varint_rec(&vec![0b00000001], 0));
-        assert_eq!((1, 3), read_varint_rec(&vec![0b00000011], 0));
-        assert_eq!((1, 7), read_varint_rec(&vec![0b00000111], 0));
-        assert_eq!((1, 15), read_varint_rec(&vec![0b00001111], 0));
-        assert_eq!((1, 127), read_varint_rec(&vec![0b11111111], 0));
+        assert_eq!((1, 1), read_varint_at(&[0b00000001], 0));
+        assert_eq!((1, 3), read_varint_at(&[0b00000011], 0));
+        assert_eq!((1, 7), read_varint_at(&[0b00000111], 0));
+        assert_eq!((1, 15), read_varint_at(&[0b00001111], 0));
+        assert_eq!((1, 127), read_varint_at(&[0b11111111], 0));
+        assert_eq!((1, 1), read_varint_rec(&[0b00000001], 0));
+        assert_eq!((1, 3), read_varint_rec(&[0b00000011], 0));
+        assert_eq!((1, 7), read_varint_rec(&[0b00000111], 0));
+        assert_eq!((1, 15), read_varint_rec(&[0b00001111], 0));
+        assert_eq!((1, 127), read_varint_rec(&[0b11111111], 0));
     }
 
     #[test]
     fn read_two_byte_varint() {
-        assert_eq!((2, 128), read_varint_at(&vec![0b10000001, 0b00000000], 0));
-        assert_eq!((2, 129), read_varint_at(&vec![0b10000001, 0b00000001], 0));
-        assert_eq!((2, 255), read_varint_at(&vec![0b10000001, 0b01111111], 0));
-        assert_eq!((2, 128), read_varint_rec(&vec![0b10000001, 0b00000000], 0));
-        assert_eq!((2, 129), read_varint_rec(&vec![0b10000001, 0b00000001], 0));
-        assert_eq!((2, 255), read_varint_rec(&vec![0b10000001, 0b01111111], 0));
+        assert_eq!((2, 128), read_varint_at(&[0b10000001, 0b00000000], 0));
+        assert_eq!((2, 129), read_varint_at(&[0b10000001, 0b00000001], 0));
+        assert_eq!((2, 255), read_varint_at(&[0b10000001, 0b01111111], 0));
+        assert_eq!((2, 128), read_varint_rec(&[0b10000001, 0b00000000], 0));
+        assert_eq!((2, 129), read_varint_rec(&[0b10000001, 0b00000001], 0));
+        assert_eq!((2, 255), read_varint_rec(&[0b10000001, 0b01111111], 0));
     }
 
     #[test]
     fn read_nine_byte_varint() {
-        assert_eq!((9, -1), read_varint_at(&vec![0xff; 9], 0));
-        assert_eq!((9, -1), read_varint_rec(&vec![0xff; 9], 0));
+        assert_eq!((9, -1), read_varint_at(&[0xff; 9], 0));
+        assert_eq!((9, -1), read_varint_rec(&[0xff; 9], 0));
+    }
+
+    #[test]
+    fn try_read_varint_tests() {
+        assert_eq!(Some((1, 1)), try_read_varint_at(&[0b00000001], 0));
+        assert_eq!(
+            Some((2, 128)),
+            try_read_varint_at(&[0b10000001, 0b00000000], 0)
+        );
+        assert_eq!(Some((9, -1)), try_read_varint_at(&[0xff; 9], 0));
+        assert_eq!(Some((1, 1)), try_read_varint_at(&[0xff, 0x01], 1));
+        assert_eq!(None, try_read_varint_at(&[0b10000001], 0));
+        assert_eq!(None, try_read_varint_at(&[0xff; 8], 0));
+        assert_eq!(None, try_read_varint_at(&[0x01], 1));
+        assert_eq!(None, try_read_varint_at(&[], 0));
     }
 
     #[test]
     fn read_varint_in_longer_bytes() {
-        assert_eq!((1, 1), read_varint_at(&vec![0x01; 10], 0));
-        assert_eq!((9, -1), read_varint_at(&vec![0xff; 10], 0));
-        assert_eq!((1, 1), read_varint_rec(&vec![0x01; 10], 0));
-        assert_eq!((9, -1), read_varint_rec(&vec![0xff; 10], 0));
+        assert_eq!((1, 1), read_varint_at(&[0x01; 10], 0));
+        assert_eq!((9, -1), read_varint_at(&[0xff; 10], 0));
+        assert_eq!((1, 1), read_varint_rec(&[0x01; 10], 0));
+        assert_eq!((9, -1), read_varint_rec(&[0xff; 10], 0));
         println!("{:?}", &vec![0xff; 10]);
     }
 
     #[test]
-    fn read_varint_at_short() -> () {
+    fn read_varint_at_short() {
         assert_eq!((1, 127), read_varint_at(&[255], 0));
         assert_eq!((1, 127), read_varint_rec(&[255], 0));
     }
 
     #[test]
-    fn read_varint_at_empty() -> () {
+    fn read_varint_at_empty() {
         assert_eq!((0, 0), read_varint_at(&[], 0));
         assert_eq!((0, 0), read_varint_rec(&[], 0));
     }
 
     #[test]
-    fn read_varint_at_offset() -> () {
+    fn read_varint_at_offset() {
         assert_eq!((0, 0), read_varint_at(&[], 1));
         assert_eq!((0, 0), read_varint_rec(&[], 1));
-        assert_eq!((1, 127), read_varint_at(&vec![0b10000001, 0b01111111], 1));
-        assert_eq!((1, 127), read_varint_rec(&vec![0b10000001, 0b01111111], 1));
+        assert_eq!((1, 127), read_varint_at(&[0b10000001, 0b01111111], 1));
+        assert_eq!((1, 127), read_varint_rec(&[0b10000001, 0b01111111], 1));
     }
 
     #[test]
-    fn read_be_word_at_tests() -> () {
+    fn write_varint_tests() {
+        assert_eq!(vec![0], write_varint(0));
+        assert_eq!(vec![127], write_varint(127));
+        assert_eq!(vec![0x81, 0], write_varint(128));
+        assert_eq!(vec![0xff, 0x7f], write_varint(16383));
+        assert_eq!(vec![0x81, 0x80, 0], write_varint(16384));
+        assert_eq!(vec![0xff; 9], write_varint(-1));
+        assert_eq!(9, write_varint(i64::MIN).len());
+        assert_eq!(8, write_varint(0x00ff_ffff_ffff_ffff).len());
+        assert_eq!(9, write_varint(0x0100_0000_0000_0000).len());
+
+        let mut buffer = vec![42];
+        write_varint_into(&mut buffer, 128);
+        assert_eq!(vec![42, 0x81, 0], buffer);
+    }
+
+    #[test]
+    fn write_varint_round_trip_tests() {
+        let mut values = vec![0, 1, -1, i64::MIN, i64::MAX, i64::MIN + 1, i64::MAX - 1];
+        for shift in 0..64 {
+            let n = 1i64 << shift;
+            values.extend([
+                n,
+                n.wrapping_sub(1),
+                n.wrapping_add(1),
+                n.wrapping_neg(),
+                !n,
+            ]);
+        }
+        // a cheap xorshift to cover arbitrary bit patterns
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..1000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            values.push(state as i64);
+        }
+
+        for n in values {
+            let bytes = write_varint(n);
+            assert_eq!((bytes.len() as u8, n), read_varint_at(&bytes, 0), "{}", n);
+        }
+    }
+
+    #[test]
+    fn read_be_word_at_tests() {
         assert_eq!((2, 3086), read_be_word_at(&[12, 14], 0));
         assert_eq!((2, 3086), read_be_word_at(&[255, 12, 14], 1));
         assert_eq!((1, 255), read_be_word_at(&[255], 0));
@@ -364,7 +546,7 @@ mod test {
     }
 
     #[test]
-    fn read_be_double_word_at_tests() -> () {
+    fn read_be_double_word_at_tests() {
         assert_eq!((4, 202182159), read_be_double_word_at(&[12, 13, 14, 15], 0));
         assert_eq!(
             (4, 202182159),
@@ -375,4 +557,25 @@ mod test {
         assert_eq!((1, 255), read_be_double_word_at(&[255], 0));
         assert_eq!((0, 0), read_be_double_word_at(&[255], 1));
     }
+
+    #[test]
+    fn read_be_u24_at_tests() {
+        assert_eq!((3, 789774), read_be_u24_at(&[12, 13, 14], 0));
+        assert_eq!((3, 16777215), read_be_u24_at(&[0, 255, 255, 255], 1));
+        assert_eq!((2, 3086), read_be_u24_at(&[12, 14], 0));
+        assert_eq!((1, 255), read_be_u24_at(&[255], 0));
+        assert_eq!((0, 0), read_be_u24_at(&[255], 1));
+    }
+
+    #[test]
+    fn read_be_u64_at_tests() {
+        assert_eq!((8, u64::MAX), read_be_u64_at(&[255; 8], 0));
+        assert_eq!(
+            (8, 72057594037927937),
+            read_be_u64_at(&[9, 1, 0, 0, 0, 0, 0, 0, 1], 1)
+        );
+        assert_eq!((4, 202182159), read_be_u64_at(&[12, 13, 14, 15], 0));
+        assert_eq!((2, 3086), read_be_u64_at(&[12, 14], 0));
+        assert_eq!((0, 0), read_be_u64_at(&[], 0));
+    }
 }
@@ -1,6 +1,11 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub enum Value<'p> {
     Null,
     String(Cow<'p, str>),
@@ -9,6 +14,17 @@ pub enum Value<'p> {
     Float(f64),
 }
 
+// the type a column prefers for its values, see https://www.sqlite.org/datatype3.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affinity {
+    Text,
+    Numeric,
+    Integer,
+    Real,
+    // no preference, values are left alone
+    Blob,
+}
+
 impl<'p> Value<'p> {
     pub fn as_str(&self) -> Option<&str> {
         if let Value::String(s) = self {
@@ -17,4 +33,656 @@ impl<'p> Value<'p> {
             None
         }
     }
+
+    pub fn as_int(&self) -> Option<i64> {
+        if let Value::Int(i) = self {
+            Some(*i)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        if let Value::Float(f) = self {
+            Some(*f)
+        } else {
+            None
+        }
+    }
+
+    // numeric columns can mix serial types from one row to the next
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    pub fn as_blob(&self) -> Option<&[u8]> {
+        if let Value::Blob(b) = self {
+            Some(b.as_ref())
+        } else {
+            None
+        }
+    }
+
+    // copies borrowed text and blobs so the value no longer depends on the page it was read from
+    pub fn into_owned(self) -> Value<'static> {
+        match self {
+            Value::Null => Value::Null,
+            Value::String(s) => Value::String(Cow::Owned(s.into_owned())),
+            Value::Blob(b) => Value::Blob(Cow::Owned(b.into_owned())),
+            Value::Int(i) => Value::Int(i),
+            Value::Float(f) => Value::Float(f),
+        }
+    }
+
+    // unlike Display, the output must parse back to the same value
+    pub fn to_sql_literal(&self) -> String {
+        match self {
+            Value::Null => "NULL".to_string(),
+            Value::String(s) => format!("'{}'", s.replace('\'', "''")),
+            Value::Float(f) if f.is_nan() => "NULL".to_string(),
+            // sqlite itself dumps infinities as out of range literals
+            Value::Float(f) if f.is_infinite() && f.is_sign_positive() => "1e999".to_string(),
+            Value::Float(f) if f.is_infinite() => "-1e999".to_string(),
+            Value::Blob(_) | Value::Int(_) | Value::Float(_) => self.to_string(),
+        }
+    }
+
+    // converts the value like sqlite does before storing it in, or comparing it to, a column of
+    // the given affinity, values which can't be converted are left as they are
+    pub fn affinity_coerce(self, affinity: Affinity) -> Value<'p> {
+        match (affinity, self) {
+            (Affinity::Text, Value::Int(i)) => Value::String(Cow::Owned(i.to_string())),
+            (Affinity::Text, Value::Float(f)) => {
+                Value::String(Cow::Owned(Value::Float(f).to_string()))
+            }
+            // integer and numeric affinities only differ for CAST
+            (Affinity::Numeric | Affinity::Integer, Value::String(s)) => {
+                parse_numeric(&s).unwrap_or(Value::String(s))
+            }
+            (Affinity::Real, Value::String(s)) => {
+                match parse_numeric(&s).and_then(|v| v.as_f64()) {
+                    Some(f) => Value::Float(f),
+                    None => Value::String(s),
+                }
+            }
+            (Affinity::Real, Value::Int(i)) => Value::Float(i as f64),
+            (_, value) => value,
+        }
+    }
+
+    // sqlite's sort order: null < numbers < text < blobs
+    // ints and floats are compared numerically, text with the binary collation and blobs bytewise
+    pub fn cmp_sqlite(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => Self::cmp_floats(*a, *b),
+            (Value::Int(a), Value::Float(b)) => Self::cmp_int_float(*a, *b),
+            (Value::Float(a), Value::Int(b)) => Self::cmp_int_float(*b, *a).reverse(),
+            (Value::String(a), Value::String(b)) => a.as_bytes().cmp(b.as_bytes()),
+            (Value::Blob(a), Value::Blob(b)) => a.cmp(b),
+            (a, b) => a.storage_class_rank().cmp(&b.storage_class_rank()),
+        }
+    }
+
+    fn storage_class_rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Int(_) | Value::Float(_) => 1,
+            Value::String(_) => 2,
+            Value::Blob(_) => 3,
+        }
+    }
+
+    // -0.0 and 0.0 are equal, nans are all equal whatever their sign and payload and are ordered
+    // after every other float
+    fn cmp_floats(a: f64, b: f64) -> Ordering {
+        match (a.is_nan(), b.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        }
+    }
+
+    // compared without going through a lossy i64 -> f64 conversion
+    fn cmp_int_float(i: i64, f: f64) -> Ordering {
+        if f.is_nan() || f >= i64::MAX as f64 {
+            Ordering::Less
+        } else if f < i64::MIN as f64 {
+            Ordering::Greater
+        } else {
+            let truncated = f.trunc();
+            i.cmp(&(truncated as i64))
+                .then_with(|| Self::cmp_floats(0.0, f - truncated))
+        }
+    }
+}
+
+impl PartialEq for Value<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp_sqlite(other) == Ordering::Equal
+    }
+}
+
+// cmp_sqlite is a total order: nans are equal to each other, unlike in f64's PartialEq
+impl Eq for Value<'_> {}
+
+// consistent with equality: ints and floats holding the same integer hash the same, text and blobs
+// with the same bytes don't as they're never equal
+impl Hash for Value<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.storage_class_rank().hash(state);
+        match self {
+            Value::Null => (),
+            Value::Int(i) => i.hash(state),
+            Value::Float(f)
+                if f.fract() == 0.0 && *f >= i64::MIN as f64 && *f < i64::MAX as f64 =>
+            {
+                (*f as i64).hash(state)
+            }
+            Value::Float(f) if f.is_nan() => f64::NAN.to_bits().hash(state),
+            Value::Float(f) => f.to_bits().hash(state),
+            Value::String(s) => s.as_bytes().hash(state),
+            Value::Blob(b) => b.hash(state),
+        }
+    }
+}
+
+impl PartialOrd for Value<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp_sqlite(other))
+    }
+}
+
+// text holding a decimal integer or real literal, optionally surrounded by spaces, reals which are
+// integers are turned into ints, and integers too large for an i64 into reals
+pub fn parse_numeric(text: &str) -> Option<Value<'static>> {
+    let text = text.trim();
+    // rust also parses inf and nan, which sqlite doesn't
+    let is_literal = text.chars().any(|c| c.is_ascii_digit())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'));
+    if !is_literal {
+        return None;
+    }
+    if let Ok(i) = text.parse::<i64>() {
+        return Some(Value::Int(i));
+    }
+    let f = text.parse::<f64>().ok()?;
+    // i64::MAX as f64 rounds up to 2^63 which doesn't fit
+    if f.fract() == 0.0 && f >= i64::MIN as f64 && f < i64::MAX as f64 {
+        Some(Value::Int(f as i64))
+    } else {
+        Some(Value::Float(f))
+    }
+}
+
+// null is rendered as an empty string like sqlite3 does in list mode
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Blob(b) => {
+                write!(f, "x'")?;
+                for byte in b.iter() {
+                    write!(f, "{:02x}", byte)?;
+                }
+                write!(f, "'")
+            }
+            Value::Int(i) => write!(f, "{}", i),
+            // spelled like sqlite3 prints them
+            Value::Float(n) if n.is_infinite() && n.is_sign_positive() => write!(f, "Inf"),
+            Value::Float(n) if n.is_infinite() => write!(f, "-Inf"),
+            // keep a trailing .0 so that floats can be told apart from ints
+            Value::Float(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{:.1}", n),
+            Value::Float(n) if n.abs() >= 1e15 => write!(f, "{:e}", n),
+            Value::Float(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+// a rust type a single value can be read as, see FromRow for whole records
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> anyhow::Result<Self>;
+}
+
+impl FromValue for i64 {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        match value {
+            Value::Int(i) => Ok(i),
+            value => mismatch("an integer", &value),
+        }
+    }
+}
+
+impl FromValue for i32 {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        let i = i64::from_value(value)?;
+        i32::try_from(i).map_err(|_| anyhow::anyhow!("integer {} doesn't fit in an i32", i))
+    }
+}
+
+// a column of numeric affinity can store a value either way, integers are read as reals like
+// sqlite3_column_double does
+impl FromValue for f64 {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        match value {
+            Value::Float(f) => Ok(f),
+            Value::Int(i) => Ok(i as f64),
+            value => mismatch("a real", &value),
+        }
+    }
+}
+
+// sqlite has no boolean, TRUE and FALSE are stored as 1 and 0
+impl FromValue for bool {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        match value {
+            Value::Int(0) => Ok(false),
+            Value::Int(1) => Ok(true),
+            Value::Int(i) => anyhow::bail!("expected 0 or 1, got {}", i),
+            value => mismatch("a boolean", &value),
+        }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        match value {
+            Value::String(s) => Ok(s.into_owned()),
+            value => mismatch("text", &value),
+        }
+    }
+}
+
+// text is read as its utf-8 bytes like sqlite3_column_blob does
+impl FromValue for Vec<u8> {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        match value {
+            Value::Blob(b) => Ok(b.into_owned()),
+            Value::String(s) => Ok(s.into_owned().into_bytes()),
+            value => mismatch("a blob", &value),
+        }
+    }
+}
+
+// null is the only value which isn't converted
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+fn mismatch<T>(expected: &str, value: &Value) -> anyhow::Result<T> {
+    let actual = match value {
+        Value::Null => "null",
+        Value::String(_) => "text",
+        Value::Blob(_) => "a blob",
+        Value::Int(_) => "an integer",
+        Value::Float(_) => "a real",
+    };
+    anyhow::bail!("expected {}, got {}", expected, actual)
+}
+
+// blobs are serialized as base64 strings
+#[cfg(feature = "serde")]
+impl serde::Serialize for Value<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_none(),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Blob(b) => serializer.serialize_str(&crate::utils::encode_base64(b)),
+            Value::Int(i) => serializer.serialize_i64(*i),
+            Value::Float(f) => serializer.serialize_f64(*f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn value_display_tests() {
+        assert_eq!("", Value::Null.to_string());
+        assert_eq!("abc", Value::String(Cow::from("abc")).to_string());
+        assert_eq!(
+            "x'deadbeef'",
+            Value::Blob(Cow::from(&[0xde, 0xad, 0xbe, 0xef][..])).to_string()
+        );
+        assert_eq!("x''", Value::Blob(Cow::from(&[][..])).to_string());
+        assert_eq!("-12", Value::Int(-12).to_string());
+        assert_eq!("1.0", Value::Float(1.0).to_string());
+        assert_eq!("-0.5", Value::Float(-0.5).to_string());
+        assert_eq!("1e20", Value::Float(1e20).to_string());
+        assert_eq!("Inf", Value::Float(f64::INFINITY).to_string());
+        assert_eq!("-Inf", Value::Float(f64::NEG_INFINITY).to_string());
+    }
+
+    #[test]
+    fn value_affinity_coerce_tests() {
+        let text = |s: &'static str| Value::String(Cow::from(s));
+        let coerce = |value: Value<'static>, affinity| value.affinity_coerce(affinity);
+
+        assert!(matches!(
+            coerce(text("12"), Affinity::Numeric),
+            Value::Int(12)
+        ));
+        assert!(matches!(
+            coerce(text(" -12 "), Affinity::Integer),
+            Value::Int(-12)
+        ));
+        assert!(matches!(
+            coerce(text("3.0"), Affinity::Numeric),
+            Value::Int(3)
+        ));
+        assert!(matches!(
+            coerce(text("1e2"), Affinity::Numeric),
+            Value::Int(100)
+        ));
+        assert!(matches!(coerce(text(".5"), Affinity::Numeric), Value::Float(f) if f == 0.5));
+        assert!(matches!(
+            coerce(text("9223372036854775808"), Affinity::Numeric),
+            Value::Float(f) if f == 9223372036854775808.0
+        ));
+        // not numbers
+        for s in ["abc", "0x10", "inf", "NaN", "1 2", "", "-", "."] {
+            assert_eq!(text(s), coerce(text(s), Affinity::Numeric), "{}", s);
+        }
+        assert_eq!(
+            Value::Float(2.5),
+            coerce(Value::Float(2.5), Affinity::Integer)
+        );
+
+        assert!(matches!(coerce(text("3"), Affinity::Real), Value::Float(f) if f == 3.0));
+        assert!(matches!(coerce(Value::Int(3), Affinity::Real), Value::Float(f) if f == 3.0));
+        assert_eq!(text("abc"), coerce(text("abc"), Affinity::Real));
+
+        assert_eq!(text("12"), coerce(Value::Int(12), Affinity::Text));
+        assert_eq!(text("2.5"), coerce(Value::Float(2.5), Affinity::Text));
+        assert_eq!(
+            Value::Blob(Cow::from(&[1][..])),
+            coerce(Value::Blob(Cow::from(&[1][..])), Affinity::Text)
+        );
+
+        assert_eq!(text("12"), coerce(text("12"), Affinity::Blob));
+        assert!(matches!(
+            coerce(Value::Int(12), Affinity::Blob),
+            Value::Int(12)
+        ));
+        for affinity in [
+            Affinity::Text,
+            Affinity::Numeric,
+            Affinity::Real,
+            Affinity::Blob,
+        ] {
+            assert_eq!(Value::Null, coerce(Value::Null, affinity));
+        }
+    }
+
+    #[test]
+    fn value_accessor_tests() {
+        let string = Value::String(Cow::from("a"));
+        let blob = Value::Blob(Cow::from(&b"a"[..]));
+        assert_eq!(Some("a"), string.as_str());
+        assert_eq!(None, blob.as_str());
+        assert_eq!(Some(&b"a"[..]), blob.as_blob());
+        assert_eq!(None, string.as_blob());
+        assert_eq!(Some(3), Value::Int(3).as_int());
+        assert_eq!(None, Value::Float(3.0).as_int());
+        assert_eq!(Some(3.5), Value::Float(3.5).as_float());
+        assert_eq!(None, Value::Int(3).as_float());
+        assert_eq!(Some(3.0), Value::Int(3).as_f64());
+        assert_eq!(Some(3.5), Value::Float(3.5).as_f64());
+        assert_eq!(None, Value::Null.as_f64());
+        assert_eq!(None, string.as_f64());
+    }
+
+    #[test]
+    fn value_into_owned_tests() {
+        let text = String::from("abc");
+        let owned = Value::String(Cow::from(text.as_str())).into_owned();
+        drop(text);
+        assert!(matches!(owned, Value::String(Cow::Owned(_))));
+        assert_eq!(Value::String(Cow::from("abc")), owned);
+        assert!(matches!(
+            Value::Blob(Cow::from(&[1, 2][..])).into_owned(),
+            Value::Blob(Cow::Owned(b)) if b == [1, 2]
+        ));
+        assert_eq!(Value::Int(3), Value::Int(3).into_owned());
+    }
+
+    #[test]
+    fn value_to_sql_literal_tests() {
+        assert_eq!("NULL", Value::Null.to_sql_literal());
+        assert_eq!("'abc'", Value::String(Cow::from("abc")).to_sql_literal());
+        assert_eq!("'it''s'", Value::String(Cow::from("it's")).to_sql_literal());
+        assert_eq!("''", Value::String(Cow::from("")).to_sql_literal());
+        assert_eq!("x''", Value::Blob(Cow::from(&[][..])).to_sql_literal());
+        assert_eq!(
+            "x'01ff'",
+            Value::Blob(Cow::from(&[1, 255][..])).to_sql_literal()
+        );
+        assert_eq!("-3", Value::Int(-3).to_sql_literal());
+        assert_eq!("2.0", Value::Float(2.0).to_sql_literal());
+        assert_eq!("0.1", Value::Float(0.1).to_sql_literal());
+        assert_eq!("1e999", Value::Float(f64::INFINITY).to_sql_literal());
+        assert_eq!("-1e999", Value::Float(f64::NEG_INFINITY).to_sql_literal());
+        assert_eq!("NULL", Value::Float(f64::NAN).to_sql_literal());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn value_serialize_tests() {
+        let to_json = |v: Value| serde_json::to_string(&v).unwrap();
+        assert_eq!("null", to_json(Value::Null));
+        assert_eq!("\"abc\"", to_json(Value::String(Cow::from("abc"))));
+        assert_eq!(
+            "\"3q2+7w==\"",
+            to_json(Value::Blob(Cow::from(&[0xde, 0xad, 0xbe, 0xef][..])))
+        );
+        assert_eq!("-3", to_json(Value::Int(-3)));
+        assert_eq!("1.5", to_json(Value::Float(1.5)));
+    }
+
+    #[test]
+    fn value_cmp_sqlite_tests() {
+        let null = Value::Null;
+        let int = Value::Int(5);
+        let float = Value::Float(5.0);
+        let string = Value::String(Cow::from("a"));
+        let blob = Value::Blob(Cow::from(&b"a"[..]));
+
+        assert_eq!(Ordering::Equal, int.cmp_sqlite(&float));
+        assert_eq!(Ordering::Equal, float.cmp_sqlite(&int));
+        assert_eq!(int, float);
+        assert!(Value::Int(5) < Value::Float(5.5));
+        assert!(Value::Float(4.5) < Value::Int(5));
+        assert!(Value::Int(-5) < Value::Float(-4.5));
+        assert!(Value::Int(i64::MAX) < Value::Float(1e19));
+        assert!(Value::Int(i64::MIN) > Value::Float(-1e19));
+        assert!(Value::Int(i64::MAX) < Value::Float(f64::NAN));
+        assert_eq!(Value::Float(0.0), Value::Float(-0.0));
+        assert_eq!(Value::Float(f64::NAN), Value::Float(f64::NAN));
+        assert_eq!(Value::Float(f64::NAN), Value::Float(-f64::NAN));
+        assert!(Value::Float(-f64::NAN) > Value::Float(f64::INFINITY));
+
+        assert!(null < int);
+        assert!(null < float);
+        assert!(float < string);
+        assert!(Value::Int(i64::MAX) < string);
+        assert!(string < blob);
+        assert!(null < blob);
+        assert_eq!(Value::Null, Value::Null);
+
+        assert!(Value::String(Cow::from("B")) < Value::String(Cow::from("a")));
+        assert!(Value::String(Cow::from("a")) < Value::String(Cow::from("ab")));
+        assert!(Value::Blob(Cow::from(&[1][..])) < Value::Blob(Cow::from(&[1, 0][..])));
+        assert!(Value::Blob(Cow::from(&[2][..])) > Value::Blob(Cow::from(&[1, 255][..])));
+    }
+
+    #[test]
+    fn value_hash_tests() {
+        let distinct = |values: Vec<Value>| values.into_iter().collect::<HashSet<_>>().len();
+        assert_eq!(
+            1,
+            distinct(vec![Value::Int(5), Value::Float(5.0), Value::Int(5)])
+        );
+        assert_eq!(1, distinct(vec![Value::Float(0.0), Value::Float(-0.0)]));
+        assert_eq!(
+            1,
+            distinct(vec![Value::Int(i64::MIN), Value::Float(i64::MIN as f64)])
+        );
+        assert_eq!(
+            2,
+            distinct(vec![Value::Int(i64::MAX), Value::Float(i64::MAX as f64)])
+        );
+        assert_eq!(
+            1,
+            distinct(vec![
+                Value::Float(f64::NAN),
+                Value::Float(-f64::NAN),
+                Value::Float(f64::from_bits(f64::NAN.to_bits() | 1))
+            ])
+        );
+        assert_eq!(2, distinct(vec![Value::Float(0.5), Value::Float(1.5)]));
+        assert_eq!(
+            2,
+            distinct(vec![
+                Value::String(Cow::from("a")),
+                Value::Blob(Cow::from(&b"a"[..]))
+            ])
+        );
+        assert_eq!(
+            1,
+            distinct(vec![
+                Value::String(Cow::from("a")),
+                Value::String(Cow::Owned("a".to_string()))
+            ])
+        );
+        assert_eq!(2, distinct(vec![Value::Null, Value::Int(0)]));
+
+        // equal values hash the same
+        let hash = |v: &Value| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            v.hash(&mut hasher);
+            hasher.finish()
+        };
+        for (a, b) in [
+            (Value::Int(-7), Value::Float(-7.0)),
+            (Value::Float(0.0), Value::Float(-0.0)),
+            (Value::Float(f64::NAN), Value::Float(-f64::NAN)),
+            (
+                Value::Blob(Cow::from(&[1, 2][..])),
+                Value::Blob(Cow::Owned(vec![1, 2])),
+            ),
+        ] {
+            assert_eq!(a, b);
+            assert_eq!(hash(&a), hash(&b), "{:?} {:?}", a, b);
+        }
+    }
+
+    fn mismatch_message<T: FromValue>(value: Value) -> String {
+        T::from_value(value).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn from_value_i64_tests() {
+        assert_eq!(3, i64::from_value(Value::Int(3)).unwrap());
+        assert_eq!(
+            "expected an integer, got text",
+            mismatch_message::<i64>(Value::String(Cow::from("3")))
+        );
+        assert_eq!(
+            "expected an integer, got a real",
+            mismatch_message::<i64>(Value::Float(3.0))
+        );
+    }
+
+    #[test]
+    fn from_value_i32_tests() {
+        assert_eq!(-3, i32::from_value(Value::Int(-3)).unwrap());
+        assert_eq!(
+            "integer 3000000000 doesn't fit in an i32",
+            mismatch_message::<i32>(Value::Int(3_000_000_000))
+        );
+        assert_eq!(
+            "expected an integer, got null",
+            mismatch_message::<i32>(Value::Null)
+        );
+    }
+
+    #[test]
+    fn from_value_f64_tests() {
+        assert_eq!(2.5, f64::from_value(Value::Float(2.5)).unwrap());
+        assert_eq!(3.0, f64::from_value(Value::Int(3)).unwrap());
+        assert_eq!(
+            "expected a real, got a blob",
+            mismatch_message::<f64>(Value::Blob(Cow::from(&[1][..])))
+        );
+    }
+
+    #[test]
+    fn from_value_string_tests() {
+        assert_eq!(
+            "abc",
+            String::from_value(Value::String(Cow::from("abc"))).unwrap()
+        );
+        assert_eq!(
+            "expected text, got an integer",
+            mismatch_message::<String>(Value::Int(1))
+        );
+    }
+
+    #[test]
+    fn from_value_blob_tests() {
+        assert_eq!(
+            vec![0xde, 0xad],
+            Vec::<u8>::from_value(Value::Blob(Cow::from(&[0xde, 0xad][..]))).unwrap()
+        );
+        assert_eq!(
+            b"abc".to_vec(),
+            Vec::<u8>::from_value(Value::String(Cow::from("abc"))).unwrap()
+        );
+        assert_eq!(
+            "expected a blob, got a real",
+            mismatch_message::<Vec<u8>>(Value::Float(1.5))
+        );
+    }
+
+    #[test]
+    fn from_value_bool_tests() {
+        assert!(bool::from_value(Value::Int(1)).unwrap());
+        assert!(!bool::from_value(Value::Int(0)).unwrap());
+        assert_eq!(
+            "expected 0 or 1, got 2",
+            mismatch_message::<bool>(Value::Int(2))
+        );
+        assert_eq!(
+            "expected a boolean, got text",
+            mismatch_message::<bool>(Value::String(Cow::from("true")))
+        );
+    }
+
+    #[test]
+    fn from_value_option_tests() {
+        assert_eq!(None, Option::<i64>::from_value(Value::Null).unwrap());
+        assert_eq!(Some(3), Option::<i64>::from_value(Value::Int(3)).unwrap());
+        assert_eq!(Some(3.0), Option::<f64>::from_value(Value::Int(3)).unwrap());
+        assert_eq!(
+            "expected an integer, got text",
+            mismatch_message::<Option<i64>>(Value::String(Cow::from("3")))
+        );
+    }
 }